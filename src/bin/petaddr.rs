@@ -0,0 +1,473 @@
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, Parser, Subcommand};
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signer;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use PetAddr::{
+    config::AppConfig,
+    open_storage,
+    pet::{
+        export_key, import_key, Claimant, Estimate, ExportedKey, KeyFormat, MnemonicSearch, PetAddress, PetAddressInfo, PetStorage,
+        SearchEngine, SearchHandle, VanityPattern,
+    },
+    utils::load_env,
+};
+
+/// How often `grind` reports its progress on stderr
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Parser)]
+#[command(name = "petaddr", version, about = "Offline vanity address generation and pool maintenance")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Search for addresses matching a pattern and print or save their keys
+    Grind {
+        #[command(flatten)]
+        pattern: PatternArgs,
+        /// Number of addresses to find
+        #[arg(short = 'n', long, default_value_t = 1)]
+        count: usize,
+        /// Derive keypairs from random BIP39 mnemonics (much slower)
+        #[arg(long)]
+        mnemonic: bool,
+        /// Search threads, 0 means one per CPU
+        #[arg(long, default_value_t = 0)]
+        threads: usize,
+        /// Write each keypair to <dir>/<address>.json in solana-keygen format instead of printing it
+        #[arg(long)]
+        out_dir: Option<PathBuf>,
+        /// Encoding of printed secret keys: base58, solana-json, hex or base64
+        #[arg(long, default_value = "base58")]
+        format: KeyFormat,
+    },
+    /// Inspect and repair the address pools in the database (server must be stopped)
+    Pool {
+        /// Database to open instead of `pet_generator.db_path` from config.toml
+        #[arg(long, global = true)]
+        db: Option<PathBuf>,
+        #[command(subcommand)]
+        command: PoolCommand,
+    },
+    /// Expected attempts and search time for a pattern
+    Estimate {
+        #[command(flatten)]
+        pattern: PatternArgs,
+        /// Estimate for a mnemonic pool instead of plain keypairs
+        #[arg(long)]
+        mnemonic: bool,
+        /// Search threads to benchmark, 0 means one per CPU
+        #[arg(long, default_value_t = 0)]
+        threads: usize,
+        /// Seconds to measure the hash rate for; 0 skips the time estimate
+        #[arg(long, default_value_t = 2)]
+        benchmark_seconds: u64,
+    },
+    /// Check that a keypair file is valid and belongs to the expected address
+    Verify {
+        /// Keypair file: a solana-keygen JSON array, or base58, hex or base64 text
+        file: PathBuf,
+        /// Expected address; taken from the file name (<address>.json) when omitted
+        #[arg(long)]
+        pubkey: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum PoolCommand {
+    /// Addresses waiting in each configured pool, or in one pool
+    Count {
+        #[arg(long)]
+        pool: Option<String>,
+    },
+    /// Ids and public keys of the addresses in a pool
+    ListPublic {
+        /// Pool to list, the default pool when omitted
+        #[arg(long)]
+        pool: Option<String>,
+    },
+    /// Take addresses out of a pool and print their keys
+    Claim {
+        /// Pool to claim from, the default pool when omitted
+        #[arg(long)]
+        pool: Option<String>,
+        #[arg(short = 'n', long, default_value_t = 1)]
+        count: usize,
+        /// Claim what is available when the pool holds fewer than `count`
+        #[arg(long)]
+        allow_partial: bool,
+        /// Encoding of the secret keys: base58, solana-json, hex or base64
+        #[arg(long, default_value = "base58")]
+        format: KeyFormat,
+    },
+    /// Remove every unclaimed address from a pool
+    Clear {
+        /// Pool to clear, the default pool when omitted
+        #[arg(long)]
+        pool: Option<String>,
+        /// Name of the pool again, to confirm
+        #[arg(long)]
+        confirm: String,
+    },
+}
+
+#[derive(Args)]
+struct PatternArgs {
+    #[arg(long)]
+    prefix: Option<String>,
+    #[arg(long)]
+    suffix: Option<String>,
+    #[arg(long)]
+    case_insensitive: bool,
+}
+
+impl PatternArgs {
+    fn pattern(&self) -> Result<VanityPattern> {
+        VanityPattern::new(self.prefix.clone(), self.suffix.clone(), self.case_insensitive)
+    }
+}
+
+/// One key printed by `grind` or `pool claim`
+#[derive(Serialize)]
+struct KeyLine<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
+    public_key: &'a str,
+    private_key: ExportedKey,
+    key_format: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mnemonic: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    derivation_path: Option<&'a str>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Grind { pattern, count, mnemonic, threads, out_dir, format } => {
+            grind(pattern.pattern()?, count, mnemonic, threads, out_dir.as_deref(), format).await
+        }
+        Command::Pool { db, command } => run_pool_command(db.as_deref(), command),
+        Command::Estimate { pattern, mnemonic, threads, benchmark_seconds } => {
+            estimate(pattern.pattern()?, mnemonic, threads, benchmark_seconds).await
+        }
+        Command::Verify { file, pubkey } => verify(&file, pubkey.as_deref()),
+    }
+}
+
+async fn grind(
+    pattern: VanityPattern,
+    count: usize,
+    mnemonic: bool,
+    threads: usize,
+    out_dir: Option<&Path>,
+    format: KeyFormat,
+) -> Result<()> {
+    if count == 0 {
+        bail!("--count must be at least 1");
+    }
+    if let Some(dir) = out_dir {
+        std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+
+    let engine = SearchEngine::new(threads, 0);
+    let mut search = submit(&engine, pattern.clone(), mnemonic, count);
+    eprintln!(
+        "Searching for {} address(es) matching {} on {} threads, about {:.0} attempts each",
+        count,
+        pattern,
+        engine.worker_count(),
+        pattern.difficulty()
+    );
+
+    let started = Instant::now();
+    let mut progress = tokio::time::interval(PROGRESS_INTERVAL);
+    progress.tick().await;
+    let mut found = 0;
+    while found < count {
+        tokio::select! {
+            address = search.recv() => {
+                let Some(address) = address else {
+                    break;
+                };
+                match out_dir {
+                    Some(dir) => println!("{}", write_keypair_file(dir, &address)?.display()),
+                    None => print_key(None, &address, format)?,
+                }
+                found += 1;
+            }
+            _ = progress.tick() => {
+                let attempts = search.attempts();
+                eprintln!(
+                    "{} of {} found, {} attempts, {:.0} keys/s",
+                    found,
+                    count,
+                    attempts,
+                    attempts as f64 / started.elapsed().as_secs_f64()
+                );
+            }
+        }
+    }
+
+    eprintln!("Found {} address(es) in {} attempts, {:.1}s", found, search.attempts(), started.elapsed().as_secs_f64());
+    engine.shutdown();
+    Ok(())
+}
+
+async fn estimate(pattern: VanityPattern, mnemonic: bool, threads: usize, benchmark_seconds: u64) -> Result<()> {
+    let estimate = Estimate::new(&pattern, None);
+
+    println!("Pattern:     {}", pattern);
+    println!("Difficulty:  {:.0} attempts on average", estimate.difficulty);
+    for percentile in &estimate.percentiles {
+        println!("{:>3.0}% chance within {:.0} attempts", percentile.probability * 100.0, percentile.attempts);
+    }
+
+    if benchmark_seconds == 0 {
+        return Ok(());
+    }
+
+    let engine = SearchEngine::new(threads, 0);
+    // Matches found while measuring are thrown away, only the attempt count matters
+    let search = submit(&engine, pattern.clone(), mnemonic, u32::MAX as usize);
+    let started = Instant::now();
+    tokio::time::sleep(Duration::from_secs(benchmark_seconds)).await;
+    // Attempts are counted per chunk, and a chunk of mnemonics can outlast a short benchmark
+    while search.attempts() == 0 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let rate = search.attempts() as f64 / started.elapsed().as_secs_f64();
+    search.cancel();
+    engine.shutdown();
+
+    let estimate = Estimate::new(&pattern, Some(rate));
+    println!(
+        "Hash rate:   {:.0} {}/s on {} threads",
+        rate,
+        if mnemonic { "mnemonics" } else { "keys" },
+        engine.worker_count()
+    );
+    println!("Expected:    {}", human_duration(estimate.difficulty / rate));
+    for percentile in &estimate.percentiles {
+        println!(
+            "{:>3.0}% chance within {}",
+            percentile.probability * 100.0,
+            human_duration(percentile.attempts / rate)
+        );
+    }
+    Ok(())
+}
+
+fn verify(file: &Path, expected: Option<&str>) -> Result<()> {
+    let contents = std::fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?;
+    let contents = contents.trim();
+
+    let (keypair, format) = if contents.starts_with('[') {
+        let bytes: Vec<u8> = serde_json::from_str(contents).context("Keypair file is not a JSON byte array")?;
+        if bytes.len() != 64 {
+            bail!("Keypair file holds {} bytes, expected 64", bytes.len());
+        }
+        (import_key(&ExportedKey::Bytes(bytes), KeyFormat::SolanaJson)?, KeyFormat::SolanaJson)
+    } else {
+        let key = ExportedKey::Text(contents.to_string());
+        [KeyFormat::Base58, KeyFormat::Hex, KeyFormat::Base64]
+            .into_iter()
+            .find_map(|format| import_key(&key, format).ok().map(|keypair| (keypair, format)))
+            .ok_or_else(|| anyhow!("{} does not hold a valid keypair", file.display()))?
+    };
+
+    let actual = keypair.pubkey().to_string();
+    let expected = expected.map(str::to_string).or_else(|| {
+        let stem = file.file_stem()?.to_str()?;
+        stem.parse::<Pubkey>().is_ok().then(|| stem.to_string())
+    });
+    if let Some(expected) = expected {
+        if expected != actual {
+            bail!("{} holds the keypair of {}, not {}", file.display(), actual, expected);
+        }
+    }
+
+    println!("OK {} ({})", actual, format);
+    Ok(())
+}
+
+fn run_pool_command(db: Option<&Path>, command: PoolCommand) -> Result<()> {
+    load_env()?;
+    let mut config = AppConfig::load().map_err(|e| anyhow!("Failed to load configuration: {}", e))?;
+    if let Some(db) = db {
+        config.pet_generator.db_path = db.display().to_string();
+    }
+    let storage = open_storage(&config)
+        .with_context(|| format!("Failed to open {}, is the server still running?", config.pet_generator.db_path))?;
+
+    let pools: Vec<String> = config.pet_generator.pools().into_iter().map(|pool| pool.name).collect();
+    let pool_or_default = |pool: Option<String>| configured_pool(&pools, pool);
+
+    match command {
+        PoolCommand::Count { pool } => {
+            let selected = match pool {
+                Some(pool) => vec![configured_pool(&pools, Some(pool))?],
+                None => pools.clone(),
+            };
+            for pool in selected {
+                println!("{}\t{}", pool, storage.count_addresses(&pool)?);
+            }
+        }
+        PoolCommand::ListPublic { pool } => {
+            for (id, public_key) in storage.list_public_keys(&pool_or_default(pool)?)? {
+                println!("{}\t{}", id, public_key);
+            }
+        }
+        PoolCommand::Claim { pool, count, allow_partial, format } => {
+            let pool = pool_or_default(pool)?;
+            let claimed = claim(&storage, &pool, count, allow_partial)?;
+            for address_info in &claimed {
+                print_key(Some(address_info.id), &address_info.address, format)?;
+            }
+            eprintln!("Claimed {} address(es) from pool '{}'", claimed.len(), pool);
+        }
+        PoolCommand::Clear { pool, confirm } => {
+            let pool = pool_or_default(pool)?;
+            if confirm != pool {
+                bail!("--confirm must repeat the pool name '{}'", pool);
+            }
+            let removed = storage.clear_all_addresses(&pool)?;
+            println!("Removed {} address(es) from pool '{}'", removed, pool);
+        }
+    }
+
+    storage.flush()?;
+    Ok(())
+}
+
+/// `pool` if it is configured, the first configured pool when none is given. A typo would
+/// otherwise read an empty pool, or claim from one no server serves.
+fn configured_pool(pools: &[String], pool: Option<String>) -> Result<String> {
+    match pool {
+        None => Ok(pools[0].clone()),
+        Some(pool) if pools.contains(&pool) => Ok(pool),
+        Some(pool) => bail!("Pool '{}' is not configured; configured pools: {}", pool, pools.join(", ")),
+    }
+}
+
+fn claim(storage: &PetStorage, pool: &str, count: usize, allow_partial: bool) -> Result<Vec<PetAddressInfo>> {
+    if count == 0 {
+        bail!("--count must be at least 1");
+    }
+    let claimant = Claimant {
+        request_id: Some("petaddr-cli".to_string()),
+        ..Claimant::default()
+    };
+    let claimed = storage.claim_addresses(pool, count, &claimant, allow_partial)?;
+    if claimed.is_empty() {
+        if allow_partial {
+            bail!("Pool '{}' is empty", pool);
+        }
+        bail!("Pool '{}' has fewer than {} addresses", pool, count);
+    }
+    Ok(claimed)
+}
+
+fn submit(engine: &SearchEngine, pattern: VanityPattern, mnemonic: bool, count: usize) -> SearchHandle {
+    if mnemonic {
+        engine.submit_search(MnemonicSearch { pattern }, count)
+    } else {
+        engine.submit(pattern, count)
+    }
+}
+
+fn print_key(id: Option<u64>, address: &PetAddress, format: KeyFormat) -> Result<()> {
+    let line = KeyLine {
+        id,
+        public_key: &address.public_key,
+        private_key: export_key(address, format)?,
+        key_format: format.as_str(),
+        mnemonic: address.mnemonic.as_deref(),
+        derivation_path: address.derivation_path.as_deref(),
+    };
+    let mut stdout = std::io::stdout().lock();
+    serde_json::to_writer(&mut stdout, &line)?;
+    writeln!(stdout)?;
+    Ok(())
+}
+
+/// Write `<address>.json` like `solana-keygen`, and `<address>.mnemonic` for mnemonic keys
+fn write_keypair_file(dir: &Path, address: &PetAddress) -> Result<PathBuf> {
+    let path = dir.join(format!("{}.json", address.public_key));
+    let ExportedKey::Bytes(bytes) = export_key(address, KeyFormat::SolanaJson)? else {
+        unreachable!("solana-json keys are byte arrays");
+    };
+    write_secret_file(&path, &serde_json::to_vec(&bytes)?)?;
+
+    if let Some(mnemonic) = &address.mnemonic {
+        let phrase_path = dir.join(format!("{}.mnemonic", address.public_key));
+        write_secret_file(&phrase_path, format!("{}\n", mnemonic).as_bytes())?;
+    }
+    Ok(path)
+}
+
+/// Create a file readable by the owner only; never overwrites
+fn write_secret_file(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    file.write_all(contents)?;
+    Ok(())
+}
+
+/// Format `seconds` as a short readable duration, such as `42.0s`, `3m 5s` or `2d 4h`
+fn human_duration(seconds: f64) -> String {
+    if seconds < 60.0 {
+        return format!("{:.1}s", seconds);
+    }
+
+    let s = seconds.round() as u64;
+    match s {
+        0..3600 => format!("{}m {}s", s / 60, s % 60),
+        3600..86400 => format!("{}h {}m", s / 3600, s % 3600 / 60),
+        _ => format!("{}d {}h", s / 86400, s % 86400 / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn human_duration_picks_the_two_largest_units() {
+        assert_eq!(human_duration(0.0), "0.0s");
+        assert_eq!(human_duration(59.94), "59.9s");
+        assert_eq!(human_duration(60.0), "1m 0s");
+        assert_eq!(human_duration(3599.4), "59m 59s");
+        assert_eq!(human_duration(3600.0), "1h 0m");
+        assert_eq!(human_duration(86399.0), "23h 59m");
+        assert_eq!(human_duration(86400.0 * 3.0 + 3600.0 * 5.0), "3d 5h");
+    }
+
+    #[test]
+    fn pool_names_must_be_configured() {
+        let pools = vec!["pet".to_string(), "dog".to_string()];
+        assert_eq!(configured_pool(&pools, None).unwrap(), "pet");
+        assert_eq!(configured_pool(&pools, Some("dog".to_string())).unwrap(), "dog");
+
+        let error = configured_pool(&pools, Some("dgo".to_string())).unwrap_err().to_string();
+        assert!(error.contains("'dgo' is not configured") && error.contains("pet, dog"), "{}", error);
+    }
+}
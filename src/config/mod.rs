@@ -2,6 +2,8 @@ use anyhow::Result;
 use config::{Config, ConfigError, Environment, File};
 use serde::{Deserialize, Serialize};
 
+use crate::pet::{PoolKind, Scope};
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct AppConfig {
     pub server: ServerConfig,
@@ -10,6 +12,20 @@ pub struct AppConfig {
     pub swagger: SwaggerConfig,
     pub pet_generator: PetGeneratorConfig,
     pub rate_limit: RateLimitConfig,
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub leases: LeaseConfig,
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub jobs: JobsConfig,
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -42,14 +58,232 @@ pub struct SwaggerConfig {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct PetGeneratorConfig {
     pub pool_size: usize,
+    /// Refilling starts when fewer addresses are left, `pool_size` when not set
+    #[serde(default)]
+    pub low_watermark: Option<usize>,
     pub batch_size: usize,
     pub db_path: String,
+    #[serde(default)]
+    pub pattern: PatternConfig,
+    /// Number of OS threads searching for keys, 0 means one per CPU
+    #[serde(default)]
+    pub worker_threads: usize,
+    /// Niceness applied to the search threads (Linux only, 0 leaves it unchanged)
+    #[serde(default)]
+    pub nice: i32,
+    /// Named address pools; when empty a single "pet" pool is built from the fields above
+    #[serde(default)]
+    pub pools: Vec<PoolConfig>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PoolConfig {
+    pub name: String,
+    /// `keypair` (default) or `mnemonic` for addresses backed by a BIP39 seed phrase
+    #[serde(default)]
+    pub kind: PoolKind,
+    pub pool_size: usize,
+    #[serde(default)]
+    pub low_watermark: Option<usize>,
+    pub batch_size: usize,
+    #[serde(default)]
+    pub pattern: PatternConfig,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PatternConfig {
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+    #[serde(default)]
+    pub case_insensitive: bool,
+}
+
+impl Default for PatternConfig {
+    fn default() -> Self {
+        Self {
+            prefix: None,
+            suffix: Some("Pet".to_string()),
+            case_insensitive: false,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct RateLimitConfig {
+    /// Limit for the claim endpoints, per client IP
     pub max_requests_per_minute: u32,
     pub window_seconds: u64,
+    /// Body message of rejected requests; `{limit}` and `{window}` are filled in
+    #[serde(default = "default_rate_limit_message")]
+    pub message: String,
+    /// Limit for job submissions (`POST /jobs`, `/split-key`), defaults to the claim limit
+    #[serde(default)]
+    pub jobs: Option<RateLimitRule>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct RateLimitRule {
+    pub max_requests: u32,
+    pub window_seconds: u64,
+}
+
+fn default_rate_limit_message() -> String {
+    "Rate limit exceeded. Maximum {limit} requests per {window} seconds allowed.".to_string()
+}
+
+impl RateLimitConfig {
+    pub fn claims(&self) -> RateLimitRule {
+        RateLimitRule {
+            max_requests: self.max_requests_per_minute,
+            window_seconds: self.window_seconds,
+        }
+    }
+
+    pub fn jobs(&self) -> RateLimitRule {
+        self.jobs.clone().unwrap_or_else(|| self.claims())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct EncryptionConfig {
+    pub enabled: bool,
+    /// File holding the hex or base64 encoded 32-byte master key
+    pub key_file: Option<String>,
+    /// Environment variable holding the master key, takes precedence over `key_file`
+    pub key_env: String,
+    /// Version recorded on every record sealed with the current master key
+    pub key_version: u32,
+}
+
+impl Default for EncryptionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key_file: None,
+            key_env: "PETADDR_MASTER_KEY".to_string(),
+            key_version: 1,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AuthConfig {
+    /// Route groups that accept requests without an API key; admin routes always need one
+    pub anonymous: Vec<Scope>,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            anonymous: vec![Scope::Status],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct LeaseConfig {
+    /// Lease duration when the request does not ask for one
+    pub default_ttl_seconds: u64,
+    /// Longest lease a client may ask for
+    pub max_ttl_seconds: u64,
+    /// How often expired leases are returned to their pools
+    pub reaper_interval_seconds: u64,
+}
+
+impl Default for LeaseConfig {
+    fn default() -> Self {
+        Self {
+            default_ttl_seconds: 300,
+            max_ttl_seconds: 3600,
+            reaper_interval_seconds: 30,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct IdempotencyConfig {
+    /// How long a claim made with an `Idempotency-Key` is replayed for retries
+    pub retention_seconds: u64,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self { retention_seconds: 86400 }
+    }
+}
+
+impl IdempotencyConfig {
+    pub fn retention(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.retention_seconds as i64)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct AuditConfig {
+    /// Days to keep claim audit entries; 0 keeps them forever
+    pub retention_days: u64,
+}
+
+impl AuditConfig {
+    pub fn retention(&self) -> Option<chrono::Duration> {
+        (self.retention_days > 0).then(|| chrono::Duration::days(self.retention_days as i64))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct JobsConfig {
+    /// Longest expected search a custom job may ask for at the measured hash rate; 0 disables the check
+    pub max_eta_seconds: u64,
+    /// Most expected attempts a custom job may ask for. Until a hash rate has been measured
+    /// this is the only limit, and with it disabled (0) no job is admitted before then.
+    pub max_difficulty: f64,
+}
+
+impl Default for JobsConfig {
+    fn default() -> Self {
+        Self {
+            max_eta_seconds: 3600,
+            max_difficulty: 1e9,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ShutdownConfig {
+    /// How long in-flight requests may take to finish after SIGINT/SIGTERM
+    pub drain_timeout_seconds: u64,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self { drain_timeout_seconds: 30 }
+    }
+}
+
+impl ShutdownConfig {
+    pub fn drain_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.drain_timeout_seconds)
+    }
+}
+
+impl PetGeneratorConfig {
+    pub const DEFAULT_POOL_NAME: &'static str = "pet";
+
+    /// Pools served by this instance, the first one being the default pool
+    pub fn pools(&self) -> Vec<PoolConfig> {
+        if self.pools.is_empty() {
+            vec![PoolConfig {
+                name: Self::DEFAULT_POOL_NAME.to_string(),
+                kind: PoolKind::Keypair,
+                pool_size: self.pool_size,
+                low_watermark: self.low_watermark,
+                batch_size: self.batch_size,
+                pattern: self.pattern.clone(),
+            }]
+        } else {
+            self.pools.clone()
+        }
+    }
 }
 
 impl AppConfig {
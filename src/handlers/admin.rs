@@ -0,0 +1,240 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+
+use crate::config::RateLimitRule;
+use crate::handlers::PetAppState;
+use crate::models::{
+    ApiKeyRateLimit, ApiKeyResponse, ApiResponse, AuditEntryResponse, AuditQuery, CreateApiKeyRequest,
+    CreatedApiKeyResponse,
+};
+use crate::pet::{ApiKey, AuditEntry, PetStorage, Scope};
+
+/// Audit entries returned when the query gives no limit
+const DEFAULT_AUDIT_LIMIT: usize = 100;
+/// Most audit entries returned by one query
+const MAX_AUDIT_LIMIT: usize = 1000;
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/api-keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 200, description = "API key created; the key is only returned this once", body = ApiResponse<CreatedApiKeyResponse>),
+        (status = 400, description = "Invalid scopes or rate limit", body = ApiResponse<String>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the admin scope", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Admin"
+)]
+pub async fn create_api_key(
+    State(app_state): State<Arc<PetAppState>>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<Json<ApiResponse<CreatedApiKeyResponse>>, StatusCode> {
+    let scopes = request.scopes.iter()
+        .map(|scope| scope.parse::<Scope>())
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let rate_limit = match request.rate_limit {
+        Some(limit) if limit.max_requests == 0 || limit.window_seconds == 0 => {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Some(limit) => Some(RateLimitRule {
+            max_requests: limit.max_requests,
+            window_seconds: limit.window_seconds,
+        }),
+        None => None,
+    };
+
+    let (key, api_key) = ApiKey::generate(
+        request.name,
+        scopes,
+        request.daily_quota,
+        request.monthly_quota,
+        rate_limit,
+    )
+    .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    if let Err(e) = app_state.storage.store_api_key(&api_key) {
+        tracing::error!("Failed to store API key: {}", e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    tracing::info!("Created API key {} ({})", api_key.id, api_key.name);
+
+    let api_key = api_key_response(&app_state.storage, api_key)?;
+    Ok(Json(ApiResponse::success(CreatedApiKeyResponse { key, api_key })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/api-keys",
+    responses(
+        (status = 200, description = "All API keys with their usage", body = ApiResponse<Vec<ApiKeyResponse>>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the admin scope", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Admin"
+)]
+pub async fn list_api_keys(
+    State(app_state): State<Arc<PetAppState>>,
+) -> Result<Json<ApiResponse<Vec<ApiKeyResponse>>>, StatusCode> {
+    let api_keys = app_state.storage.list_api_keys().map_err(|e| {
+        tracing::error!("Failed to list API keys: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let responses = api_keys.into_iter()
+        .map(|api_key| api_key_response(&app_state.storage, api_key))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Json(ApiResponse::success(responses)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/api-keys/{id}",
+    params(
+        ("id" = String, Path, description = "API key id")
+    ),
+    responses(
+        (status = 200, description = "API key revoked", body = ApiResponse<String>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the admin scope", body = ApiResponse<String>),
+        (status = 404, description = "Unknown API key", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Admin"
+)]
+pub async fn delete_api_key(
+    State(app_state): State<Arc<PetAppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<String>>, StatusCode> {
+    match app_state.storage.delete_api_key(&id) {
+        Ok(true) => {
+            app_state.auth.forget(&id);
+            tracing::info!("Revoked API key {}", id);
+            Ok(Json(ApiResponse::success(id)))
+        }
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to delete API key {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/audit",
+    params(
+        ("from" = Option<String>, Query, description = "Earliest claim time to include (RFC 3339)"),
+        ("to" = Option<String>, Query, description = "Latest claim time to include (RFC 3339)"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of entries, 100 by default and at most 1000")
+    ),
+    responses(
+        (status = 200, description = "Addresses handed out in the time range, oldest first", body = ApiResponse<Vec<AuditEntryResponse>>),
+        (status = 400, description = "Invalid time or limit", body = ApiResponse<String>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the admin scope", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Admin"
+)]
+pub async fn list_audit(
+    State(app_state): State<Arc<PetAppState>>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<ApiResponse<Vec<AuditEntryResponse>>>, StatusCode> {
+    let parse_time = |time: Option<&str>| {
+        time.map(|time| {
+            chrono::DateTime::parse_from_rfc3339(time)
+                .map(|time| time.with_timezone(&chrono::Utc))
+                .map_err(|_| StatusCode::BAD_REQUEST)
+        })
+        .transpose()
+    };
+    let from = parse_time(query.from.as_deref())?;
+    let to = parse_time(query.to.as_deref())?;
+
+    let limit = query.limit.unwrap_or(DEFAULT_AUDIT_LIMIT);
+    if limit == 0 || limit > MAX_AUDIT_LIMIT {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let entries = app_state.storage.list_audit(from, to, limit).map_err(|e| {
+        tracing::error!("Failed to list audit entries: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(ApiResponse::success(entries.into_iter().map(audit_entry_response).collect())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/audit/{public_key}",
+    params(
+        ("public_key" = String, Path, description = "Public key of the address")
+    ),
+    responses(
+        (status = 200, description = "Every time the address was handed out, oldest first", body = ApiResponse<Vec<AuditEntryResponse>>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the admin scope", body = ApiResponse<String>),
+        (status = 404, description = "The address was never handed out, or its entries are past retention", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Admin"
+)]
+pub async fn get_address_audit(
+    State(app_state): State<Arc<PetAppState>>,
+    Path(public_key): Path<String>,
+) -> Result<Json<ApiResponse<Vec<AuditEntryResponse>>>, StatusCode> {
+    let entries = app_state.storage.audit_for_public_key(&public_key).map_err(|e| {
+        tracing::error!("Failed to get audit entries of {}: {}", public_key, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if entries.is_empty() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(Json(ApiResponse::success(entries.into_iter().map(audit_entry_response).collect())))
+}
+
+fn audit_entry_response(entry: AuditEntry) -> AuditEntryResponse {
+    AuditEntryResponse {
+        id: entry.id,
+        kind: entry.kind.as_str().to_string(),
+        pool: entry.pool,
+        address_id: entry.address_id,
+        public_key: entry.public_key,
+        api_key: entry.api_key,
+        client_ip: entry.client_ip,
+        request_id: entry.request_id,
+        claimed_at: entry.claimed_at.to_rfc3339(),
+    }
+}
+
+fn api_key_response(storage: &PetStorage, api_key: ApiKey) -> Result<ApiKeyResponse, StatusCode> {
+    let (claimed_today, claimed_this_month) = storage.api_key_usage(&api_key.id).map_err(|e| {
+        tracing::error!("Failed to get usage of API key {}: {}", api_key.id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(ApiKeyResponse {
+        id: api_key.id,
+        name: api_key.name,
+        scopes: api_key.scopes.iter().map(|scope| scope.to_string()).collect(),
+        daily_quota: api_key.daily_quota,
+        monthly_quota: api_key.monthly_quota,
+        rate_limit: api_key.rate_limit.map(|rule| ApiKeyRateLimit {
+            max_requests: rule.max_requests,
+            window_seconds: rule.window_seconds,
+        }),
+        claimed_today,
+        claimed_this_month,
+        created_at: api_key.created_at.to_rfc3339(),
+    })
+}
@@ -0,0 +1,255 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use std::sync::Arc;
+
+use crate::handlers::{pool_status, PetAppState};
+use crate::models::{ApiResponse, ClearPoolRequest, ClearPoolResponse, PetGeneratorStatusResponse, UpdatePoolRequest};
+use crate::pet::{PetPool, PoolSettings};
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/generator/pause",
+    responses(
+        (status = 200, description = "Every pool paused; batches in flight are cancelled", body = ApiResponse<Vec<PetGeneratorStatusResponse>>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the admin scope", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Admin"
+)]
+pub async fn pause_generator(
+    State(app_state): State<Arc<PetAppState>>,
+) -> Result<Json<ApiResponse<Vec<PetGeneratorStatusResponse>>>, StatusCode> {
+    set_all_paused(&app_state, true).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/generator/resume",
+    responses(
+        (status = 200, description = "Every pool resumed", body = ApiResponse<Vec<PetGeneratorStatusResponse>>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the admin scope", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Admin"
+)]
+pub async fn resume_generator(
+    State(app_state): State<Arc<PetAppState>>,
+) -> Result<Json<ApiResponse<Vec<PetGeneratorStatusResponse>>>, StatusCode> {
+    set_all_paused(&app_state, false).await
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/admin/pools/{name}",
+    params(
+        ("name" = String, Path, description = "Address pool name", example = "pet")
+    ),
+    request_body = UpdatePoolRequest,
+    responses(
+        (status = 200, description = "Settings changed and persisted", body = ApiResponse<PetGeneratorStatusResponse>),
+        (status = 400, description = "Invalid batch size, or a low watermark above the pool size", body = ApiResponse<String>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the admin scope", body = ApiResponse<String>),
+        (status = 404, description = "Unknown pool", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Admin"
+)]
+pub async fn update_pool(
+    State(app_state): State<Arc<PetAppState>>,
+    Path(name): Path<String>,
+    Json(request): Json<UpdatePoolRequest>,
+) -> Result<Json<ApiResponse<PetGeneratorStatusResponse>>, StatusCode> {
+    let pool = app_state.generator.pool(&name).ok_or(StatusCode::NOT_FOUND)?;
+    let updated = update_settings(&app_state, pool, |settings| {
+        if let Some(pool_size) = request.pool_size {
+            settings.pool_size = pool_size;
+        }
+        if let Some(low_watermark) = request.low_watermark {
+            settings.low_watermark = low_watermark;
+        }
+        if let Some(batch_size) = request.batch_size {
+            settings.batch_size = batch_size;
+        }
+        settings.validate().is_ok()
+    })?;
+    if updated.is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    pool_status(&app_state, pool)
+        .await
+        .map(|status| Json(ApiResponse::success(status)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/pools/{name}/pause",
+    params(
+        ("name" = String, Path, description = "Address pool name", example = "pet")
+    ),
+    responses(
+        (status = 200, description = "Pool paused; its batch in flight is cancelled", body = ApiResponse<PetGeneratorStatusResponse>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the admin scope", body = ApiResponse<String>),
+        (status = 404, description = "Unknown pool", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Admin"
+)]
+pub async fn pause_pool(
+    State(app_state): State<Arc<PetAppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse<PetGeneratorStatusResponse>>, StatusCode> {
+    set_paused(&app_state, &name, true).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/pools/{name}/resume",
+    params(
+        ("name" = String, Path, description = "Address pool name", example = "pet")
+    ),
+    responses(
+        (status = 200, description = "Pool resumed", body = ApiResponse<PetGeneratorStatusResponse>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the admin scope", body = ApiResponse<String>),
+        (status = 404, description = "Unknown pool", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Admin"
+)]
+pub async fn resume_pool(
+    State(app_state): State<Arc<PetAppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse<PetGeneratorStatusResponse>>, StatusCode> {
+    set_paused(&app_state, &name, false).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/pools/{name}/refill",
+    params(
+        ("name" = String, Path, description = "Address pool name", example = "pet")
+    ),
+    responses(
+        (status = 200, description = "The pool is checked and topped up right away", body = ApiResponse<PetGeneratorStatusResponse>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the admin scope", body = ApiResponse<String>),
+        (status = 404, description = "Unknown pool", body = ApiResponse<String>),
+        (status = 409, description = "The pool is paused or the generator is stopped", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Admin"
+)]
+pub async fn refill_pool(
+    State(app_state): State<Arc<PetAppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse<PetGeneratorStatusResponse>>, StatusCode> {
+    let pool = app_state.generator.pool(&name).ok_or(StatusCode::NOT_FOUND)?;
+    if current_settings(&app_state, pool)?.paused || !app_state.generator.is_running().await {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    app_state.generator.refill(pool).map_err(|e| {
+        tracing::error!("Failed to refill pool '{}': {}", pool.name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    pool_status(&app_state, pool)
+        .await
+        .map(|status| Json(ApiResponse::success(status)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/pools/{name}/clear",
+    params(
+        ("name" = String, Path, description = "Address pool name", example = "pet")
+    ),
+    request_body = ClearPoolRequest,
+    responses(
+        (status = 200, description = "Unclaimed addresses deleted; the pool is refilled as usual", body = ApiResponse<ClearPoolResponse>),
+        (status = 400, description = "`confirm` does not match the pool name", body = ApiResponse<String>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the admin scope", body = ApiResponse<String>),
+        (status = 404, description = "Unknown pool", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Admin"
+)]
+pub async fn clear_pool(
+    State(app_state): State<Arc<PetAppState>>,
+    Path(name): Path<String>,
+    Json(request): Json<ClearPoolRequest>,
+) -> Result<Json<ApiResponse<ClearPoolResponse>>, StatusCode> {
+    let pool = app_state.generator.pool(&name).ok_or(StatusCode::NOT_FOUND)?;
+    if request.confirm != pool.name {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match app_state.generator.clear(pool) {
+        Ok(removed) => Ok(Json(ApiResponse::success(ClearPoolResponse {
+            pool: pool.name.clone(),
+            removed,
+        }))),
+        Err(e) => {
+            tracing::error!("Failed to clear pool '{}': {}", pool.name, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn set_paused(
+    app_state: &PetAppState,
+    name: &str,
+    paused: bool,
+) -> Result<Json<ApiResponse<PetGeneratorStatusResponse>>, StatusCode> {
+    let pool = app_state.generator.pool(name).ok_or(StatusCode::NOT_FOUND)?;
+    update_settings(app_state, pool, |settings| {
+        settings.paused = paused;
+        true
+    })?;
+
+    pool_status(app_state, pool)
+        .await
+        .map(|status| Json(ApiResponse::success(status)))
+}
+
+async fn set_all_paused(
+    app_state: &PetAppState,
+    paused: bool,
+) -> Result<Json<ApiResponse<Vec<PetGeneratorStatusResponse>>>, StatusCode> {
+    let mut statuses = Vec::with_capacity(app_state.generator.pools().len());
+    for pool in app_state.generator.pools() {
+        update_settings(app_state, pool, |settings| {
+            settings.paused = paused;
+            true
+        })?;
+        statuses.push(pool_status(app_state, pool).await?);
+    }
+
+    Ok(Json(ApiResponse::success(statuses)))
+}
+
+fn current_settings(app_state: &PetAppState, pool: &PetPool) -> Result<PoolSettings, StatusCode> {
+    app_state.generator.settings(pool).map_err(|e| {
+        tracing::error!("Failed to get settings of pool '{}': {}", pool.name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+fn update_settings(
+    app_state: &PetAppState,
+    pool: &PetPool,
+    update: impl FnOnce(&mut PoolSettings) -> bool,
+) -> Result<Option<PoolSettings>, StatusCode> {
+    app_state.generator.update_settings(pool, update).map_err(|e| {
+        tracing::error!("Failed to change settings of pool '{}': {}", pool.name, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
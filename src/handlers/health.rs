@@ -2,7 +2,7 @@ use axum::{response::Json, http::StatusCode};
 use crate::models::{ApiResponse, HealthResponse};
 
 static START_TIME: std::sync::LazyLock<chrono::DateTime<chrono::Utc>> = 
-    std::sync::LazyLock::new(|| chrono::Utc::now());
+    std::sync::LazyLock::new(chrono::Utc::now);
 
 /// Health check endpoint
 ///
@@ -0,0 +1,255 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Extension, Json,
+};
+use std::sync::Arc;
+
+use crate::handlers::PetAppState;
+use crate::middleware::AuthenticatedKey;
+use crate::models::{
+    ApiResponse, CreateJobRequest, EstimatePercentileResponse, EstimateQuery, EstimateResponse, JobResponse, JobResultResponse,
+    SplitKeyRequest, SplitKeyResponse,
+};
+use crate::pet::{Estimate, JobProgress, JobStatus, SplitKeySearch, VanityJob, VanityPattern};
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/jobs",
+    request_body = CreateJobRequest,
+    responses(
+        (status = 200, description = "Job submitted; poll it by id with the same API key", body = ApiResponse<JobResponse>),
+        (status = 400, description = "Invalid pattern", body = ApiResponse<String>),
+        (status = 422, description = "Pattern would take longer than the server allows; see /estimate", body = ApiResponse<String>),
+        (status = 429, description = "Rate limit exceeded", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Vanity Jobs"
+)]
+pub async fn create_job(
+    State(app_state): State<Arc<PetAppState>>,
+    api_key: Option<Extension<AuthenticatedKey>>,
+    Json(request): Json<CreateJobRequest>,
+) -> Result<Json<ApiResponse<JobResponse>>, StatusCode> {
+    let pattern = VanityPattern::new(request.prefix, request.suffix, request.case_insensitive)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let estimate = Estimate::new(&pattern, live_hash_rate(&app_state));
+    if !app_state.jobs.admits(&estimate) {
+        tracing::info!("Rejected vanity job for pattern {} (difficulty {:.0})", pattern, estimate.difficulty);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    match app_state.jobs.submit(pattern, requester(api_key.as_ref())) {
+        Ok(job) => {
+            let progress = JobProgress { job, rate: None, eta_seconds: None };
+            Ok(Json(ApiResponse::success(job_response(progress))))
+        }
+        Err(e) => {
+            tracing::error!("Failed to create vanity job: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/split-key",
+    request_body = SplitKeyRequest,
+    responses(
+        (status = 200, description = "Split-key job submitted; poll it by id for the partial scalar", body = ApiResponse<JobResponse>),
+        (status = 400, description = "Invalid public point or pattern", body = ApiResponse<String>),
+        (status = 422, description = "Pattern would take longer than the server allows; see /estimate", body = ApiResponse<String>),
+        (status = 429, description = "Rate limit exceeded", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Vanity Jobs"
+)]
+pub async fn search_split_key(
+    State(app_state): State<Arc<PetAppState>>,
+    api_key: Option<Extension<AuthenticatedKey>>,
+    Json(request): Json<SplitKeyRequest>,
+) -> Result<Json<ApiResponse<JobResponse>>, StatusCode> {
+    let pattern = VanityPattern::new(request.prefix, request.suffix, request.case_insensitive)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    SplitKeySearch::new(&request.public_point, pattern.clone())
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    let estimate = Estimate::new(&pattern, live_hash_rate(&app_state));
+    if !app_state.jobs.admits(&estimate) {
+        tracing::info!("Rejected split-key job for pattern {} (difficulty {:.0})", pattern, estimate.difficulty);
+        return Err(StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    match app_state.jobs.submit_split_key(&request.public_point, pattern, requester(api_key.as_ref())) {
+        Ok(job) => {
+            let progress = JobProgress { job, rate: None, eta_seconds: None };
+            Ok(Json(ApiResponse::success(job_response(progress))))
+        }
+        Err(e) => {
+            tracing::error!("Failed to create split-key job: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/jobs/{id}",
+    params(
+        ("id" = String, Path, description = "Job id")
+    ),
+    responses(
+        (status = 200, description = "Job progress, with the keypair or partial scalar once completed", body = ApiResponse<JobResponse>),
+        (status = 404, description = "Unknown job, or one submitted with another API key", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Vanity Jobs"
+)]
+pub async fn get_job(
+    State(app_state): State<Arc<PetAppState>>,
+    api_key: Option<Extension<AuthenticatedKey>>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<JobResponse>>, StatusCode> {
+    match app_state.jobs.get(&id) {
+        Ok(Some(progress)) if progress.job.is_visible_to(requester(api_key.as_ref())) => {
+            Ok(Json(ApiResponse::success(job_response(progress))))
+        }
+        Ok(_) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to get vanity job {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/jobs/{id}",
+    params(
+        ("id" = String, Path, description = "Job id")
+    ),
+    responses(
+        (status = 200, description = "Job cancelled", body = ApiResponse<JobResponse>),
+        (status = 404, description = "Unknown job, or one submitted with another API key", body = ApiResponse<String>),
+        (status = 409, description = "Job already finished", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Vanity Jobs"
+)]
+pub async fn cancel_job(
+    State(app_state): State<Arc<PetAppState>>,
+    api_key: Option<Extension<AuthenticatedKey>>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<JobResponse>>, StatusCode> {
+    owned_job(&app_state, api_key.as_ref(), &id)?;
+
+    match app_state.jobs.cancel(&id) {
+        Ok(Some(job)) if job.status == JobStatus::Cancelled => {
+            let progress = JobProgress { job, rate: None, eta_seconds: None };
+            Ok(Json(ApiResponse::success(job_response(progress))))
+        }
+        Ok(Some(_)) => Err(StatusCode::CONFLICT),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to cancel vanity job {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/estimate",
+    params(
+        ("prefix" = Option<String>, Query, description = "Start of the address"),
+        ("suffix" = Option<String>, Query, description = "End of the address"),
+        ("case_insensitive" = Option<bool>, Query, description = "Match letters in either case, false by default")
+    ),
+    responses(
+        (status = 200, description = "Expected attempts and search time for the pattern", body = ApiResponse<EstimateResponse>),
+        (status = 400, description = "Invalid pattern", body = ApiResponse<String>)
+    ),
+    tag = "Vanity Jobs"
+)]
+pub async fn estimate_pattern(
+    State(app_state): State<Arc<PetAppState>>,
+    Query(query): Query<EstimateQuery>,
+) -> Result<Json<ApiResponse<EstimateResponse>>, StatusCode> {
+    let pattern = VanityPattern::new(query.prefix, query.suffix, query.case_insensitive)
+        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let estimate = Estimate::new(&pattern, live_hash_rate(&app_state));
+
+    Ok(Json(ApiResponse::success(EstimateResponse {
+        pattern: pattern.to_string(),
+        difficulty: estimate.difficulty,
+        hash_rate: estimate.hash_rate,
+        eta_seconds: estimate.eta_seconds(),
+        percentiles: estimate
+            .percentiles
+            .iter()
+            .map(|percentile| EstimatePercentileResponse {
+                probability: percentile.probability,
+                attempts: percentile.attempts,
+                eta_seconds: estimate.seconds(percentile.attempts),
+            })
+            .collect(),
+        job_allowed: app_state.jobs.admits(&estimate),
+    })))
+}
+
+/// Id of the API key a request was made with
+fn requester(api_key: Option<&Extension<AuthenticatedKey>>) -> Option<&str> {
+    api_key.map(|Extension(AuthenticatedKey(key))| key.id.as_str())
+}
+
+/// Look up a job, hiding jobs submitted with a different API key
+fn owned_job(
+    app_state: &PetAppState,
+    api_key: Option<&Extension<AuthenticatedKey>>,
+    id: &str,
+) -> Result<VanityJob, StatusCode> {
+    app_state.storage.get_job(id)
+        .map_err(|e| {
+            tracing::error!("Failed to get vanity job {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .filter(|job| job.is_visible_to(requester(api_key)))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Keypair attempts per second a new job would get. Pool batches and jobs in flight share
+/// the search workers, so their rates add up and a new search takes an equal share.
+fn live_hash_rate(app_state: &PetAppState) -> Option<f64> {
+    let total = match app_state.jobs.hash_rate() {
+        Some(jobs) => jobs + app_state.generator.running_hash_rate().unwrap_or(0.0),
+        None => app_state.generator.hash_rate()?,
+    };
+    let running = app_state.jobs.running() + app_state.generator.running_batches();
+    Some(total / (running + 1) as f64)
+}
+
+fn job_response(progress: JobProgress) -> JobResponse {
+    let job = progress.job;
+    JobResponse {
+        id: job.id,
+        pattern: job.pattern.to_string(),
+        status: job.status.as_str().to_string(),
+        difficulty: job.difficulty,
+        attempts: job.attempts,
+        rate: progress.rate,
+        eta_seconds: progress.eta_seconds,
+        created_at: job.created_at.to_rfc3339(),
+        finished_at: job.finished_at.map(|t| t.to_rfc3339()),
+        result: job.result.map(|address| JobResultResponse {
+            public_key: address.public_key,
+            private_key: address.private_key,
+            address: address.address,
+        }),
+        public_point: job.public_point,
+        partial_key: job.partial_key.map(|partial| SplitKeyResponse {
+            address: partial.address,
+            partial_scalar: partial.partial_scalar,
+        }),
+    }
+}
@@ -0,0 +1,250 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Extension, Json,
+};
+use std::sync::Arc;
+
+use crate::handlers::pet::{
+    abandon_idempotent, address_payload, begin_idempotent, claimant, consume_claim_quota, key_delivery, quota_outcome,
+    refund_claim_quota, Idempotency,
+};
+use crate::handlers::PetAppState;
+use crate::metrics::ClaimOutcome;
+use crate::middleware::{AuthenticatedKey, RequestContext};
+use crate::models::{ApiResponse, ConfirmLeaseRequest, CreateLeaseRequest, LeaseResponse, LeaseStatusResponse};
+use crate::pet::{Lease, PetAddressInfo};
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/claims",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key return the same lease")
+    ),
+    request_body(content = Option<CreateLeaseRequest>, description = "Optional lease parameters"),
+    responses(
+        (status = 200, description = "Address reserved; confirm the lease to get its keypair", body = ApiResponse<LeaseResponse>),
+        (status = 400, description = "Invalid TTL or idempotency key, or an idempotency key without an API key", body = ApiResponse<String>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the claim scope", body = ApiResponse<String>),
+        (status = 404, description = "Unknown pool or no addresses available", body = ApiResponse<String>),
+        (status = 409, description = "A request with this idempotency key is still in progress", body = ApiResponse<String>),
+        (status = 410, description = "The claim or lease this idempotency key made is no longer held", body = ApiResponse<String>),
+        (status = 422, description = "Idempotency key was used with different parameters", body = ApiResponse<String>),
+        (status = 429, description = "Rate limit or claim quota exceeded", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Pet Address"
+)]
+pub async fn create_lease(
+    State(app_state): State<Arc<PetAppState>>,
+    api_key: Option<Extension<AuthenticatedKey>>,
+    context: Option<Extension<RequestContext>>,
+    headers: HeaderMap,
+    request: Option<Json<CreateLeaseRequest>>,
+) -> Result<Json<ApiResponse<LeaseResponse>>, StatusCode> {
+    let api_key = api_key.as_ref().map(|Extension(AuthenticatedKey(key))| key);
+    let Json(request) = request.unwrap_or_default();
+
+    let pool = match request.pool.as_deref() {
+        Some(name) => app_state.generator.pool(name).ok_or(StatusCode::NOT_FOUND)?,
+        None => app_state.generator.default_pool(),
+    };
+
+    let ttl = request.ttl_seconds.unwrap_or(app_state.leases.default_ttl_seconds);
+    if ttl == 0 || ttl > app_state.leases.max_ttl_seconds {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let fingerprint = format!("lease|{}|{}", pool.name, ttl);
+    let idempotency = begin_idempotent(&app_state, &headers, api_key, &fingerprint)?;
+    if let Idempotency::Replay(claim) = idempotency {
+        app_state.metrics.record_claim(&pool.name, ClaimOutcome::Replayed, 0);
+        let lease = claim.lease.ok_or_else(|| {
+            tracing::error!("Idempotent claim for a lease request has no lease");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        return Ok(lease_response(lease, claim.address));
+    }
+
+    let charged_at = match consume_claim_quota(&app_state, api_key, 1) {
+        Ok(charged_at) => charged_at,
+        Err(status) => {
+            app_state.metrics.record_claim(&pool.name, quota_outcome(status), 0);
+            abandon_idempotent(&app_state, &idempotency);
+            return Err(status);
+        }
+    };
+
+    let leased = app_state.storage.lease_next_address(
+        &pool.name,
+        &claimant(api_key, context.as_ref()),
+        chrono::Duration::seconds(ttl as i64),
+        charged_at,
+        idempotency.key(),
+    );
+    app_state.generator.notify_claim(pool);
+
+    // Nothing was handed out, so it does not count against the quota or use up the idempotency key
+    if let Ok(None) | Err(_) = &leased {
+        if let Some(api_key) = api_key {
+            refund_claim_quota(&app_state, api_key, 1, charged_at);
+        }
+        abandon_idempotent(&app_state, &idempotency);
+    }
+
+    match leased {
+        Ok(Some((lease, address_info))) => {
+            app_state.metrics.record_claim(&pool.name, ClaimOutcome::Claimed, 1);
+            Ok(lease_response(lease, address_info))
+        }
+        Ok(None) => {
+            app_state.metrics.record_claim(&pool.name, ClaimOutcome::Empty, 0);
+            Err(StatusCode::NOT_FOUND)
+        }
+        Err(e) => {
+            app_state.metrics.record_claim(&pool.name, ClaimOutcome::Error, 0);
+            tracing::error!("Failed to lease address from pool '{}': {}", pool.name, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/claims/{id}/confirm",
+    params(
+        ("id" = String, Path, description = "Lease id")
+    ),
+    request_body(content = Option<ConfirmLeaseRequest>, description = "Optional delivery of the keypair"),
+    responses(
+        (status = 200, description = "Lease confirmed, the address is claimed for good and its keypair returned", body = ApiResponse<LeaseStatusResponse>),
+        (status = 400, description = "Invalid recipient key or key format", body = ApiResponse<String>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 404, description = "Unknown lease", body = ApiResponse<String>),
+        (status = 410, description = "Lease expired and the address went back to the pool", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Pet Address"
+)]
+pub async fn confirm_lease(
+    State(app_state): State<Arc<PetAppState>>,
+    api_key: Option<Extension<AuthenticatedKey>>,
+    Path(id): Path<String>,
+    request: Option<Json<ConfirmLeaseRequest>>,
+) -> Result<Json<ApiResponse<LeaseStatusResponse>>, StatusCode> {
+    let Json(request) = request.unwrap_or_default();
+    let delivery = key_delivery(request.recipient_key.as_deref(), request.format.as_deref())?;
+
+    let (lease, address_info) = app_state.storage.get_leased_address(&id)
+        .map_err(|e| {
+            tracing::error!("Failed to get lease {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    check_owner(&lease, api_key.as_ref())?;
+
+    if lease.is_expired(chrono::Utc::now()) {
+        // Don't wait for the reaper, the client should know right away
+        if let Err(e) = app_state.storage.release_lease(&id) {
+            tracing::error!("Failed to release expired lease {}: {}", id, e);
+        }
+        return Err(StatusCode::GONE);
+    }
+
+    // Ready the keypair before the lease is gone, so a failure here leaves it confirmable
+    let address = address_payload(address_info, &delivery).map_err(|e| {
+        tracing::error!("Failed to seal leased address from pool '{}': {}", lease.pool, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    match app_state.storage.confirm_lease(&id) {
+        Ok(Some(_)) => {
+            let mut status = lease_status(lease, "confirmed");
+            status.address = Some(address);
+            Ok(Json(ApiResponse::success(status)))
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to confirm lease {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/claims/{id}/release",
+    params(
+        ("id" = String, Path, description = "Lease id")
+    ),
+    responses(
+        (status = 200, description = "Lease released, the address is back in its pool", body = ApiResponse<LeaseStatusResponse>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 404, description = "Unknown lease", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Pet Address"
+)]
+pub async fn release_lease(
+    State(app_state): State<Arc<PetAppState>>,
+    api_key: Option<Extension<AuthenticatedKey>>,
+    Path(id): Path<String>,
+) -> Result<Json<ApiResponse<LeaseStatusResponse>>, StatusCode> {
+    let lease = owned_lease(&app_state, api_key.as_ref(), &id)?;
+
+    match app_state.storage.release_lease(&id) {
+        Ok(Some(_)) => Ok(Json(ApiResponse::success(lease_status(lease, "released")))),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to release lease {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+fn lease_response(lease: Lease, address_info: PetAddressInfo) -> Json<ApiResponse<LeaseResponse>> {
+    Json(ApiResponse::success(LeaseResponse {
+        lease_id: lease.id,
+        pool: lease.pool,
+        expires_at: lease.expires_at.to_rfc3339(),
+        address_id: address_info.id,
+        public_key: address_info.address.public_key,
+        address: address_info.address.address,
+    }))
+}
+
+/// Look up a lease, hiding leases taken with a different API key
+fn owned_lease(
+    app_state: &PetAppState,
+    api_key: Option<&Extension<AuthenticatedKey>>,
+    id: &str,
+) -> Result<Lease, StatusCode> {
+    let lease = app_state.storage.get_lease(id)
+        .map_err(|e| {
+            tracing::error!("Failed to get lease {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    check_owner(&lease, api_key)?;
+
+    Ok(lease)
+}
+
+fn check_owner(lease: &Lease, api_key: Option<&Extension<AuthenticatedKey>>) -> Result<(), StatusCode> {
+    let requester = api_key.map(|Extension(AuthenticatedKey(key))| key.id.as_str());
+    if lease.api_key.is_some() && lease.api_key.as_deref() != requester {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(())
+}
+
+fn lease_status(lease: Lease, status: &str) -> LeaseStatusResponse {
+    LeaseStatusResponse {
+        lease_id: lease.id,
+        pool: lease.pool,
+        address_id: lease.address_id,
+        status: status.to_string(),
+        address: None,
+    }
+}
@@ -0,0 +1,52 @@
+use axum::{
+    extract::State,
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use crate::handlers::PetAppState;
+use crate::models::ApiResponse;
+
+/// Content type of the Prometheus text exposition format
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text format", body = String, content_type = "text/plain"),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the status scope", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error")
+    ),
+    tag = "Health Check"
+)]
+pub async fn get_metrics(State(app_state): State<Arc<PetAppState>>) -> Result<Response, StatusCode> {
+    let metrics = &app_state.metrics;
+
+    // Gauges that are cheaper to read on scrape than to keep up to date
+    for pool in app_state.generator.pools() {
+        match app_state.storage.count_addresses(&pool.name) {
+            Ok(count) => metrics.pool_depth.with_label_values(&[&pool.name]).set(count as i64),
+            Err(e) => tracing::error!("Failed to count addresses of pool '{}': {}", pool.name, e),
+        }
+        match app_state.generator.settings(pool) {
+            Ok(settings) => metrics.pool_target.with_label_values(&[&pool.name]).set(settings.pool_size as i64),
+            Err(e) => tracing::error!("Failed to get settings of pool '{}': {}", pool.name, e),
+        }
+    }
+    match app_state.storage.size_on_disk() {
+        Ok(size) => metrics.db_size.set(size as i64),
+        Err(e) => tracing::error!("Failed to get database size: {}", e),
+    }
+
+    let body = metrics.render().map_err(|e| {
+        tracing::error!("Failed to render metrics: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let mut response = body.into_response();
+    response.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(PROMETHEUS_CONTENT_TYPE));
+    Ok(response)
+}
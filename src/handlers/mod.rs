@@ -1,7 +1,17 @@
 pub mod health;
 pub mod time;
 pub mod pet;
+pub mod job;
+pub mod admin;
+pub mod generator;
+pub mod lease;
+pub mod metrics;
 
 pub use health::*;
 pub use time::*;
-pub use pet::*;
\ No newline at end of file
+pub use pet::*;
+pub use job::*;
+pub use admin::*;
+pub use generator::*;
+pub use lease::*;
+pub use metrics::*;
\ No newline at end of file
@@ -1,53 +1,548 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
-    Json,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
+use crypto_box::PublicKey;
 use std::sync::Arc;
 
-use crate::models::{ApiResponse, GetPetAddressResponse, PetGeneratorStatusResponse};
-use crate::pet::{PetGenerator, PetStorage};
+use crate::models::{
+    ApiResponse, BatchClaimRequest, BatchClaimResponse, ClaimQuery, GeneratorBatchResponse, GeneratorErrorResponse,
+    GetPetAddressResponse, PetAddressPayload, PetGeneratorStatusResponse, SealedPetAddressResponse,
+};
+use crate::pet::delivery::{parse_recipient_key, seal_keypair, seal_mnemonic, SEALED_BOX_SCHEME};
+use crate::config::{IdempotencyConfig, LeaseConfig};
+use crate::metrics::{ClaimOutcome, Metrics};
+use crate::middleware::{AuthenticatedKey, Authenticator, RequestContext};
+use crate::pet::{
+    export_key, ApiKey, Claimant, IdempotencyState, IdempotentClaim, JobManager, KeyFormat, PetAddressInfo,
+    PetGenerator, PetPool, PetStorage,
+};
+
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Most addresses a single batch claim may ask for
+pub const MAX_BATCH_CLAIM: usize = 100;
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+/// Batch claim counts, sent as headers when the addresses are streamed as NDJSON
+pub const CLAIM_REQUESTED: HeaderName = HeaderName::from_static("x-claim-requested");
+pub const CLAIM_COUNT: HeaderName = HeaderName::from_static("x-claim-count");
+pub const CLAIM_PARTIAL: HeaderName = HeaderName::from_static("x-claim-partial");
 
 pub struct PetAppState {
     pub generator: Arc<PetGenerator>,
     pub storage: Arc<PetStorage>,
+    pub jobs: Arc<JobManager>,
+    pub auth: Authenticator,
+    pub leases: LeaseConfig,
+    pub idempotency: IdempotencyConfig,
+    pub metrics: Arc<Metrics>,
+}
+
+/// What a claim request should do about its `Idempotency-Key` header
+pub(crate) enum Idempotency {
+    /// No header was sent
+    None,
+    /// The key is reserved for this request; pass it to storage with the claim
+    Reserved(String),
+    /// The key was already used with the same parameters, answer with the earlier claim
+    Replay(Box<IdempotentClaim>),
+}
+
+impl Idempotency {
+    pub(crate) fn key(&self) -> Option<&str> {
+        match self {
+            Idempotency::Reserved(key) => Some(key),
+            _ => None,
+        }
+    }
 }
 
 #[utoipa::path(
     get,
     path = "/api/v1/pet/address",
+    params(
+        ("recipient_key" = Option<String>, Query, description = "X25519 public key (hex or base64); when set the keypair is returned sealed to it instead of in clear"),
+        ("format" = Option<String>, Query, description = "Encoding of the returned secret key: base58 (default), solana-json, hex or base64. Cannot be combined with recipient_key"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key return the same address")
+    ),
     responses(
-        (status = 200, description = "Successfully retrieved Pet address", body = ApiResponse<GetPetAddressResponse>),
+        (status = 200, description = "Successfully retrieved Pet address, sealed when recipient_key was given", body = ApiResponse<PetAddressPayload>),
+        (status = 400, description = "Invalid recipient key, key format or idempotency key, or an idempotency key without an API key", body = ApiResponse<String>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the claim scope", body = ApiResponse<String>),
         (status = 404, description = "No Pet addresses available", body = ApiResponse<String>),
+        (status = 409, description = "A request with this idempotency key is still in progress", body = ApiResponse<String>),
+        (status = 410, description = "The claim or lease this idempotency key made is no longer held", body = ApiResponse<String>),
+        (status = 422, description = "Idempotency key was used with different parameters", body = ApiResponse<String>),
+        (status = 429, description = "Rate limit or claim quota exceeded", body = ApiResponse<String>),
         (status = 500, description = "Internal server error", body = ApiResponse<String>)
     ),
     tag = "Pet Address"
 )]
 pub async fn get_pet_address(
     State(app_state): State<Arc<PetAppState>>,
-) -> Result<Json<ApiResponse<GetPetAddressResponse>>, StatusCode> {
-    match app_state.storage.get_next_address() {
+    api_key: Option<Extension<AuthenticatedKey>>,
+    context: Option<Extension<RequestContext>>,
+    headers: HeaderMap,
+    Query(query): Query<ClaimQuery>,
+) -> Result<Json<ApiResponse<PetAddressPayload>>, StatusCode> {
+    let pool = app_state.generator.default_pool();
+    let api_key = api_key.as_ref().map(|Extension(AuthenticatedKey(key))| key);
+    claim_address(&app_state, pool, api_key, &claimant(api_key, context.as_ref()), &headers, &query)
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{name}/address",
+    params(
+        ("name" = String, Path, description = "Address pool name", example = "pet"),
+        ("recipient_key" = Option<String>, Query, description = "X25519 public key (hex or base64); when set the keypair is returned sealed to it instead of in clear"),
+        ("format" = Option<String>, Query, description = "Encoding of the returned secret key: base58 (default), solana-json, hex or base64. Cannot be combined with recipient_key"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key return the same address")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved address from the pool, sealed when recipient_key was given", body = ApiResponse<PetAddressPayload>),
+        (status = 400, description = "Invalid recipient key, key format or idempotency key, or an idempotency key without an API key", body = ApiResponse<String>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the claim scope", body = ApiResponse<String>),
+        (status = 404, description = "Unknown pool or no addresses available", body = ApiResponse<String>),
+        (status = 409, description = "A request with this idempotency key is still in progress", body = ApiResponse<String>),
+        (status = 410, description = "The claim or lease this idempotency key made is no longer held", body = ApiResponse<String>),
+        (status = 422, description = "Idempotency key was used with different parameters", body = ApiResponse<String>),
+        (status = 429, description = "Rate limit or claim quota exceeded", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Pet Address"
+)]
+pub async fn get_pool_address(
+    State(app_state): State<Arc<PetAppState>>,
+    api_key: Option<Extension<AuthenticatedKey>>,
+    context: Option<Extension<RequestContext>>,
+    headers: HeaderMap,
+    Path(name): Path<String>,
+    Query(query): Query<ClaimQuery>,
+) -> Result<Json<ApiResponse<PetAddressPayload>>, StatusCode> {
+    let pool = app_state.generator.pool(&name).ok_or(StatusCode::NOT_FOUND)?;
+    let api_key = api_key.as_ref().map(|Extension(AuthenticatedKey(key))| key);
+    claim_address(&app_state, pool, api_key, &claimant(api_key, context.as_ref()), &headers, &query)
+}
+
+fn claim_address(
+    app_state: &PetAppState,
+    pool: &PetPool,
+    api_key: Option<&ApiKey>,
+    claimant: &Claimant,
+    headers: &HeaderMap,
+    query: &ClaimQuery,
+) -> Result<Json<ApiResponse<PetAddressPayload>>, StatusCode> {
+    let delivery = key_delivery(query.recipient_key.as_deref(), query.format.as_deref())?;
+
+    let fingerprint = format!(
+        "claim|{}|{}|{}",
+        pool.name,
+        query.recipient_key.as_deref().unwrap_or_default(),
+        delivery.format(),
+    );
+    let idempotency = begin_idempotent(app_state, headers, api_key, &fingerprint)?;
+    if let Idempotency::Replay(claim) = idempotency {
+        app_state.metrics.record_claim(&pool.name, ClaimOutcome::Replayed, 0);
+        let response = address_payload(claim.address, &delivery).map_err(|e| {
+            tracing::error!("Failed to seal replayed Pet address from pool '{}': {}", pool.name, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        return Ok(Json(ApiResponse::success(response)));
+    }
+
+    let charged_at = match consume_claim_quota(app_state, api_key, 1) {
+        Ok(charged_at) => charged_at,
+        Err(status) => {
+            app_state.metrics.record_claim(&pool.name, quota_outcome(status), 0);
+            abandon_idempotent(app_state, &idempotency);
+            return Err(status);
+        }
+    };
+
+    let claimed = app_state.storage.claim_next_address(&pool.name, claimant, idempotency.key());
+    app_state.generator.notify_claim(pool);
+
+    // Nothing was handed out, so it does not count against the quota or use up the idempotency key
+    if let Ok(None) | Err(_) = &claimed {
+        if let Some(api_key) = api_key {
+            refund_claim_quota(app_state, api_key, 1, charged_at);
+        }
+        abandon_idempotent(app_state, &idempotency);
+    }
+
+    match claimed {
         Ok(Some(address_info)) => {
-            let response = GetPetAddressResponse {
-                id: address_info.id,
-                public_key: address_info.address.public_key,
-                private_key: address_info.address.private_key,
-                address: address_info.address.address,
-                created_at: address_info.created_at.to_rfc3339(),
-            };
-            
+            app_state.metrics.record_claim(&pool.name, ClaimOutcome::Claimed, 1);
+            let response = address_payload(address_info, &delivery).map_err(|e| {
+                tracing::error!("Failed to seal Pet address from pool '{}': {}", pool.name, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+
             Ok(Json(ApiResponse::success(response)))
         }
         Ok(None) => {
+            app_state.metrics.record_claim(&pool.name, ClaimOutcome::Empty, 0);
             Err(StatusCode::NOT_FOUND)
         }
         Err(e) => {
-            tracing::error!("Failed to get Pet address: {}", e);
+            app_state.metrics.record_claim(&pool.name, ClaimOutcome::Error, 0);
+            tracing::error!("Failed to get Pet address from pool '{}': {}", pool.name, e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
 }
 
+/// Who is making a claim, for the audit log
+pub(crate) fn claimant(api_key: Option<&ApiKey>, context: Option<&Extension<RequestContext>>) -> Claimant {
+    let context = context.map(|Extension(context)| context);
+    Claimant {
+        api_key: api_key.map(|key| key.id.clone()),
+        client_ip: context.and_then(|context| context.client_ip.clone()),
+        request_id: context.map(|context| context.request_id.clone()),
+    }
+}
+
+/// Count `count` claims against the quota of `api_key`, if the request has one.
+/// Returns when the quota was charged, for refunding against the same day and month.
+pub(crate) fn consume_claim_quota(
+    app_state: &PetAppState,
+    api_key: Option<&ApiKey>,
+    count: u64,
+) -> Result<chrono::DateTime<chrono::Utc>, StatusCode> {
+    let charged_at = chrono::Utc::now();
+    let Some(api_key) = api_key else {
+        return Ok(charged_at);
+    };
+
+    match app_state.storage.consume_claim_quota(api_key, count, charged_at) {
+        Ok(true) => Ok(charged_at),
+        Ok(false) => Err(StatusCode::TOO_MANY_REQUESTS),
+        Err(e) => {
+            tracing::error!("Failed to check claim quota of API key {}: {}", api_key.id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/pet/addresses",
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Retries with the same key return the same addresses")
+    ),
+    request_body = BatchClaimRequest,
+    responses(
+        (status = 200, description = "Claimed addresses. With `Accept: application/x-ndjson` they are streamed one per line, with the counts in the X-Claim-Requested, X-Claim-Count and X-Claim-Partial headers",
+            content(
+                (ApiResponse<BatchClaimResponse> = "application/json"),
+                (PetAddressPayload = "application/x-ndjson")
+            )
+        ),
+        (status = 400, description = "Invalid count, recipient key, key format or idempotency key, or an idempotency key without an API key", body = ApiResponse<String>),
+        (status = 401, description = "Missing or invalid API key", body = ApiResponse<String>),
+        (status = 403, description = "API key lacks the claim scope", body = ApiResponse<String>),
+        (status = 404, description = "Unknown pool, or fewer addresses available than requested without allow_partial", body = ApiResponse<String>),
+        (status = 409, description = "A request with this idempotency key is still in progress", body = ApiResponse<String>),
+        (status = 410, description = "The claim or lease this idempotency key made is no longer held", body = ApiResponse<String>),
+        (status = 422, description = "Idempotency key was used with different parameters", body = ApiResponse<String>),
+        (status = 429, description = "Rate limit or claim quota exceeded", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Pet Address"
+)]
+pub async fn claim_pet_addresses(
+    State(app_state): State<Arc<PetAppState>>,
+    api_key: Option<Extension<AuthenticatedKey>>,
+    context: Option<Extension<RequestContext>>,
+    headers: HeaderMap,
+    Json(request): Json<BatchClaimRequest>,
+) -> Result<Response, StatusCode> {
+    let api_key = api_key.as_ref().map(|Extension(AuthenticatedKey(key))| key);
+    let claimant = claimant(api_key, context.as_ref());
+
+    if request.count == 0 || request.count > MAX_BATCH_CLAIM {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let pool = match request.pool.as_deref() {
+        Some(name) => app_state.generator.pool(name).ok_or(StatusCode::NOT_FOUND)?,
+        None => app_state.generator.default_pool(),
+    };
+
+    let delivery = key_delivery(request.recipient_key.as_deref(), request.format.as_deref())?;
+    let wants_ndjson = headers
+        .get(header::ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .is_some_and(|accept| accept.contains(NDJSON_CONTENT_TYPE));
+
+    let fingerprint = format!(
+        "batch|{}|{}|{}|{}|{}",
+        pool.name,
+        request.count,
+        request.allow_partial,
+        request.recipient_key.as_deref().unwrap_or_default(),
+        delivery.format(),
+    );
+    let idempotency = begin_idempotent(&app_state, &headers, api_key, &fingerprint)?;
+    if let Idempotency::Replay(claim) = idempotency {
+        app_state.metrics.record_claim(&pool.name, ClaimOutcome::Replayed, 0);
+        let addresses = claim.batch
+            .into_iter()
+            .map(|address_info| address_payload(address_info, &delivery))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(|e| {
+                tracing::error!("Failed to seal replayed Pet addresses from pool '{}': {}", pool.name, e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        return batch_response(request.count, addresses, wants_ndjson);
+    }
+
+    // The whole batch must fit in the quota; whatever is not handed out is refunded
+    let charged_at = match consume_claim_quota(&app_state, api_key, request.count as u64) {
+        Ok(charged_at) => charged_at,
+        Err(status) => {
+            app_state.metrics.record_claim(&pool.name, quota_outcome(status), 0);
+            abandon_idempotent(&app_state, &idempotency);
+            return Err(status);
+        }
+    };
+
+    // Keypairs are sealed before the claim is committed, so a failure leaves them in the pool
+    let claimed = app_state.storage.claim_addresses_with(
+        &pool.name,
+        request.count,
+        &claimant,
+        request.allow_partial,
+        idempotency.key(),
+        |address_info| address_payload(address_info, &delivery),
+    );
+    app_state.generator.notify_claim(pool);
+
+    let unclaimed = claimed.as_ref().map_or(request.count, |addresses| request.count - addresses.len());
+    if let (Some(api_key), 1..) = (api_key, unclaimed) {
+        refund_claim_quota(&app_state, api_key, unclaimed as u64, charged_at);
+    }
+    if unclaimed == request.count {
+        abandon_idempotent(&app_state, &idempotency);
+    }
+
+    let addresses = match claimed {
+        Ok(addresses) if addresses.is_empty() => {
+            app_state.metrics.record_claim(&pool.name, ClaimOutcome::Empty, 0);
+            return Err(StatusCode::NOT_FOUND);
+        }
+        Ok(addresses) => addresses,
+        Err(e) => {
+            app_state.metrics.record_claim(&pool.name, ClaimOutcome::Error, 0);
+            tracing::error!("Failed to claim {} addresses from pool '{}': {}", request.count, pool.name, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    let outcome = if addresses.len() < request.count { ClaimOutcome::Partial } else { ClaimOutcome::Claimed };
+    app_state.metrics.record_claim(&pool.name, outcome, addresses.len());
+
+    batch_response(request.count, addresses, wants_ndjson)
+}
+
+/// Batch claim response, as one JSON document or streamed as NDJSON
+fn batch_response(
+    requested: usize,
+    addresses: Vec<PetAddressPayload>,
+    ndjson: bool,
+) -> Result<Response, StatusCode> {
+    let claimed = addresses.len();
+    let partial = claimed < requested;
+
+    if ndjson {
+        let lines = addresses.into_iter().map(|payload| {
+            let mut line = serde_json::to_vec(&payload)?;
+            line.push(b'\n');
+            Ok::<_, std::io::Error>(line)
+        });
+
+        let mut response = Body::from_stream(futures_util::stream::iter(lines)).into_response();
+        let headers = response.headers_mut();
+        headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(NDJSON_CONTENT_TYPE));
+        headers.insert(CLAIM_REQUESTED, HeaderValue::from(requested));
+        headers.insert(CLAIM_COUNT, HeaderValue::from(claimed));
+        headers.insert(CLAIM_PARTIAL, HeaderValue::from_static(if partial { "true" } else { "false" }));
+        return Ok(response);
+    }
+
+    Ok(Json(ApiResponse::success(BatchClaimResponse {
+        requested,
+        claimed,
+        partial,
+        addresses,
+    })).into_response())
+}
+
+/// Check the `Idempotency-Key` header of a claim whose parameters are summed up by `fingerprint`.
+/// Keys are scoped to the API key making the request, so anonymous requests cannot use them:
+/// they would all share one namespace and could replay each other's claims.
+pub(crate) fn begin_idempotent(
+    app_state: &PetAppState,
+    headers: &HeaderMap,
+    api_key: Option<&ApiKey>,
+    fingerprint: &str,
+) -> Result<Idempotency, StatusCode> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY) else {
+        return Ok(Idempotency::None);
+    };
+    let Some(api_key) = api_key else {
+        return Err(StatusCode::BAD_REQUEST);
+    };
+    let key = value.to_str().map_err(|_| StatusCode::BAD_REQUEST)?;
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LEN {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let key = format!("{}:{}", api_key.id, key);
+    let state = app_state.storage
+        .reserve_idempotency_key(&key, fingerprint, app_state.idempotency.retention())
+        .map_err(|e| {
+            tracing::error!("Failed to reserve idempotency key: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    match state {
+        IdempotencyState::Reserved => Ok(Idempotency::Reserved(key)),
+        IdempotencyState::Pending { fingerprint: used } | IdempotencyState::Completed { fingerprint: used, .. }
+            if used != fingerprint =>
+        {
+            Err(StatusCode::UNPROCESSABLE_ENTITY)
+        }
+        IdempotencyState::Pending { .. } => Err(StatusCode::CONFLICT),
+        IdempotencyState::Completed { claim, .. } => match still_held(app_state, api_key, &claim) {
+            Ok(true) => Ok(Idempotency::Replay(claim)),
+            Ok(false) => Err(StatusCode::GONE),
+            Err(e) => {
+                tracing::error!("Failed to check idempotent claim: {}", e);
+                Err(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        },
+    }
+}
+
+/// Whether the claim or lease an idempotency key made still belongs to `api_key`. A lease that
+/// was confirmed, released or expired since must not be handed out again.
+fn still_held(app_state: &PetAppState, api_key: &ApiKey, claim: &IdempotentClaim) -> anyhow::Result<bool> {
+    let held = match &claim.lease {
+        Some(lease) => app_state.storage.get_lease(&lease.id)?
+            .is_some_and(|lease| lease.api_key.as_deref() == Some(api_key.id.as_str())),
+        None => {
+            let mut held = true;
+            for address_info in std::iter::once(&claim.address).chain(&claim.batch) {
+                held &= app_state.storage.get_claim(address_info.id)?.is_some_and(|record| {
+                    record.public_key == address_info.address.public_key
+                        && record.api_key.as_deref() == Some(api_key.id.as_str())
+                });
+            }
+            held
+        }
+    };
+    Ok(held)
+}
+
+/// Free a reserved idempotency key after the request failed to claim anything
+pub(crate) fn abandon_idempotent(app_state: &PetAppState, idempotency: &Idempotency) {
+    if let Some(key) = idempotency.key() {
+        if let Err(e) = app_state.storage.release_idempotency_key(key) {
+            tracing::error!("Failed to release idempotency key: {}", e);
+        }
+    }
+}
+
+/// Claim outcome for a failed [`consume_claim_quota`]
+pub(crate) fn quota_outcome(status: StatusCode) -> ClaimOutcome {
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        ClaimOutcome::QuotaExceeded
+    } else {
+        ClaimOutcome::Error
+    }
+}
+
+pub(crate) fn refund_claim_quota(
+    app_state: &PetAppState,
+    api_key: &ApiKey,
+    count: u64,
+    charged_at: chrono::DateTime<chrono::Utc>,
+) {
+    if let Err(e) = app_state.storage.refund_claim_quota(&api_key.id, count, charged_at) {
+        tracing::error!("Failed to refund claim quota of API key {}: {}", api_key.id, e);
+    }
+}
+
+/// How a claimed keypair is returned: in clear in some encoding, or sealed to the client's key
+pub(crate) enum KeyDelivery {
+    Plain(KeyFormat),
+    Sealed(PublicKey),
+}
+
+impl KeyDelivery {
+    /// Encoding named in idempotency fingerprints, the default one when `format` was omitted
+    fn format(&self) -> String {
+        match self {
+            KeyDelivery::Plain(format) => format.to_string(),
+            KeyDelivery::Sealed(_) => String::new(),
+        }
+    }
+}
+
+/// Check `recipient_key` and `format` before an address is taken out of the pool.
+/// A sealed keypair is always the raw 64 bytes, so the two cannot be combined.
+pub(crate) fn key_delivery(recipient_key: Option<&str>, format: Option<&str>) -> Result<KeyDelivery, StatusCode> {
+    match (recipient_key, format) {
+        (Some(_), Some(_)) => Err(StatusCode::BAD_REQUEST),
+        (Some(key), None) => parse_recipient_key(key)
+            .map(KeyDelivery::Sealed)
+            .map_err(|_| StatusCode::BAD_REQUEST),
+        (None, format) => format
+            .map(str::parse::<KeyFormat>)
+            .transpose()
+            .map(|format| KeyDelivery::Plain(format.unwrap_or_default()))
+            .map_err(|_| StatusCode::BAD_REQUEST),
+    }
+}
+
+pub(crate) fn address_payload(
+    address_info: PetAddressInfo,
+    delivery: &KeyDelivery,
+) -> anyhow::Result<PetAddressPayload> {
+    let payload = match delivery {
+        KeyDelivery::Sealed(recipient) => PetAddressPayload::Sealed(SealedPetAddressResponse {
+            id: address_info.id,
+            sealed_keypair: seal_keypair(recipient, &address_info.address)?,
+            sealed_mnemonic: seal_mnemonic(recipient, &address_info.address)?,
+            derivation_path: address_info.address.derivation_path,
+            encryption: SEALED_BOX_SCHEME.to_string(),
+            public_key: address_info.address.public_key,
+            address: address_info.address.address,
+            created_at: address_info.created_at.to_rfc3339(),
+        }),
+        KeyDelivery::Plain(format) => PetAddressPayload::Plain(GetPetAddressResponse {
+            id: address_info.id,
+            private_key: export_key(&address_info.address, *format)?,
+            key_format: format.to_string(),
+            mnemonic: address_info.address.mnemonic,
+            derivation_path: address_info.address.derivation_path,
+            public_key: address_info.address.public_key,
+            address: address_info.address.address,
+            created_at: address_info.created_at.to_rfc3339(),
+        }),
+    };
+
+    Ok(payload)
+}
+
 #[utoipa::path(
     get,
     path = "/api/v1/pet/status",
@@ -60,19 +555,93 @@ pub async fn get_pet_address(
 pub async fn get_pet_status(
     State(app_state): State<Arc<PetAppState>>,
 ) -> Result<Json<ApiResponse<PetGeneratorStatusResponse>>, StatusCode> {
-    match app_state.generator.get_current_count().await {
-        Ok(count) => {
-            let response = PetGeneratorStatusResponse {
-                total_addresses: count,
-                pool_size: 100, // TODO: Get from config
-                generation_active: true, // TODO: Get actual status
-            };
-            
-            Ok(Json(ApiResponse::success(response)))
+    let pool = app_state.generator.default_pool();
+    pool_status(&app_state, pool)
+        .await
+        .map(|status| Json(ApiResponse::success(status)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools/{name}/status",
+    params(
+        ("name" = String, Path, description = "Address pool name", example = "pet")
+    ),
+    responses(
+        (status = 200, description = "Pool generator status", body = ApiResponse<PetGeneratorStatusResponse>),
+        (status = 404, description = "Unknown pool", body = ApiResponse<String>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Pet Address"
+)]
+pub async fn get_pool_status(
+    State(app_state): State<Arc<PetAppState>>,
+    Path(name): Path<String>,
+) -> Result<Json<ApiResponse<PetGeneratorStatusResponse>>, StatusCode> {
+    let pool = app_state.generator.pool(&name).ok_or(StatusCode::NOT_FOUND)?;
+    pool_status(&app_state, pool)
+        .await
+        .map(|status| Json(ApiResponse::success(status)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pools",
+    responses(
+        (status = 200, description = "Status of every configured pool", body = ApiResponse<Vec<PetGeneratorStatusResponse>>),
+        (status = 500, description = "Internal server error", body = ApiResponse<String>)
+    ),
+    tag = "Pet Address"
+)]
+pub async fn list_pools(
+    State(app_state): State<Arc<PetAppState>>,
+) -> Result<Json<ApiResponse<Vec<PetGeneratorStatusResponse>>>, StatusCode> {
+    let mut statuses = Vec::with_capacity(app_state.generator.pools().len());
+    for pool in app_state.generator.pools() {
+        statuses.push(pool_status(&app_state, pool).await?);
+    }
+
+    Ok(Json(ApiResponse::success(statuses)))
+}
+
+pub(crate) async fn pool_status(
+    app_state: &PetAppState,
+    pool: &PetPool,
+) -> Result<PetGeneratorStatusResponse, StatusCode> {
+    match app_state.generator.pool_status(pool).await {
+        Ok(status) => {
+            Ok(PetGeneratorStatusResponse {
+                pool: pool.name.clone(),
+                kind: pool.kind.as_str().to_string(),
+                pattern: pool.pattern.to_string(),
+                total_addresses: status.available,
+                pool_size: status.settings.pool_size,
+                low_watermark: status.settings.low_watermark(),
+                batch_size: status.settings.batch_size,
+                state: match (status.running, status.settings.paused) {
+                    (false, _) => "stopped",
+                    (true, true) => "paused",
+                    (true, false) => "running",
+                }.to_string(),
+                generation_active: status.running && !status.settings.paused,
+                batch_in_flight: status.batch.map(|batch| GeneratorBatchResponse {
+                    size: batch.size,
+                    generated: batch.generated,
+                    attempts: batch.attempts,
+                    started_at: batch.started_at.to_rfc3339(),
+                }),
+                generated_total: status.generated_total,
+                last_error: status.last_error.map(|error| GeneratorErrorResponse {
+                    message: error.message,
+                    at: error.at.to_rfc3339(),
+                }),
+                hash_rate: status.hash_rate,
+                eta_seconds: status.eta_seconds,
+            })
         }
         Err(e) => {
-            tracing::error!("Failed to get Pet generator status: {}", e);
+            tracing::error!("Failed to get Pet generator status for pool '{}': {}", pool.name, e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
         }
     }
-}
\ No newline at end of file
+}
@@ -1,4 +1,7 @@
+#![allow(non_snake_case)]
+
 pub mod config;
+pub mod metrics;
 pub mod handlers;
 pub mod middleware;
 pub mod models;
@@ -6,45 +9,121 @@ pub mod routes;
 pub mod utils;
 pub mod pet;
 
-use axum::{Extension, Router};
+use axum::{middleware::{from_fn, from_fn_with_state}, Router};
 use tower::ServiceBuilder;
-use tower_http::trace::TraceLayer;
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
+use std::future::IntoFuture;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
 use std::sync::Arc;
 
 use crate::config::AppConfig;
-use crate::middleware::{cors_layer, logging_layer, RateLimiter, rate_limit_middleware};
+use crate::middleware::{
+    auth_middleware, cors_layer, http_metrics_middleware, logging_layer, rate_limit_middleware, request_context_middleware, Authenticator,
+    RateLimiter,
+};
 use crate::routes::create_routes;
 use crate::handlers::PetAppState;
-use crate::pet::{PetGenerator, PetStorage};
+use crate::metrics::Metrics;
+use crate::pet::archive;
+use crate::pet::{
+    spawn_audit_purge, spawn_idempotency_purge, spawn_lease_reaper, ArchiveManifest, ImportSummary, JobManager, MasterKey, PetGenerator,
+    PetPool, PetStorage, Scope,
+};
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         crate::handlers::health::health_check,
         crate::handlers::health::detailed_health_check,
+        crate::handlers::metrics::get_metrics,
         crate::handlers::time::get_server_time,
         crate::handlers::time::get_multi_timezone,
         crate::handlers::pet::get_pet_address,
+        crate::handlers::pet::claim_pet_addresses,
         crate::handlers::pet::get_pet_status,
+        crate::handlers::pet::get_pool_address,
+        crate::handlers::pet::get_pool_status,
+        crate::handlers::pet::list_pools,
+        crate::handlers::job::create_job,
+        crate::handlers::job::search_split_key,
+        crate::handlers::job::get_job,
+        crate::handlers::job::cancel_job,
+        crate::handlers::job::estimate_pattern,
+        crate::handlers::lease::create_lease,
+        crate::handlers::lease::confirm_lease,
+        crate::handlers::lease::release_lease,
+        crate::handlers::admin::create_api_key,
+        crate::handlers::admin::list_api_keys,
+        crate::handlers::admin::delete_api_key,
+        crate::handlers::admin::list_audit,
+        crate::handlers::admin::get_address_audit,
+        crate::handlers::generator::pause_generator,
+        crate::handlers::generator::resume_generator,
+        crate::handlers::generator::update_pool,
+        crate::handlers::generator::pause_pool,
+        crate::handlers::generator::resume_pool,
+        crate::handlers::generator::refill_pool,
+        crate::handlers::generator::clear_pool,
     ),
     components(schemas(
         crate::models::ApiResponse<crate::models::HealthResponse>,
         crate::models::ApiResponse<crate::models::ServerTimeResponse>,
         crate::models::ApiResponse<crate::models::GetPetAddressResponse>,
+        crate::models::ApiResponse<crate::models::PetAddressPayload>,
         crate::models::ApiResponse<crate::models::PetGeneratorStatusResponse>,
+        crate::models::ApiResponse<Vec<crate::models::PetGeneratorStatusResponse>>,
         crate::models::ApiResponse<serde_json::Value>,
         crate::models::HealthResponse,
         crate::models::ServerTimeResponse,
         crate::models::GetPetAddressResponse,
+        crate::pet::ExportedKey,
+        crate::models::SealedPetAddressResponse,
+        crate::models::PetAddressPayload,
+        crate::models::BatchClaimRequest,
+        crate::models::BatchClaimResponse,
+        crate::models::ApiResponse<crate::models::BatchClaimResponse>,
+        crate::models::SplitKeyRequest,
+        crate::models::SplitKeyResponse,
         crate::models::PetGeneratorStatusResponse,
+        crate::models::GeneratorBatchResponse,
+        crate::models::GeneratorErrorResponse,
+        crate::models::CreateLeaseRequest,
+        crate::models::LeaseResponse,
+        crate::models::ConfirmLeaseRequest,
+        crate::models::LeaseStatusResponse,
+        crate::models::ApiResponse<crate::models::LeaseResponse>,
+        crate::models::ApiResponse<crate::models::LeaseStatusResponse>,
+        crate::models::CreateJobRequest,
+        crate::models::JobResponse,
+        crate::models::JobResultResponse,
+        crate::models::ApiResponse<crate::models::JobResponse>,
+        crate::models::EstimateQuery,
+        crate::models::EstimateResponse,
+        crate::models::EstimatePercentileResponse,
+        crate::models::ApiResponse<crate::models::EstimateResponse>,
+        crate::models::ApiKeyRateLimit,
+        crate::models::CreateApiKeyRequest,
+        crate::models::ApiKeyResponse,
+        crate::models::CreatedApiKeyResponse,
+        crate::models::ApiResponse<crate::models::CreatedApiKeyResponse>,
+        crate::models::ApiResponse<Vec<crate::models::ApiKeyResponse>>,
+        crate::models::AuditQuery,
+        crate::models::AuditEntryResponse,
+        crate::models::ApiResponse<Vec<crate::models::AuditEntryResponse>>,
+        crate::models::UpdatePoolRequest,
+        crate::models::ClearPoolRequest,
+        crate::models::ClearPoolResponse,
+        crate::models::ApiResponse<crate::models::ClearPoolResponse>,
         crate::models::TimeQuery,
     )),
     tags(
         (name = "Time Service", description = "APIs for getting server time"),
         (name = "Health Check", description = "Service health status check"),
-        (name = "Pet Address", description = "APIs for Pet address generation and management")
+        (name = "Pet Address", description = "APIs for Pet address generation and management"),
+        (name = "Vanity Jobs", description = "APIs for custom vanity address searches"),
+        (name = "Admin", description = "API key management, claim audit log and generator control, requires an admin key")
     ),
     info(
         title = "PetAddr Server API",
@@ -55,33 +134,94 @@ use crate::pet::{PetGenerator, PetStorage};
 pub struct ApiDoc;
 
 pub async fn create_app(config: AppConfig) -> anyhow::Result<(Router, Arc<PetGenerator>)> {
-    // Initialize Pet storage
-    let storage = Arc::new(PetStorage::new(&config.pet_generator.db_path)?);
+    // Initialize Pet storage, refusing to start with the wrong master key
+    let master_key = MasterKey::load(&config.encryption)?;
+    let storage = Arc::new(PetStorage::open(&config.pet_generator.db_path, master_key)?);
+    let metrics = Arc::new(Metrics::new()?);
     
     // Initialize Pet generator
     let generator = Arc::new(PetGenerator::new(
         Arc::clone(&storage),
         config.pet_generator.clone(),
-    ));
+        Arc::clone(&metrics),
+    )?);
+    
+    // Custom vanity jobs share the generator's search workers; pick up any left running
+    let jobs = Arc::new(JobManager::new(Arc::clone(&storage), generator.engine(), config.jobs.clone()));
+    jobs.resume()?;
+    
+    // Audit entries are kept forever unless a retention period is configured
+    if let Some(retention) = config.audit.retention() {
+        spawn_audit_purge(Arc::clone(&storage), retention);
+    }
+    
+    // Forget idempotency keys once retries are no longer expected
+    spawn_idempotency_purge(Arc::clone(&storage), config.idempotency.retention());
+    
+    // Return expired leases to their pools in the background
+    spawn_lease_reaper(
+        Arc::clone(&storage),
+        std::time::Duration::from_secs(config.leases.reaper_interval_seconds.max(1)),
+    );
+    
+    let auth = Authenticator::new(Arc::clone(&storage), &config.rate_limit.message, Arc::clone(&metrics));
+    let anonymous = |scope: Scope| config.auth.anonymous.contains(&scope);
     
     // Create Pet app state
     let pet_state = Arc::new(PetAppState {
         generator: Arc::clone(&generator),
         storage,
+        jobs,
+        auth: auth.clone(),
+        leases: config.leases.clone(),
+        idempotency: config.idempotency.clone(),
+        metrics: Arc::clone(&metrics),
     });
     
-    // Create rate limiter
-    let rate_limiter = RateLimiter::new(
-        config.rate_limit.max_requests_per_minute,
-        config.rate_limit.window_seconds,
-    );
+    // Create one rate limiter per limited route group; status and health routes are exempt
+    let claim_limiter = RateLimiter::from_rule(&config.rate_limit.claims(), &config.rate_limit.message)
+        .with_metrics(&metrics, "claims");
+    let job_limiter = RateLimiter::from_rule(&config.rate_limit.jobs(), &config.rate_limit.message)
+        .with_metrics(&metrics, "jobs");
     
-    let (base_routes, pet_routes, pet_status_routes) = create_routes(&config);
+    // Auth is added last so it runs before the rate limiter, which counts keyed requests per key
+    let routes = create_routes(&config);
     
     let mut app = Router::new()
-        .merge(base_routes)
-        .merge(pet_status_routes.with_state(Arc::clone(&pet_state)))
-        .merge(pet_routes.with_state(pet_state));
+        .merge(routes.base)
+        .merge(
+            // Metrics reveal pool depths and traffic, so they need a status key even when status routes are open
+            routes.metrics
+                .route_layer(from_fn_with_state(auth.route(Scope::Status, false), auth_middleware))
+                .with_state(Arc::clone(&pet_state)),
+        )
+        .merge(
+            routes.status
+                .route_layer(from_fn_with_state(auth.route(Scope::Status, anonymous(Scope::Status)), auth_middleware))
+                .with_state(Arc::clone(&pet_state)),
+        )
+        .merge(
+            routes.jobs
+                .route_layer(from_fn_with_state(job_limiter, rate_limit_middleware))
+                .route_layer(from_fn_with_state(auth.route(Scope::Jobs, anonymous(Scope::Jobs)), auth_middleware))
+                .with_state(Arc::clone(&pet_state)),
+        )
+        .merge(
+            routes.job_status
+                .route_layer(from_fn_with_state(auth.route(Scope::Jobs, anonymous(Scope::Jobs)), auth_middleware))
+                .with_state(Arc::clone(&pet_state)),
+        )
+        .merge(
+            routes.admin
+                .route_layer(from_fn_with_state(auth.route(Scope::Admin, false), auth_middleware))
+                .with_state(Arc::clone(&pet_state)),
+        )
+        .merge(
+            routes.claim
+                .route_layer(from_fn_with_state(claim_limiter, rate_limit_middleware))
+                .route_layer(from_fn_with_state(auth.route(Scope::Claim, anonymous(Scope::Claim)), auth_middleware))
+                .with_state(pet_state),
+        );
 
     // Add Swagger UI if enabled
     if config.swagger.enabled {
@@ -98,6 +238,8 @@ pub async fn create_app(config: AppConfig) -> anyhow::Result<(Router, Arc<PetGen
         ServiceBuilder::new()
             .layer(logging_layer())
             .layer(cors_layer())
+            .layer(from_fn(request_context_middleware))
+            .layer(from_fn_with_state(metrics, http_metrics_middleware))
     );
 
     Ok((app, generator))
@@ -129,14 +271,199 @@ pub async fn run_server(config: AppConfig) -> anyhow::Result<()> {
     tracing::info!("⏰ Time API: http://{}{}/time", addr, config.api_base_url());
     tracing::info!("🐕 Pet Address API: http://{}{}/pet/address", addr, config.api_base_url());
     tracing::info!("📊 Pet Status API: http://{}{}/pet/status", addr, config.api_base_url());
+    tracing::info!("🗂️  Pools API: http://{}{}/pools", addr, config.api_base_url());
+    tracing::info!("🎯 Vanity Jobs API: http://{}{}/jobs", addr, config.api_base_url());
     tracing::info!("❤️  Health Check: http://{}/health", addr);
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await?;
     
+    // The listener closes as soon as a signal arrives, open connections get the drain timeout to finish
+    let (signal_tx, signal_rx) = tokio::sync::oneshot::channel();
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            let _ = signal_tx.send(());
+        })
+        .into_future();
+    let mut server = std::pin::pin!(server);
+    
+    tokio::select! {
+        result = &mut server => return result.map_err(Into::into),
+        _ = signal_rx => {}
+    }
+    
+    let drain_timeout = config.shutdown.drain_timeout();
+    tracing::info!("🛑 Shutting down, draining requests for up to {}s", drain_timeout.as_secs());
+    
+    let drain = async {
+        match tokio::time::timeout(drain_timeout, &mut server).await {
+            Ok(result) => result.map_err(anyhow::Error::from),
+            Err(_) => {
+                tracing::warn!("Requests still running after the drain timeout were dropped");
+                Ok(())
+            }
+        }
+    };
+    shutdown(drain, &generator).await?;
+    
+    tracing::info!("👋 Server stopped");
     Ok(())
 }
 
+/// Wait for the in-flight requests to drain and the generator to stop, then flush the
+/// database once, when nothing writes to it any more
+pub async fn shutdown(
+    drain: impl std::future::Future<Output = anyhow::Result<()>>,
+    generator: &PetGenerator,
+) -> anyhow::Result<()> {
+    let (drained, stopped) = tokio::join!(drain, generator.stop());
+    let flushed = generator.storage().flush();
+    drained?;
+    stopped?;
+    flushed?;
+    
+    tracing::info!("💾 Database flushed");
+    Ok(())
+}
+
+/// Resolves on Ctrl+C, or on SIGTERM where there is one
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Open the configured database outside of the server, e.g. for CLI maintenance commands
+pub fn open_storage(config: &AppConfig) -> anyhow::Result<PetStorage> {
+    let master_key = MasterKey::load(&config.encryption)?;
+    PetStorage::open(&config.pet_generator.db_path, master_key)
+}
+
+/// Seal every stored private key with `new_key`, using the configured key to unwrap existing records
+pub fn reencrypt_storage(config: &AppConfig, new_key: MasterKey) -> anyhow::Result<usize> {
+    init_logging(&config.logging.level);
+
+    let current_key = MasterKey::load(&config.encryption)?;
+    let storage = PetStorage::open(&config.pet_generator.db_path, current_key)?;
+    let count = storage.reencrypt(&new_key)?;
+
+    tracing::info!("🔐 Re-encrypted {} addresses with master key version {}", count, new_key.version());
+    tracing::info!("Update [encryption] to point at the new key before starting the server");
+
+    Ok(count)
+}
+
+/// Write the unclaimed addresses of `pools` (every configured pool when empty) to a new encrypted archive.
+///
+/// With `remove` the exported addresses are deleted once the archive is on disk, so they are
+/// only ever served by the database they are imported into. The server must be stopped.
+pub fn export_storage(
+    config: &AppConfig,
+    output: &Path,
+    pools: &[String],
+    passphrase: &str,
+    remove: bool,
+) -> anyhow::Result<ArchiveManifest> {
+    init_logging(&config.logging.level);
+
+    let configured = PetPool::from_configs(&config.pet_generator.pools())?;
+    let pools: Vec<String> = if pools.is_empty() {
+        configured.iter().map(|pool| pool.name.clone()).collect()
+    } else {
+        for name in pools {
+            if !configured.iter().any(|pool| &pool.name == name) {
+                anyhow::bail!("Pool \"{}\" is not configured", name);
+            }
+        }
+        pools.to_vec()
+    };
+
+    let storage = open_storage(config)?;
+    let records = archive::export_records(&storage, &pools)?;
+
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let file = options
+        .open(output)
+        .map_err(|e| anyhow::anyhow!("Failed to create archive {}: {}", output.display(), e))?;
+    let manifest = archive::write_archive(BufWriter::new(&file), &records, passphrase)?;
+    file.sync_all()?;
+
+    tracing::info!("📦 Exported {} addresses to {} ({})", manifest.count, output.display(), manifest.checksum);
+
+    if remove {
+        for record in &records {
+            storage.remove_address(&record.pool, record.info.id)?;
+        }
+        storage.flush()?;
+        tracing::info!("Removed {} exported addresses from the database", records.len());
+    }
+
+    Ok(manifest)
+}
+
+/// Import an archive written by [`export_storage`] into the configured pools. The server must be stopped.
+pub fn import_storage(
+    config: &AppConfig,
+    input: &Path,
+    passphrase: &str,
+    target_pool: Option<&str>,
+    preserve_ids: bool,
+) -> anyhow::Result<ImportSummary> {
+    init_logging(&config.logging.level);
+
+    let file = std::fs::File::open(input)
+        .map_err(|e| anyhow::anyhow!("Failed to open archive {}: {}", input.display(), e))?;
+    let (manifest, records) = archive::read_archive(BufReader::new(file), passphrase)?;
+    tracing::info!(
+        "Read {} addresses exported at {} ({})",
+        manifest.count,
+        manifest.created_at.to_rfc3339(),
+        manifest.checksum
+    );
+
+    let pools = PetPool::from_configs(&config.pet_generator.pools())?;
+    let storage = open_storage(config)?;
+    let summary = archive::import_archive(&storage, &pools, &records, target_pool, preserve_ids)?;
+
+    tracing::info!(
+        "📥 Imported {} addresses, skipped {} already known and {} not matching their pool's pattern",
+        summary.imported,
+        summary.duplicates,
+        summary.mismatched
+    );
+
+    Ok(summary)
+}
+
 fn init_logging(level: &str) {
     let log_level = match level.to_lowercase().as_str() {
         "trace" => tracing::Level::TRACE,
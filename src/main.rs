@@ -1,8 +1,117 @@
+#![allow(non_snake_case)]
+
 use anyhow::Result;
-use PetAddr::{config::AppConfig, utils::load_env, run_server};
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use PetAddr::{
+    config::{AppConfig, RateLimitRule},
+    export_storage, import_storage, open_storage,
+    pet::{archive::load_passphrase, ApiKey, MasterKey, Scope},
+    reencrypt_storage, run_server,
+    utils::load_env,
+};
+
+#[derive(Parser)]
+#[command(name = "PetAddr", version, about = "Solana vanity address server")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the HTTP server (default)
+    Serve,
+    /// Write a new random master key to a file
+    GenerateKey {
+        /// Output file for the hex encoded key
+        output: PathBuf,
+    },
+    /// Re-encrypt all stored private keys with a new master key (server must be stopped)
+    Reencrypt {
+        /// File holding the new hex or base64 encoded master key
+        #[arg(long)]
+        new_key_file: PathBuf,
+        /// Version recorded on records sealed with the new key
+        #[arg(long)]
+        new_key_version: u32,
+    },
+    /// Write pooled addresses to an encrypted archive (server must be stopped)
+    Export {
+        /// Archive file to create; never overwritten
+        output: PathBuf,
+        /// Pool to export, repeatable; every configured pool when omitted
+        #[arg(long = "pool")]
+        pools: Vec<String>,
+        /// Delete the exported addresses from this database once the archive is written
+        #[arg(long)]
+        remove: bool,
+        /// File holding the archive passphrase
+        #[arg(long)]
+        passphrase_file: Option<PathBuf>,
+        /// Environment variable holding the passphrase when no file is given
+        #[arg(long, default_value = "PETADDR_ARCHIVE_PASSPHRASE")]
+        passphrase_env: String,
+    },
+    /// Add the addresses of an archive to the pools, skipping known public keys (server must be stopped)
+    Import {
+        /// Archive file written by `export`
+        input: PathBuf,
+        /// Import every address into this pool instead of the one it was exported from
+        #[arg(long)]
+        pool: Option<String>,
+        /// Keep the archived ids instead of numbering the addresses after this database's last id
+        #[arg(long)]
+        preserve_ids: bool,
+        /// File holding the archive passphrase
+        #[arg(long)]
+        passphrase_file: Option<PathBuf>,
+        /// Environment variable holding the passphrase when no file is given
+        #[arg(long, default_value = "PETADDR_ARCHIVE_PASSPHRASE")]
+        passphrase_env: String,
+    },
+    /// Manage API keys (server must be stopped; use the admin API while it runs)
+    ApiKey {
+        #[command(subcommand)]
+        command: ApiKeyCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum ApiKeyCommand {
+    /// Create a key and print it; it cannot be shown again
+    Create {
+        /// Name to recognise the key by
+        #[arg(long)]
+        name: String,
+        /// Comma separated scopes: claim, status, jobs, admin
+        #[arg(long, value_delimiter = ',', required = true)]
+        scopes: Vec<Scope>,
+        /// Addresses the key may claim per UTC day
+        #[arg(long)]
+        daily_quota: Option<u64>,
+        /// Addresses the key may claim per UTC month
+        #[arg(long)]
+        monthly_quota: Option<u64>,
+        /// Requests the key may make per rate window, replacing the per-IP limit
+        #[arg(long)]
+        rate_limit: Option<u32>,
+        /// Rate window in seconds
+        #[arg(long, default_value_t = 60)]
+        rate_window: u64,
+    },
+    /// List keys and their usage
+    List,
+    /// Revoke a key by id
+    Revoke {
+        id: String,
+    },
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
     // 加载环境变量
     load_env()?;
     
@@ -10,6 +119,71 @@ async fn main() -> Result<()> {
     let config = AppConfig::load()
         .map_err(|e| anyhow::anyhow!("配置加载失败: {}", e))?;
     
-    // 运行服务器
-    run_server(config).await
+    match cli.command.unwrap_or(Command::Serve) {
+        // 运行服务器
+        Command::Serve => run_server(config).await,
+        Command::GenerateKey { output } => {
+            MasterKey::generate_file(&output)?;
+            println!("Master key written to {}", output.display());
+            Ok(())
+        }
+        Command::Reencrypt { new_key_file, new_key_version } => {
+            let new_key = MasterKey::from_file(new_key_version, &new_key_file)?;
+            reencrypt_storage(&config, new_key)?;
+            Ok(())
+        }
+        Command::Export { output, pools, remove, passphrase_file, passphrase_env } => {
+            let passphrase = load_passphrase(passphrase_file.as_deref(), &passphrase_env)?;
+            export_storage(&config, &output, &pools, &passphrase, remove)?;
+            Ok(())
+        }
+        Command::Import { input, pool, preserve_ids, passphrase_file, passphrase_env } => {
+            let passphrase = load_passphrase(passphrase_file.as_deref(), &passphrase_env)?;
+            import_storage(&config, &input, &passphrase, pool.as_deref(), preserve_ids)?;
+            Ok(())
+        }
+        Command::ApiKey { command } => run_api_key_command(&config, command),
+    }
+}
+
+fn run_api_key_command(config: &AppConfig, command: ApiKeyCommand) -> Result<()> {
+    let storage = open_storage(config)?;
+    
+    match command {
+        ApiKeyCommand::Create { name, scopes, daily_quota, monthly_quota, rate_limit, rate_window } => {
+            let rate_limit = rate_limit.map(|max_requests| RateLimitRule {
+                max_requests,
+                window_seconds: rate_window,
+            });
+            let (key, api_key) = ApiKey::generate(name, scopes, daily_quota, monthly_quota, rate_limit)?;
+            storage.store_api_key(&api_key)?;
+            
+            println!("Created API key {} ({})", api_key.id, api_key.name);
+            println!("{}", key);
+        }
+        ApiKeyCommand::List => {
+            for api_key in storage.list_api_keys()? {
+                let (today, month) = storage.api_key_usage(&api_key.id)?;
+                let scopes: Vec<_> = api_key.scopes.iter().map(|scope| scope.to_string()).collect();
+                println!(
+                    "{}  {:<20} scopes={}  claimed today={}/{} month={}/{}",
+                    api_key.id,
+                    api_key.name,
+                    scopes.join(","),
+                    today,
+                    api_key.daily_quota.map_or("-".to_string(), |q| q.to_string()),
+                    month,
+                    api_key.monthly_quota.map_or("-".to_string(), |q| q.to_string()),
+                );
+            }
+        }
+        ApiKeyCommand::Revoke { id } => {
+            if !storage.delete_api_key(&id)? {
+                anyhow::bail!("No API key with id {}", id);
+            }
+            println!("Revoked API key {}", id);
+        }
+    }
+    
+    Ok(())
 }
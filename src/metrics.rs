@@ -0,0 +1,133 @@
+use anyhow::Result;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+
+/// Batch durations range from well under a second to the batch timeout: 30 seconds for keypair
+/// pools, 600 seconds for mnemonic pools
+const BATCH_DURATION_BUCKETS: &[f64] = &[0.1, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0, 600.0];
+
+/// How a claim request ended, for `petaddr_claim_requests_total`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClaimOutcome {
+    Claimed,
+    /// Batch claim that got fewer addresses than it asked for
+    Partial,
+    /// Answered from an earlier request with the same idempotency key
+    Replayed,
+    /// The pool had no (or not enough) addresses
+    Empty,
+    QuotaExceeded,
+    Error,
+}
+
+impl ClaimOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ClaimOutcome::Claimed => "claimed",
+            ClaimOutcome::Partial => "partial",
+            ClaimOutcome::Replayed => "replayed",
+            ClaimOutcome::Empty => "empty",
+            ClaimOutcome::QuotaExceeded => "quota_exceeded",
+            ClaimOutcome::Error => "error",
+        }
+    }
+}
+
+/// Prometheus metrics of the server, rendered by `GET /metrics`
+pub struct Metrics {
+    registry: Registry,
+    pub pool_depth: IntGaugeVec,
+    pub pool_target: IntGaugeVec,
+    pub keys_generated: IntCounterVec,
+    pub search_attempts: IntCounterVec,
+    pub batch_duration: HistogramVec,
+    pub batch_timeouts: IntCounterVec,
+    pub claim_requests: IntCounterVec,
+    pub addresses_claimed: IntCounterVec,
+    pub rate_limited: IntCounterVec,
+    pub db_size: IntGauge,
+    pub http_request_duration: HistogramVec,
+}
+
+impl Metrics {
+    pub fn new() -> Result<Self> {
+        let registry = Registry::new_custom(Some("petaddr".to_string()), None)?;
+
+        let metrics = Self {
+            pool_depth: IntGaugeVec::new(
+                Opts::new("pool_depth", "Unclaimed addresses in the pool"),
+                &["pool"],
+            )?,
+            pool_target: IntGaugeVec::new(
+                Opts::new("pool_target", "Number of addresses the generator keeps in the pool"),
+                &["pool"],
+            )?,
+            keys_generated: IntCounterVec::new(
+                Opts::new("keys_generated_total", "Matching addresses generated and stored"),
+                &["pool"],
+            )?,
+            search_attempts: IntCounterVec::new(
+                Opts::new("search_attempts_total", "Keypairs tried while generating addresses"),
+                &["pool"],
+            )?,
+            batch_duration: HistogramVec::new(
+                HistogramOpts::new("batch_duration_seconds", "Time taken by a generation batch")
+                    .buckets(BATCH_DURATION_BUCKETS.to_vec()),
+                &["pool"],
+            )?,
+            batch_timeouts: IntCounterVec::new(
+                Opts::new("batch_timeouts_total", "Generation batches that hit the batch timeout"),
+                &["pool"],
+            )?,
+            claim_requests: IntCounterVec::new(
+                Opts::new("claim_requests_total", "Claim and lease requests by outcome"),
+                &["pool", "outcome"],
+            )?,
+            addresses_claimed: IntCounterVec::new(
+                Opts::new("addresses_claimed_total", "Addresses handed out by claims and leases"),
+                &["pool"],
+            )?,
+            rate_limited: IntCounterVec::new(
+                Opts::new("rate_limited_total", "Requests rejected by a rate limiter"),
+                &["limiter"],
+            )?,
+            db_size: IntGauge::new("db_size_bytes", "Size of the sled database on disk")?,
+            http_request_duration: HistogramVec::new(
+                HistogramOpts::new("http_request_duration_seconds", "HTTP request latency"),
+                &["method", "route", "status"],
+            )?,
+            registry,
+        };
+
+        metrics.registry.register(Box::new(metrics.pool_depth.clone()))?;
+        metrics.registry.register(Box::new(metrics.pool_target.clone()))?;
+        metrics.registry.register(Box::new(metrics.keys_generated.clone()))?;
+        metrics.registry.register(Box::new(metrics.search_attempts.clone()))?;
+        metrics.registry.register(Box::new(metrics.batch_duration.clone()))?;
+        metrics.registry.register(Box::new(metrics.batch_timeouts.clone()))?;
+        metrics.registry.register(Box::new(metrics.claim_requests.clone()))?;
+        metrics.registry.register(Box::new(metrics.addresses_claimed.clone()))?;
+        metrics.registry.register(Box::new(metrics.rate_limited.clone()))?;
+        metrics.registry.register(Box::new(metrics.db_size.clone()))?;
+        metrics.registry.register(Box::new(metrics.http_request_duration.clone()))?;
+
+        Ok(metrics)
+    }
+
+    /// Count a claim request and the addresses it handed out
+    pub fn record_claim(&self, pool: &str, outcome: ClaimOutcome, addresses: usize) {
+        self.claim_requests.with_label_values(&[pool, outcome.as_str()]).inc();
+        if addresses > 0 {
+            self.addresses_claimed.with_label_values(&[pool]).inc_by(addresses as u64);
+        }
+    }
+
+    /// Everything in the Prometheus text exposition format
+    pub fn render(&self) -> Result<String> {
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+}
@@ -0,0 +1,150 @@
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use dashmap::DashMap;
+use std::sync::Arc;
+
+use crate::metrics::Metrics;
+use crate::models::ApiResponse;
+use crate::pet::{ApiKey, PetStorage, Scope};
+use super::RateLimiter;
+
+/// API key a request was authenticated with, available to handlers as an extension
+#[derive(Clone)]
+pub struct AuthenticatedKey(pub ApiKey);
+
+/// Looks up API keys and enforces their own rate limits
+#[derive(Clone)]
+pub struct Authenticator {
+    storage: Arc<PetStorage>,
+    /// One limiter per key with its own rate limit, shared by all route groups
+    key_limiters: Arc<DashMap<String, RateLimiter>>,
+    rate_limit_message: Arc<str>,
+    metrics: Arc<Metrics>,
+}
+
+/// Authentication requirements of one route group
+#[derive(Clone)]
+pub struct RouteAuth {
+    authenticator: Authenticator,
+    scope: Scope,
+    allow_anonymous: bool,
+}
+
+impl Authenticator {
+    pub fn new(storage: Arc<PetStorage>, rate_limit_message: &str, metrics: Arc<Metrics>) -> Self {
+        Self {
+            storage,
+            key_limiters: Arc::new(DashMap::new()),
+            rate_limit_message: Arc::from(rate_limit_message),
+            metrics,
+        }
+    }
+
+    /// Requirements for a route group needing `scope`, optionally open to requests without a key
+    pub fn route(&self, scope: Scope, allow_anonymous: bool) -> RouteAuth {
+        RouteAuth {
+            authenticator: self.clone(),
+            scope,
+            allow_anonymous,
+        }
+    }
+
+    /// Resolve a presented key, `None` if it is unknown or does not match
+    pub fn authenticate(&self, key: &str) -> anyhow::Result<Option<ApiKey>> {
+        let Some(id) = ApiKey::parse_id(key) else {
+            return Ok(None);
+        };
+        Ok(self.storage.get_api_key(id)?.filter(|api_key| api_key.verify(key)))
+    }
+
+    fn limiter(&self, api_key: &ApiKey) -> Option<RateLimiter> {
+        let rule = api_key.rate_limit.as_ref()?;
+        let limiter = self.key_limiters
+            .entry(api_key.id.clone())
+            .or_insert_with(|| {
+                RateLimiter::from_rule(rule, &self.rate_limit_message).with_metrics(&self.metrics, "api_key")
+            });
+        Some(limiter.clone())
+    }
+
+    /// Drop the cached limiter of a deleted or changed key
+    pub fn forget(&self, id: &str) {
+        self.key_limiters.remove(id);
+    }
+}
+
+/// Key from `x-api-key`, or from an `Authorization: Bearer` header
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    if let Some(key) = headers.get("x-api-key").and_then(|v| v.to_str().ok()) {
+        return Some(key.trim());
+    }
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+fn error_response(status: StatusCode, message: &str) -> Response {
+    (status, Json(ApiResponse::<()>::error(status.as_u16() as i32, message))).into_response()
+}
+
+/// Apply with `axum::middleware::from_fn_with_state(authenticator.route(scope, anonymous), auth_middleware)`
+pub async fn auth_middleware(
+    State(route): State<RouteAuth>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let Some(key) = presented_key(request.headers()) else {
+        if route.allow_anonymous {
+            return next.run(request).await;
+        }
+        let mut response = error_response(StatusCode::UNAUTHORIZED, "API key required");
+        response.headers_mut().insert(
+            axum::http::header::WWW_AUTHENTICATE,
+            HeaderValue::from_static("Bearer"),
+        );
+        return response;
+    };
+
+    let api_key = match route.authenticator.authenticate(key) {
+        Ok(Some(api_key)) => api_key,
+        Ok(None) => return error_response(StatusCode::UNAUTHORIZED, "Invalid API key"),
+        Err(e) => {
+            tracing::error!("Failed to look up API key: {}", e);
+            return error_response(StatusCode::INTERNAL_SERVER_ERROR, "Failed to check API key");
+        }
+    };
+
+    if !api_key.has_scope(route.scope) {
+        return error_response(
+            StatusCode::FORBIDDEN,
+            &format!("API key lacks the '{}' scope", route.scope),
+        );
+    }
+
+    let decision = route.authenticator.limiter(&api_key).map(|limiter| {
+        let decision = limiter.check(&api_key.id);
+        (limiter, decision)
+    });
+    if let Some((limiter, decision)) = &decision {
+        if !decision.allowed {
+            tracing::warn!("Rate limit exceeded for API key {}", api_key.id);
+            limiter.record_rejection();
+            return decision.reject(limiter.message());
+        }
+    }
+
+    request.extensions_mut().insert(AuthenticatedKey(api_key));
+
+    let mut response = next.run(request).await;
+    if let Some((_, decision)) = decision {
+        decision.apply_headers(response.headers_mut());
+    }
+    response
+}
@@ -29,13 +29,22 @@ pub fn cors_layer() -> CorsLayer {
             axum::http::header::USER_AGENT,
             HeaderName::from_static("x-requested-with"),
             // Custom headers
-            HeaderName::from_static("x-request-id"),
+            super::REQUEST_ID,
             HeaderName::from_static("x-api-key"),
+            crate::handlers::IDEMPOTENCY_KEY,
         ]))
         // Exposed response headers
         .expose_headers([
             axum::http::header::CONTENT_LENGTH,
             axum::http::header::CONTENT_TYPE,
+            axum::http::header::RETRY_AFTER,
+            super::RATELIMIT_LIMIT,
+            super::RATELIMIT_REMAINING,
+            super::RATELIMIT_RESET,
+            super::REQUEST_ID,
+            crate::handlers::CLAIM_REQUESTED,
+            crate::handlers::CLAIM_COUNT,
+            crate::handlers::CLAIM_PARTIAL,
         ])
         // No credentials needed for development environment
         // Preflight request cache time
@@ -63,6 +72,7 @@ pub fn production_cors_layer(allowed_origins: Vec<String>) -> CorsLayer {
             axum::http::header::ACCEPT,
             axum::http::header::CONTENT_TYPE,
             HeaderName::from_static("x-api-key"),
+            crate::handlers::IDEMPOTENCY_KEY,
         ]))
         .allow_credentials(true)
         .max_age(std::time::Duration::from_secs(86400))
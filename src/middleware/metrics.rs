@@ -0,0 +1,33 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::metrics::Metrics;
+
+/// Apply with `axum::middleware::from_fn_with_state(metrics, http_metrics_middleware)`.
+/// Requests are labelled with their route template, so path parameters don't add series.
+pub async fn http_metrics_middleware(
+    State(metrics): State<Arc<Metrics>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = request.method().clone();
+
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    metrics
+        .http_request_duration
+        .with_label_values(&[method.as_str(), &route, response.status().as_str()])
+        .observe(started.elapsed().as_secs_f64());
+    response
+}
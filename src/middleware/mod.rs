@@ -1,7 +1,13 @@
+pub mod auth;
 pub mod cors;
 pub mod logging;
+pub mod metrics;
 pub mod rate_limit;
+pub mod request_id;
 
+pub use auth::*;
 pub use cors::*;
 pub use logging::*;
-pub use rate_limit::*;
\ No newline at end of file
+pub use metrics::*;
+pub use rate_limit::*;
+pub use request_id::*;
\ No newline at end of file
@@ -1,10 +1,12 @@
 use axum::{
-    extract::{Request, ConnectInfo},
-    http::StatusCode,
+    extract::{Request, ConnectInfo, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
     middleware::Next,
     response::{IntoResponse, Response},
+    Json,
 };
 use dashmap::DashMap;
+use prometheus::IntCounter;
 use std::{
     net::SocketAddr,
     sync::Arc,
@@ -12,11 +14,33 @@ use std::{
 };
 use tokio::time::sleep;
 
+use crate::config::RateLimitRule;
+use crate::metrics::Metrics;
+use crate::models::ApiResponse;
+use super::AuthenticatedKey;
+
+pub const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+pub const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+pub const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+
 #[derive(Clone)]
 pub struct RateLimiter {
     requests: Arc<DashMap<String, Vec<Instant>>>,
     max_requests: u32,
     window_duration: Duration,
+    message: Arc<str>,
+    /// `petaddr_rate_limited_total` for this limiter, when metrics are wired up
+    rejections: Option<IntCounter>,
+}
+
+/// Outcome of a rate limit check, with the numbers reported in the `RateLimit-*` headers
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time until the oldest request in the window expires
+    pub reset: Duration,
 }
 
 impl RateLimiter {
@@ -25,6 +49,11 @@ impl RateLimiter {
             requests: Arc::new(DashMap::new()),
             max_requests,
             window_duration: Duration::from_secs(window_seconds),
+            message: Arc::from(format!(
+                "Rate limit exceeded. Maximum {} requests per {} seconds allowed.",
+                max_requests, window_seconds
+            )),
+            rejections: None,
         };
         
         // Start cleanup task
@@ -36,22 +65,64 @@ impl RateLimiter {
         limiter
     }
     
+    /// Limiter for one route group; `{limit}` and `{window}` in `message` are filled in
+    pub fn from_rule(rule: &RateLimitRule, message: &str) -> Self {
+        let mut limiter = Self::new(rule.max_requests, rule.window_seconds);
+        limiter.message = Arc::from(
+            message
+                .replace("{limit}", &rule.max_requests.to_string())
+                .replace("{window}", &rule.window_seconds.to_string()),
+        );
+        limiter
+    }
+    
+    /// Count rejections of this limiter in `metrics` under the `limiter` label `name`
+    pub fn with_metrics(mut self, metrics: &Metrics, name: &str) -> Self {
+        self.rejections = Some(metrics.rate_limited.with_label_values(&[name]));
+        self
+    }
+    
     pub fn check_rate_limit(&self, ip: &str) -> bool {
+        self.check(ip).allowed
+    }
+    
+    /// Record a request from `key` if it is under the limit
+    pub fn check(&self, key: &str) -> RateLimitDecision {
         let now = Instant::now();
         let cutoff = now - self.window_duration;
         
-        let mut entry = self.requests.entry(ip.to_string()).or_insert_with(Vec::new);
+        let mut entry = self.requests.entry(key.to_string()).or_default();
         
         // Remove old requests
         entry.retain(|&time| time > cutoff);
         
         // Check if under limit
-        if entry.len() >= self.max_requests as usize {
-            false
-        } else {
+        let allowed = entry.len() < self.max_requests as usize;
+        if allowed {
             // Add current request
             entry.push(now);
-            true
+        }
+        
+        let reset = entry
+            .first()
+            .map(|&oldest| (oldest + self.window_duration).saturating_duration_since(now))
+            .unwrap_or_default();
+        
+        RateLimitDecision {
+            allowed,
+            limit: self.max_requests,
+            remaining: self.max_requests.saturating_sub(entry.len() as u32),
+            reset,
+        }
+    }
+    
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+    
+    pub fn record_rejection(&self) {
+        if let Some(rejections) = &self.rejections {
+            rejections.inc();
         }
     }
     
@@ -70,24 +141,64 @@ impl RateLimiter {
     }
 }
 
+impl RateLimitDecision {
+    /// Seconds until the window resets, rounded up so clients never retry too early
+    pub fn reset_seconds(&self) -> u64 {
+        self.reset.as_secs() + u64::from(self.reset.subsec_nanos() > 0)
+    }
+    
+    pub fn apply_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(RATELIMIT_LIMIT, HeaderValue::from(self.limit));
+        headers.insert(RATELIMIT_REMAINING, HeaderValue::from(self.remaining));
+        headers.insert(RATELIMIT_RESET, HeaderValue::from(self.reset_seconds()));
+    }
+    
+    /// `429` response with the standard JSON body, rate limit headers and `Retry-After`
+    pub fn reject(&self, message: &str) -> Response {
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(ApiResponse::<()>::error(StatusCode::TOO_MANY_REQUESTS.as_u16() as i32, message)),
+        ).into_response();
+        self.apply_headers(response.headers_mut());
+        response.headers_mut().insert(
+            axum::http::header::RETRY_AFTER,
+            HeaderValue::from(self.reset_seconds()),
+        );
+        response
+    }
+}
+
+/// Apply with `axum::middleware::from_fn_with_state(limiter, rate_limit_middleware)`
 pub async fn rate_limit_middleware(
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(limiter): State<RateLimiter>,
     request: Request,
     next: Next,
 ) -> Response {
-    let limiter = request
-        .extensions()
-        .get::<RateLimiter>()
-        .expect("RateLimiter not found in request extensions");
+    // Keys with their own rate limit were already checked by the auth middleware,
+    // other authenticated requests are counted per key instead of per IP
+    let client = match request.extensions().get::<AuthenticatedKey>() {
+        Some(AuthenticatedKey(api_key)) if api_key.rate_limit.is_some() => {
+            return next.run(request).await;
+        }
+        Some(AuthenticatedKey(api_key)) => format!("key:{}", api_key.id),
+        // Without connect info (e.g. a service not started with `into_make_service_with_connect_info`)
+        // all clients share one bucket rather than failing the request
+        None => request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string())
+            .unwrap_or_else(|| "unknown".to_string()),
+    };
     
-    let ip = addr.ip().to_string();
+    let decision = limiter.check(&client);
     
-    if !limiter.check_rate_limit(&ip) {
-        return (
-            StatusCode::TOO_MANY_REQUESTS,
-            "Rate limit exceeded. Maximum 10 requests per minute allowed.",
-        ).into_response();
+    if !decision.allowed {
+        tracing::warn!("Rate limit exceeded for {}", client);
+        limiter.record_rejection();
+        return decision.reject(limiter.message());
     }
     
-    next.run(request).await
-}
\ No newline at end of file
+    let mut response = next.run(request).await;
+    decision.apply_headers(response.headers_mut());
+    response
+}
@@ -0,0 +1,52 @@
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use rand::RngCore;
+use std::net::SocketAddr;
+
+pub const REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client supplied request id that is kept; longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Request id and client address, inserted as a request extension
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    pub request_id: String,
+    pub client_ip: Option<String>,
+}
+
+/// Apply with `axum::middleware::from_fn(request_context_middleware)`. Keeps the client's
+/// `X-Request-Id` when it sends a sensible one, generates one otherwise, and echoes it back.
+pub async fn request_context_middleware(mut request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            let mut id = [0u8; 16];
+            rand::thread_rng().fill_bytes(&mut id);
+            hex::encode(id)
+        });
+
+    let client_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string());
+
+    request.extensions_mut().insert(RequestContext {
+        request_id: request_id.clone(),
+        client_ip,
+    });
+
+    let mut response = next.run(request).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID, value);
+    }
+    response
+}
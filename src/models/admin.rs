@@ -0,0 +1,118 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeyRateLimit {
+    #[schema(example = 60)]
+    pub max_requests: u32,
+    #[schema(example = 60)]
+    pub window_seconds: u64,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    #[schema(example = "wallet-backend")]
+    pub name: String,
+    /// Any of `claim`, `status`, `jobs` and `admin`
+    #[schema(example = json!(["claim", "status"]))]
+    pub scopes: Vec<String>,
+    /// Addresses the key may claim per UTC day
+    pub daily_quota: Option<u64>,
+    /// Addresses the key may claim per UTC month
+    pub monthly_quota: Option<u64>,
+    /// Rate limit of the key, replacing the per-IP limit
+    pub rate_limit: Option<ApiKeyRateLimit>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiKeyResponse {
+    #[schema(example = "3f9c2a71b0de")]
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub daily_quota: Option<u64>,
+    pub monthly_quota: Option<u64>,
+    pub rate_limit: Option<ApiKeyRateLimit>,
+    /// Addresses claimed with the key today (UTC)
+    pub claimed_today: u64,
+    /// Addresses claimed with the key this month (UTC)
+    pub claimed_this_month: u64,
+    pub created_at: String,
+}
+
+/// Newly created key; `key` is only ever shown here
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreatedApiKeyResponse {
+    pub key: String,
+    pub api_key: ApiKeyResponse,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AuditQuery {
+    /// Earliest claim time to include (RFC 3339)
+    #[schema(example = "2025-09-01T00:00:00Z")]
+    pub from: Option<String>,
+    /// Latest claim time to include (RFC 3339)
+    #[schema(example = "2025-09-30T23:59:59Z")]
+    pub to: Option<String>,
+    /// Maximum number of entries, 100 by default
+    #[schema(example = 100)]
+    pub limit: Option<usize>,
+}
+
+/// An address that was handed out; the private key is never recorded
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AuditEntryResponse {
+    pub id: u64,
+    /// `claim` or `lease`
+    #[schema(example = "claim")]
+    pub kind: String,
+    pub pool: String,
+    pub address_id: u64,
+    pub public_key: String,
+    /// Id of the API key the address was handed out to
+    pub api_key: Option<String>,
+    pub client_ip: Option<String>,
+    /// `X-Request-Id` of the claiming request
+    pub request_id: Option<String>,
+    pub claimed_at: String,
+}
+
+/// New refill settings of a pool; omitted fields are left unchanged
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdatePoolRequest {
+    /// Number of addresses to keep in the pool
+    #[schema(example = 100)]
+    pub pool_size: Option<usize>,
+    /// Refilling starts when fewer addresses are left; not greater than `pool_size`.
+    /// `null` removes it, so refilling starts as soon as the pool is below its size.
+    #[serde(default, deserialize_with = "explicit_null")]
+    #[schema(value_type = Option<usize>, example = 80)]
+    pub low_watermark: Option<Option<usize>>,
+    /// Most addresses searched for at once, greater than 0
+    #[schema(example = 10)]
+    pub batch_size: Option<usize>,
+}
+
+/// Tell an explicit `null` (`Some(None)`) apart from an omitted field (`None`)
+fn explicit_null<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ClearPoolRequest {
+    /// Name of the pool being cleared, repeated to confirm the deletion
+    #[schema(example = "pet")]
+    pub confirm: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ClearPoolResponse {
+    pub pool: String,
+    /// Unclaimed addresses that were deleted
+    pub removed: usize,
+}
@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::SplitKeyResponse;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateJobRequest {
+    #[schema(example = "Dog")]
+    pub prefix: Option<String>,
+    #[schema(example = "Pet")]
+    pub suffix: Option<String>,
+    #[serde(default)]
+    pub case_insensitive: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct JobResponse {
+    #[schema(example = "5f0c1e9a7b3d4c2e8a6f1b0d9c7e5a3b")]
+    pub id: String,
+    #[schema(example = "Dog*Pet")]
+    pub pattern: String,
+    /// One of `running`, `completed` or `cancelled`
+    #[schema(example = "running")]
+    pub status: String,
+    /// Expected number of attempts needed to find a match
+    pub difficulty: f64,
+    pub attempts: u64,
+    /// Attempts per second, while running
+    pub rate: Option<f64>,
+    /// Expected seconds until a match at the current rate, while running
+    pub eta_seconds: Option<f64>,
+    pub created_at: String,
+    pub finished_at: Option<String>,
+    /// Found keypair, once the job has completed
+    pub result: Option<JobResultResponse>,
+    /// Client public point, for a split-key job
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_point: Option<String>,
+    /// Found partial scalar, once a split-key job has completed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partial_key: Option<SplitKeyResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct JobResultResponse {
+    pub public_key: String,
+    pub private_key: String,
+    pub address: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct EstimateQuery {
+    #[schema(example = "Dog")]
+    pub prefix: Option<String>,
+    #[schema(example = "Pet")]
+    pub suffix: Option<String>,
+    #[serde(default)]
+    pub case_insensitive: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EstimateResponse {
+    #[schema(example = "Dog*Pet")]
+    pub pattern: String,
+    /// Expected number of attempts needed to find a match
+    pub difficulty: f64,
+    /// Keypair attempts per second a new job would get, unknown until the generator has measured a rate
+    pub hash_rate: Option<f64>,
+    /// Expected seconds until a match at `hash_rate`
+    pub eta_seconds: Option<f64>,
+    /// Attempts, and seconds at `hash_rate`, within which a match is found with 50%, 90% and 99% probability
+    pub percentiles: Vec<EstimatePercentileResponse>,
+    /// Whether a job for this pattern is within the server's limits and would be accepted
+    pub job_allowed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EstimatePercentileResponse {
+    #[schema(example = 0.9)]
+    pub probability: f64,
+    pub attempts: f64,
+    pub eta_seconds: Option<f64>,
+}
@@ -1,7 +1,11 @@
 pub mod response;
 pub mod time;
 pub mod pet;
+pub mod job;
+pub mod admin;
 
 pub use response::*;
 pub use time::*;
-pub use pet::*;
\ No newline at end of file
+pub use pet::*;
+pub use job::*;
+pub use admin::*;
\ No newline at end of file
@@ -1,18 +1,197 @@
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use crate::pet::ExportedKey;
+
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct GetPetAddressResponse {
     pub id: u64,
     pub public_key: String,
-    pub private_key: String,
+    /// Secret key in `key_format`
+    pub private_key: ExportedKey,
+    /// `base58`, `solana-json`, `hex` or `base64`
+    #[schema(example = "base58")]
+    pub key_format: String,
     pub address: String,
     pub created_at: String,
+    /// BIP39 seed phrase, for addresses of mnemonic pools
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+    /// Path the keypair is derived along from `mnemonic`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "m/44'/501'/0'/0'")]
+    pub derivation_path: Option<String>,
+}
+
+/// Claimed address whose keypair is sealed to the client's X25519 public key
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SealedPetAddressResponse {
+    pub id: u64,
+    pub public_key: String,
+    pub address: String,
+    pub created_at: String,
+    /// Sealing scheme used for `sealed_keypair`
+    #[schema(example = "x25519-xsalsa20poly1305-sealedbox")]
+    pub encryption: String,
+    /// Base64 sealed box containing the 64-byte Solana keypair
+    pub sealed_keypair: String,
+    /// Base64 sealed box containing the BIP39 seed phrase, for addresses of mnemonic pools
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sealed_mnemonic: Option<String>,
+    /// Path the keypair is derived along from the sealed mnemonic
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(example = "m/44'/501'/0'/0'")]
+    pub derivation_path: Option<String>,
+}
+
+/// Plain response, or a sealed one when `recipient_key` was given
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum PetAddressPayload {
+    Plain(GetPetAddressResponse),
+    Sealed(SealedPetAddressResponse),
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ClaimQuery {
+    /// X25519 public key (hex or base64) to seal the returned keypair to
+    pub recipient_key: Option<String>,
+    /// Encoding of the returned secret key: `base58` (default), `solana-json`, `hex` or `base64`
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BatchClaimRequest {
+    /// Number of addresses to claim
+    #[schema(example = 20)]
+    pub count: usize,
+    /// Pool to claim from, the default pool when omitted
+    #[schema(example = "pet")]
+    pub pool: Option<String>,
+    /// X25519 public key (hex or base64) to seal every returned keypair to
+    pub recipient_key: Option<String>,
+    /// Encoding of the returned secret keys: `base58` (default), `solana-json`, `hex` or `base64`
+    #[schema(example = "solana-json")]
+    pub format: Option<String>,
+    /// Return as many addresses as are available instead of failing when the pool has fewer than `count`
+    #[serde(default)]
+    pub allow_partial: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BatchClaimResponse {
+    pub requested: usize,
+    pub claimed: usize,
+    /// Set when fewer addresses than requested were claimed
+    pub partial: bool,
+    pub addresses: Vec<PetAddressPayload>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SplitKeyRequest {
+    /// Client's ed25519 public point, base58 encoded like a Solana public key
+    pub public_point: String,
+    #[schema(example = "Dog")]
+    pub prefix: Option<String>,
+    #[schema(example = "Pet")]
+    pub suffix: Option<String>,
+    #[serde(default)]
+    pub case_insensitive: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SplitKeyResponse {
+    /// Address of the combined key
+    pub address: String,
+    /// Base58 partial scalar to add to the client's secret scalar
+    pub partial_scalar: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PetGeneratorStatusResponse {
+    pub pool: String,
+    /// `keypair` or `mnemonic`
+    #[schema(example = "keypair")]
+    pub kind: String,
+    pub pattern: String,
+    /// Addresses currently waiting in the pool
     pub total_addresses: usize,
+    /// Number of addresses the generator fills the pool up to, its high watermark
     pub pool_size: usize,
+    /// Refilling starts when fewer addresses are left
+    pub low_watermark: usize,
+    /// Most addresses searched for at once
+    pub batch_size: usize,
+    /// `running`, `paused` or `stopped`
+    #[schema(example = "running")]
+    pub state: String,
     pub generation_active: bool,
-}
\ No newline at end of file
+    /// Batch being searched for right now, if any
+    pub batch_in_flight: Option<GeneratorBatchResponse>,
+    /// Addresses generated for the pool since the database was created
+    pub generated_total: u64,
+    pub last_error: Option<GeneratorErrorResponse>,
+    /// Attempts per second of the current batch, or of the last one when idle
+    pub hash_rate: Option<f64>,
+    /// Expected seconds until the pool is full again at `hash_rate`
+    pub eta_seconds: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GeneratorBatchResponse {
+    /// Addresses the batch is searching for
+    pub size: usize,
+    /// Addresses of the batch stored so far
+    pub generated: usize,
+    pub attempts: u64,
+    pub started_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GeneratorErrorResponse {
+    pub message: String,
+    pub at: String,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct CreateLeaseRequest {
+    /// Pool to lease from, the default pool when omitted
+    #[schema(example = "pet")]
+    pub pool: Option<String>,
+    /// How long the address stays reserved, the configured default when omitted
+    #[schema(example = 300)]
+    pub ttl_seconds: Option<u64>,
+}
+
+/// Reserved address; its keypair is only handed out when the lease is confirmed
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LeaseResponse {
+    /// Id to confirm or release the lease with
+    pub lease_id: String,
+    pub pool: String,
+    pub expires_at: String,
+    pub address_id: u64,
+    pub public_key: String,
+    pub address: String,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct ConfirmLeaseRequest {
+    /// X25519 public key (hex or base64) to seal the returned keypair to
+    pub recipient_key: Option<String>,
+    /// Encoding of the returned secret key: `base58` (default), `solana-json`, `hex` or `base64`
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LeaseStatusResponse {
+    pub lease_id: String,
+    pub pool: String,
+    pub address_id: u64,
+    /// `confirmed` or `released`
+    #[schema(example = "confirmed")]
+    pub status: String,
+    /// Keypair of the address, once the lease is confirmed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<PetAddressPayload>,
+}
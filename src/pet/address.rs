@@ -1,11 +1,19 @@
 use serde::{Deserialize, Serialize};
 use solana_sdk::signature::{Keypair, Signer};
 
+use super::pattern::VanityPattern;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PetAddress {
     pub public_key: String,
     pub private_key: String,
     pub address: String,
+    /// BIP39 phrase the keypair was derived from, for addresses of mnemonic pools
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<String>,
+    /// Path the keypair was derived along from `mnemonic`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub derivation_path: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -15,8 +23,19 @@ pub struct PetAddressInfo {
     pub created_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// Who claimed an address and when; never holds the private key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimRecord {
+    pub address_id: u64,
+    pub pool: String,
+    pub public_key: String,
+    /// Id of the API key the address was claimed with
+    pub api_key: Option<String>,
+    pub claimed_at: chrono::DateTime<chrono::Utc>,
+}
+
 impl PetAddress {
-    pub fn generate() -> Option<Self> {
+    pub fn generate(pattern: &VanityPattern) -> Option<Self> {
         const MAX_ATTEMPTS: usize = 1_000_000; // Limit attempts to avoid infinite loops
         
         for attempt in 1..=MAX_ATTEMPTS {
@@ -24,21 +43,23 @@ impl PetAddress {
             let pubkey = keypair.pubkey();
             let address_str = pubkey.to_string();
             
-            if address_str.ends_with("Pet") {
+            if pattern.matches(&address_str) {
                 return Some(Self {
                     public_key: pubkey.to_string(),
                     private_key: bs58::encode(&keypair.to_bytes()).into_string(),
                     address: address_str,
+                    mnemonic: None,
+                    derivation_path: None,
                 });
             }
             
             // Log progress every 100k attempts
             if attempt % 100_000 == 0 {
-                tracing::debug!("Pet address generation attempt {}/{}", attempt, MAX_ATTEMPTS);
+                tracing::debug!("Pet address generation attempt {}/{} for pattern {}", attempt, MAX_ATTEMPTS, pattern);
             }
         }
         
-        tracing::warn!("Failed to generate Pet address after {} attempts", MAX_ATTEMPTS);
+        tracing::warn!("Failed to generate Pet address matching {} after {} attempts", pattern, MAX_ATTEMPTS);
         None
     }
     
@@ -48,6 +69,17 @@ impl PetAddress {
             public_key: pubkey.to_string(),
             private_key: bs58::encode(&keypair.to_bytes()).into_string(),
             address: pubkey.to_string(),
+            mnemonic: None,
+            derivation_path: None,
+        }
+    }
+    
+    /// Address of a keypair derived from a BIP39 `mnemonic` along `derivation_path`
+    pub fn from_mnemonic(keypair: &Keypair, mnemonic: &str, derivation_path: &str) -> Self {
+        Self {
+            mnemonic: Some(mnemonic.to_string()),
+            derivation_path: Some(derivation_path.to_string()),
+            ..Self::from_keypair(keypair)
         }
     }
     
@@ -0,0 +1,125 @@
+use anyhow::{bail, Result};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::config::RateLimitRule;
+
+/// Prefix of every issued key, so leaked keys are easy to recognise
+const KEY_PREFIX: &str = "pa_";
+
+/// What an API key may be used for; each route group requires one scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Claim,
+    Status,
+    Jobs,
+    Admin,
+}
+
+impl Scope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Claim => "claim",
+            Scope::Status => "status",
+            Scope::Jobs => "jobs",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+impl std::str::FromStr for Scope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "claim" => Ok(Scope::Claim),
+            "status" => Ok(Scope::Status),
+            "jobs" => Ok(Scope::Jobs),
+            "admin" => Ok(Scope::Admin),
+            other => bail!("Unknown scope '{}', expected claim, status, jobs or admin", other),
+        }
+    }
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Stored API key; only a hash of the secret is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// Public identifier, also embedded in the key itself
+    pub id: String,
+    pub name: String,
+    /// Hex SHA-256 of the full key
+    pub key_hash: String,
+    pub scopes: Vec<Scope>,
+    /// Addresses this key may claim per UTC day
+    pub daily_quota: Option<u64>,
+    /// Addresses this key may claim per UTC month
+    pub monthly_quota: Option<u64>,
+    /// Request rate limit for this key, replacing the per-IP limit
+    pub rate_limit: Option<RateLimitRule>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ApiKey {
+    /// Create a key record, returning it with the plaintext key, which is not stored anywhere
+    pub fn generate(
+        name: String,
+        scopes: Vec<Scope>,
+        daily_quota: Option<u64>,
+        monthly_quota: Option<u64>,
+        rate_limit: Option<RateLimitRule>,
+    ) -> Result<(String, Self)> {
+        if scopes.is_empty() {
+            bail!("An API key needs at least one scope");
+        }
+
+        let mut id = [0u8; 6];
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut id);
+        rand::thread_rng().fill_bytes(&mut secret);
+
+        let id = hex::encode(id);
+        let key = format!("{}{}_{}", KEY_PREFIX, id, bs58::encode(secret).into_string());
+
+        let record = Self {
+            id,
+            name,
+            key_hash: hash_key(&key),
+            scopes,
+            daily_quota,
+            monthly_quota,
+            rate_limit,
+            created_at: chrono::Utc::now(),
+        };
+
+        Ok((key, record))
+    }
+
+    /// Id embedded in a presented key, used to look up its record
+    pub fn parse_id(key: &str) -> Option<&str> {
+        let (id, secret) = key.strip_prefix(KEY_PREFIX)?.split_once('_')?;
+        (!id.is_empty() && !secret.is_empty()).then_some(id)
+    }
+
+    /// Compare in constant time, so response timing says nothing about the stored hash
+    pub fn verify(&self, key: &str) -> bool {
+        hash_key(key).as_bytes().ct_eq(self.key_hash.as_bytes()).into()
+    }
+
+    /// Admin keys can use every route group
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope) || self.scopes.contains(&Scope::Admin)
+    }
+}
+
+fn hash_key(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
@@ -0,0 +1,306 @@
+//! Passphrase-encrypted archives of pooled addresses, for moving keys between databases.
+//!
+//! An archive is a text file of two lines. The first is the JSON [`ArchiveManifest`],
+//! readable without the passphrase; the second is the base64 XChaCha20-Poly1305
+//! ciphertext of an NDJSON body holding one [`ArchiveRecord`] per line. The key is
+//! derived from the passphrase with Argon2id, and the manifest line is authenticated
+//! along with the body, so neither can be altered without the import failing.
+
+use anyhow::{anyhow, bail, Context, Result};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use solana_sdk::signature::Signer;
+use std::collections::{BTreeMap, HashSet};
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use super::address::PetAddressInfo;
+use super::mnemonic::derive_keypair;
+use super::pool::{PetPool, PoolKind};
+use super::storage::PetStorage;
+
+/// Value of [`ArchiveManifest::format`]
+pub const ARCHIVE_FORMAT: &str = "petaddr-archive";
+const ARCHIVE_VERSION: u32 = 1;
+const CIPHER: &str = "xchacha20poly1305";
+const KDF_ALGORITHM: &str = "argon2id";
+const SALT_BYTES: usize = 16;
+
+/// Clear text header of an archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format: String,
+    pub version: u32,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// Records in the archive
+    pub count: usize,
+    /// Records per pool
+    pub pools: BTreeMap<String, usize>,
+    /// `sha256:` followed by the hex digest of the decrypted NDJSON body
+    pub checksum: String,
+    pub cipher: String,
+    /// Base64 nonce of the body ciphertext
+    pub nonce: String,
+    pub kdf: KdfParams,
+}
+
+/// How the archive key was derived from the passphrase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub algorithm: String,
+    /// Base64 salt
+    pub salt: String,
+    pub memory_kib: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+/// One line of the archive body: a pooled address and the pool it was exported from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveRecord {
+    pub pool: String,
+    #[serde(flatten)]
+    pub info: PetAddressInfo,
+}
+
+/// What [`import_archive`] did with the records of an archive
+#[derive(Debug, Default)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// Records whose public key this database already holds or has handed out
+    pub duplicates: usize,
+    /// Records not matching the pattern of the pool they were imported into
+    pub mismatched: usize,
+}
+
+/// Read the archive passphrase from `file` when given, otherwise from the `env` variable
+pub fn load_passphrase(file: Option<&Path>, env: &str) -> Result<String> {
+    let passphrase = match file {
+        Some(path) => std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read passphrase file {}", path.display()))?
+            .trim_end_matches(['\r', '\n'])
+            .to_string(),
+        None => std::env::var(env)
+            .map_err(|_| anyhow!("Set ${} or pass --passphrase-file to encrypt the archive", env))?,
+    };
+
+    if passphrase.is_empty() {
+        bail!("Archive passphrase must not be empty");
+    }
+    Ok(passphrase)
+}
+
+/// Encrypt `records` with `passphrase` and write them as an archive
+pub fn write_archive<W: Write>(mut writer: W, records: &[ArchiveRecord], passphrase: &str) -> Result<ArchiveManifest> {
+    let mut body = Vec::new();
+    let mut pools = BTreeMap::new();
+    for record in records {
+        serde_json::to_writer(&mut body, record).context("Failed to serialize archive record")?;
+        body.push(b'\n');
+        *pools.entry(record.pool.clone()).or_insert(0) += 1;
+    }
+
+    let mut salt = [0u8; SALT_BYTES];
+    OsRng.fill_bytes(&mut salt);
+    let kdf = KdfParams {
+        algorithm: KDF_ALGORITHM.to_string(),
+        salt: BASE64.encode(salt),
+        memory_kib: Params::DEFAULT_M_COST,
+        iterations: Params::DEFAULT_T_COST,
+        parallelism: Params::DEFAULT_P_COST,
+    };
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+    let manifest = ArchiveManifest {
+        format: ARCHIVE_FORMAT.to_string(),
+        version: ARCHIVE_VERSION,
+        created_at: chrono::Utc::now(),
+        count: records.len(),
+        pools,
+        checksum: checksum(&body),
+        cipher: CIPHER.to_string(),
+        nonce: BASE64.encode(nonce),
+        kdf,
+    };
+    let header = serde_json::to_string(&manifest).context("Failed to serialize archive manifest")?;
+
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &manifest.kdf)?);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: &body, aad: header.as_bytes() })
+        .map_err(|_| anyhow!("Failed to encrypt archive"))?;
+
+    writeln!(writer, "{}", header)?;
+    writeln!(writer, "{}", BASE64.encode(ciphertext))?;
+    writer.flush()?;
+
+    Ok(manifest)
+}
+
+/// Decrypt an archive and check it against its manifest
+pub fn read_archive<R: BufRead>(reader: R, passphrase: &str) -> Result<(ArchiveManifest, Vec<ArchiveRecord>)> {
+    let (manifest, header, ciphertext) = read_parts(reader)?;
+
+    let nonce = BASE64.decode(&manifest.nonce).context("Invalid archive nonce encoding")?;
+    if nonce.len() != 24 {
+        bail!("Invalid archive nonce length {}", nonce.len());
+    }
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &manifest.kdf)?);
+    let body = cipher
+        .decrypt(XNonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: header.as_bytes() })
+        .map_err(|_| anyhow!("Failed to decrypt archive, wrong passphrase or the file was modified"))?;
+
+    if checksum(&body) != manifest.checksum {
+        bail!("Archive checksum does not match its manifest");
+    }
+
+    let records = body
+        .split(|&b| b == b'\n')
+        .filter(|line| !line.is_empty())
+        .enumerate()
+        .map(|(i, line)| {
+            serde_json::from_slice::<ArchiveRecord>(line)
+                .with_context(|| format!("Invalid archive record on line {}", i + 1))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if records.len() != manifest.count {
+        bail!("Archive holds {} records but its manifest lists {}", records.len(), manifest.count);
+    }
+
+    Ok((manifest, records))
+}
+
+/// Every unclaimed address of `pools`, ready for [`write_archive`]
+pub fn export_records(storage: &PetStorage, pools: &[String]) -> Result<Vec<ArchiveRecord>> {
+    let mut records = Vec::new();
+    for pool in pools {
+        for info in storage.list_addresses(pool)? {
+            records.push(ArchiveRecord { pool: pool.clone(), info });
+        }
+    }
+    Ok(records)
+}
+
+/// Store the records of an archive in `storage`.
+///
+/// Every record goes to the pool it was exported from, or to `target_pool` when given, which
+/// must be one of `pools` and of the same kind. Records whose public key the database already knows are skipped,
+/// as are records not matching the pattern of their pool. Keypairs and ids are checked before
+/// anything is written.
+pub fn import_archive(
+    storage: &PetStorage,
+    pools: &[PetPool],
+    records: &[ArchiveRecord],
+    target_pool: Option<&str>,
+    preserve_ids: bool,
+) -> Result<ImportSummary> {
+    let last_id = storage.last_id()?;
+    let mut known = storage.known_public_keys()?;
+    let mut ids = HashSet::new();
+    let mut summary = ImportSummary::default();
+    let mut accepted = Vec::with_capacity(records.len());
+    for record in records {
+        let name = target_pool.unwrap_or(&record.pool);
+        let pool = pools
+            .iter()
+            .find(|pool| pool.name == name)
+            .with_context(|| format!("Pool \"{}\" is not configured; use --pool to import into another one", name))?;
+
+        verify_record(record)?;
+        let kind = if record.info.address.mnemonic.is_some() { PoolKind::Mnemonic } else { PoolKind::Keypair };
+        if kind != pool.kind {
+            bail!(
+                "Address {} is a {} address but pool \"{}\" holds {} addresses",
+                record.info.id,
+                kind.as_str(),
+                pool.name,
+                pool.kind.as_str()
+            );
+        }
+        if !pool.pattern.matches(&record.info.address.address) {
+            summary.mismatched += 1;
+            continue;
+        }
+        if !known.insert(record.info.address.public_key.clone()) {
+            summary.duplicates += 1;
+            continue;
+        }
+        if preserve_ids && (record.info.id <= last_id || !ids.insert(record.info.id)) {
+            bail!(
+                "Address id {} is already in use in this database; import without --preserve-ids to renumber",
+                record.info.id
+            );
+        }
+        accepted.push((record, pool));
+    }
+
+    for (record, pool) in accepted {
+        storage.import_address(&pool.name, &record.info, preserve_ids)?;
+        summary.imported += 1;
+    }
+    storage.flush()?;
+
+    Ok(summary)
+}
+
+/// Check that the keypair (and mnemonic) of a record belong to its public key
+fn verify_record(record: &ArchiveRecord) -> Result<()> {
+    let address = &record.info.address;
+    let keypair = address
+        .to_keypair()
+        .map_err(|e| anyhow!("Address {} has an invalid private key: {}", record.info.id, e))?;
+    if keypair.pubkey().to_string() != address.public_key {
+        bail!("Private key of address {} does not match its public key", record.info.id);
+    }
+
+    if let (Some(mnemonic), Some(path)) = (&address.mnemonic, &address.derivation_path) {
+        let derived = derive_keypair(mnemonic, path)
+            .with_context(|| format!("Address {} has an invalid mnemonic", record.info.id))?;
+        if derived.pubkey() != keypair.pubkey() {
+            bail!("Mnemonic of address {} does not derive its public key", record.info.id);
+        }
+    }
+
+    Ok(())
+}
+
+fn read_parts<R: BufRead>(reader: R) -> Result<(ArchiveManifest, String, Vec<u8>)> {
+    let mut lines = reader.lines();
+    let header = lines.next().context("Archive is empty")??;
+    let manifest: ArchiveManifest = serde_json::from_str(&header).context("Invalid archive manifest")?;
+
+    if manifest.format != ARCHIVE_FORMAT {
+        bail!("Not a PetAddr archive (format \"{}\")", manifest.format);
+    }
+    if manifest.version != ARCHIVE_VERSION {
+        bail!("Unsupported archive version {}", manifest.version);
+    }
+    if manifest.cipher != CIPHER || manifest.kdf.algorithm != KDF_ALGORITHM {
+        bail!("Unsupported archive encryption {} / {}", manifest.cipher, manifest.kdf.algorithm);
+    }
+
+    let body = lines.next().context("Archive has no body")??;
+    let ciphertext = BASE64.decode(body.trim()).context("Invalid archive body encoding")?;
+
+    Ok((manifest, header, ciphertext))
+}
+
+fn derive_key(passphrase: &str, kdf: &KdfParams) -> Result<Key> {
+    let salt = BASE64.decode(&kdf.salt).context("Invalid archive salt encoding")?;
+    let params = Params::new(kdf.memory_kib, kdf.iterations, kdf.parallelism, Some(32))
+        .map_err(|e| anyhow!("Invalid archive key derivation parameters: {}", e))?;
+
+    let mut key = Key::default();
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| anyhow!("Failed to derive archive key: {}", e))?;
+    Ok(key)
+}
+
+fn checksum(body: &[u8]) -> String {
+    format!("sha256:{}", hex::encode(Sha256::digest(body)))
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use super::storage::PetStorage;
+
+/// How often audit entries past the retention period are deleted
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// How an address was handed out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditKind {
+    Claim,
+    Lease,
+}
+
+impl AuditKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditKind::Claim => "claim",
+            AuditKind::Lease => "lease",
+        }
+    }
+}
+
+/// Who is claiming, recorded in the audit log with every address handed out
+#[derive(Debug, Clone, Default)]
+pub struct Claimant {
+    /// Id of the API key the request was made with
+    pub api_key: Option<String>,
+    pub client_ip: Option<String>,
+    pub request_id: Option<String>,
+}
+
+/// One address handed out; never holds the private key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: u64,
+    pub kind: AuditKind,
+    pub pool: String,
+    pub address_id: u64,
+    pub public_key: String,
+    pub api_key: Option<String>,
+    pub client_ip: Option<String>,
+    pub request_id: Option<String>,
+    pub claimed_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Periodically delete audit entries older than `retention`
+pub fn spawn_audit_purge(storage: Arc<PetStorage>, retention: chrono::Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            ticker.tick().await;
+
+            match storage.purge_audit(chrono::Utc::now() - retention) {
+                Ok(0) => {}
+                Ok(purged) => info!("Purged {} audit entries past retention", purged),
+                Err(e) => error!("Failed to purge audit entries: {}", e),
+            }
+        }
+    })
+}
@@ -0,0 +1,187 @@
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::config::EncryptionConfig;
+
+/// Master key (key-encryption key) used to wrap the per-record data keys
+pub struct MasterKey {
+    version: u32,
+    cipher: XChaCha20Poly1305,
+}
+
+/// A secret encrypted with its own data key, which is in turn wrapped by a master key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedSecret {
+    /// Version of the master key that wrapped `wrapped_key`
+    pub key_version: u32,
+    pub wrapped_key: String,
+    pub key_nonce: String,
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+impl MasterKey {
+    pub fn from_bytes(version: u32, bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 32 {
+            bail!("Master key must be 32 bytes, got {}", bytes.len());
+        }
+
+        Ok(Self {
+            version,
+            cipher: XChaCha20Poly1305::new(Key::from_slice(bytes)),
+        })
+    }
+
+    /// Parse a hex or base64 encoded 32-byte key
+    pub fn parse(version: u32, encoded: &str) -> Result<Self> {
+        let encoded = encoded.trim();
+        let bytes = hex::decode(encoded)
+            .or_else(|_| BASE64.decode(encoded))
+            .map_err(|_| anyhow!("Master key must be 32 bytes encoded as hex or base64"))?;
+        Self::from_bytes(version, &bytes)
+    }
+
+    pub fn from_file<P: AsRef<Path>>(version: u32, path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let encoded = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read master key file {}", path.display()))?;
+        Self::parse(version, &encoded)
+    }
+
+    /// Load the master key configured in `[encryption]`, the environment variable taking precedence
+    pub fn load(config: &EncryptionConfig) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+
+        if let Ok(encoded) = std::env::var(&config.key_env) {
+            if !encoded.trim().is_empty() {
+                return Self::parse(config.key_version, &encoded)
+                    .with_context(|| format!("Invalid master key in ${}", config.key_env))
+                    .map(Some);
+            }
+        }
+
+        match &config.key_file {
+            Some(path) => Self::from_file(config.key_version, path).map(Some),
+            None => bail!(
+                "Encryption is enabled but neither ${} nor encryption.key_file is set",
+                config.key_env
+            ),
+        }
+    }
+
+    /// A new random key, hex encoded
+    pub fn generate_encoded() -> String {
+        hex::encode(XChaCha20Poly1305::generate_key(&mut OsRng))
+    }
+
+    /// Write a new random key to `path`, readable by the owner only; never overwrites
+    pub fn generate_file<P: AsRef<Path>>(path: P) -> Result<()> {
+        use std::io::Write;
+
+        let path = path.as_ref();
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+
+        let mut file = options
+            .open(path)
+            .with_context(|| format!("Failed to create key file {}", path.display()))?;
+        file.write_all(Self::generate_encoded().as_bytes())?;
+        Ok(())
+    }
+
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Encrypt `plaintext` under a fresh data key; `aad` binds the ciphertext to its record
+    pub fn seal(&self, plaintext: &[u8], aad: &[u8]) -> Result<SealedSecret> {
+        let data_key = XChaCha20Poly1305::generate_key(&mut OsRng);
+        let data_cipher = XChaCha20Poly1305::new(&data_key);
+
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = data_cipher
+            .encrypt(&nonce, Payload { msg: plaintext, aad })
+            .map_err(|_| anyhow!("Failed to encrypt secret"))?;
+
+        let (wrapped_key, key_nonce) = self.wrap(&data_key)?;
+
+        Ok(SealedSecret {
+            key_version: self.version,
+            wrapped_key,
+            key_nonce,
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    pub fn open(&self, sealed: &SealedSecret, aad: &[u8]) -> Result<Vec<u8>> {
+        let data_key = self.unwrap(sealed)?;
+        let data_cipher = XChaCha20Poly1305::new(&data_key);
+
+        let nonce = decode_nonce(&sealed.nonce)?;
+        let ciphertext = BASE64.decode(&sealed.ciphertext).context("Invalid ciphertext encoding")?;
+        data_cipher
+            .decrypt(&nonce, Payload { msg: &ciphertext, aad })
+            .map_err(|_| anyhow!("Failed to decrypt secret, the record may have been tampered with"))
+    }
+
+    /// Re-wrap the data key of `sealed` under `new_key` without touching the ciphertext
+    pub fn rewrap(&self, sealed: &SealedSecret, new_key: &MasterKey) -> Result<SealedSecret> {
+        let data_key = self.unwrap(sealed)?;
+        let (wrapped_key, key_nonce) = new_key.wrap(&data_key)?;
+
+        Ok(SealedSecret {
+            key_version: new_key.version,
+            wrapped_key,
+            key_nonce,
+            ..sealed.clone()
+        })
+    }
+
+    fn wrap(&self, data_key: &Key) -> Result<(String, String)> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let wrapped = self.cipher
+            .encrypt(&nonce, data_key.as_slice())
+            .map_err(|_| anyhow!("Failed to wrap data key"))?;
+        Ok((BASE64.encode(wrapped), BASE64.encode(nonce)))
+    }
+
+    fn unwrap(&self, sealed: &SealedSecret) -> Result<Key> {
+        if sealed.key_version != self.version {
+            bail!(
+                "Secret was sealed with master key version {}, but version {} is loaded",
+                sealed.key_version,
+                self.version
+            );
+        }
+
+        let nonce = decode_nonce(&sealed.key_nonce)?;
+        let wrapped = BASE64.decode(&sealed.wrapped_key).context("Invalid wrapped key encoding")?;
+        let data_key = self.cipher
+            .decrypt(&nonce, wrapped.as_slice())
+            .map_err(|_| anyhow!("Failed to unwrap data key, wrong master key?"))?;
+        if data_key.len() != 32 {
+            bail!("Unwrapped data key has invalid length {}", data_key.len());
+        }
+        Ok(*Key::from_slice(&data_key))
+    }
+}
+
+fn decode_nonce(encoded: &str) -> Result<XNonce> {
+    let bytes = BASE64.decode(encoded).context("Invalid nonce encoding")?;
+    if bytes.len() != 24 {
+        bail!("Invalid nonce length {}", bytes.len());
+    }
+    Ok(*XNonce::from_slice(&bytes))
+}
@@ -0,0 +1,80 @@
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::{STANDARD, URL_SAFE, URL_SAFE_NO_PAD}, Engine};
+use crypto_box::aead::OsRng;
+use crypto_box::{PublicKey, SecretKey};
+use solana_sdk::signature::Keypair;
+
+use super::address::PetAddress;
+
+/// Name of the scheme used to seal claimed keys, reported alongside the ciphertext
+pub const SEALED_BOX_SCHEME: &str = "x25519-xsalsa20poly1305-sealedbox";
+
+/// Parse a client supplied X25519 public key, encoded as hex or (URL-safe) base64
+pub fn parse_recipient_key(encoded: &str) -> Result<PublicKey> {
+    let bytes = decode_key(encoded)?;
+    PublicKey::from_slice(&bytes).map_err(|_| anyhow!("Recipient key must be 32 bytes"))
+}
+
+/// Seal the 64-byte keypair of `address` to `recipient`, returning base64 ciphertext
+pub fn seal_keypair(recipient: &PublicKey, address: &PetAddress) -> Result<String> {
+    let keypair_bytes = bs58::decode(&address.private_key)
+        .into_vec()
+        .context("Stored private key is not valid base58")?;
+    let sealed = recipient
+        .seal(&mut OsRng, &keypair_bytes)
+        .map_err(|_| anyhow!("Failed to seal keypair"))?;
+    Ok(STANDARD.encode(sealed))
+}
+
+/// Seal the BIP39 phrase of a mnemonic-backed address to `recipient`, returning base64 ciphertext
+pub fn seal_mnemonic(recipient: &PublicKey, address: &PetAddress) -> Result<Option<String>> {
+    let Some(mnemonic) = &address.mnemonic else {
+        return Ok(None);
+    };
+    let sealed = recipient
+        .seal(&mut OsRng, mnemonic.as_bytes())
+        .map_err(|_| anyhow!("Failed to seal mnemonic"))?;
+    Ok(Some(STANDARD.encode(sealed)))
+}
+
+/// Decrypt a mnemonic sealed by [`seal_mnemonic`] with the recipient's secret key
+pub fn open_sealed_mnemonic(secret: &SecretKey, sealed: &str) -> Result<String> {
+    let ciphertext = STANDARD.decode(sealed).context("Sealed mnemonic is not valid base64")?;
+    let mnemonic = secret
+        .unseal(&ciphertext)
+        .map_err(|_| anyhow!("Failed to open sealed mnemonic, wrong recipient key?"))?;
+    String::from_utf8(mnemonic).context("Sealed mnemonic is not valid UTF-8")
+}
+
+/// Decrypt a keypair sealed by [`seal_keypair`] with the recipient's secret key
+pub fn open_sealed_keypair(secret: &SecretKey, sealed: &str) -> Result<Keypair> {
+    let ciphertext = STANDARD.decode(sealed).context("Sealed keypair is not valid base64")?;
+    let keypair_bytes = secret
+        .unseal(&ciphertext)
+        .map_err(|_| anyhow!("Failed to open sealed keypair, wrong recipient key?"))?;
+    Keypair::try_from(keypair_bytes.as_slice()).map_err(|e| anyhow!("Invalid keypair: {}", e))
+}
+
+/// A new recipient key pair, the public half base64 encoded for the `recipient_key` parameter
+pub fn generate_recipient_key() -> (SecretKey, String) {
+    let secret = SecretKey::generate(&mut OsRng);
+    let public = STANDARD.encode(secret.public_key().as_bytes());
+    (secret, public)
+}
+
+fn decode_key(encoded: &str) -> Result<Vec<u8>> {
+    let encoded = encoded.trim();
+    if encoded.len() == 64 {
+        if let Ok(bytes) = hex::decode(encoded) {
+            return Ok(bytes);
+        }
+    }
+
+    for engine in [&URL_SAFE_NO_PAD, &URL_SAFE, &STANDARD] {
+        if let Ok(bytes) = engine.decode(encoded) {
+            return Ok(bytes);
+        }
+    }
+
+    bail!("Recipient key must be hex or base64 encoded")
+}
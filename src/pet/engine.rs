@@ -0,0 +1,298 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+use solana_sdk::signature::{Keypair, Signer};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use super::address::PetAddress;
+use super::pattern::VanityPattern;
+
+/// Attempts a worker makes on one job before checking the queue again
+const CHUNK_SIZE: u64 = 4096;
+
+/// A kind of vanity search the engine can run
+pub trait KeySearch: Send + Sync + 'static {
+    type Output: Send + 'static;
+
+    /// Try up to `max_attempts` candidates, passing matches to `found` until it returns false.
+    /// Returns the number of attempts made.
+    fn search_chunk(&self, max_attempts: u64, found: &mut dyn FnMut(Self::Output) -> bool) -> u64;
+}
+
+/// Plain keypair search: random keypairs whose public key matches a pattern
+pub struct KeypairSearch {
+    pub pattern: VanityPattern,
+}
+
+impl KeySearch for KeypairSearch {
+    type Output = PetAddress;
+
+    fn search_chunk(&self, max_attempts: u64, found: &mut dyn FnMut(PetAddress) -> bool) -> u64 {
+        for attempt in 1..=max_attempts {
+            let keypair = Keypair::new();
+            let address = keypair.pubkey().to_string();
+            if self.pattern.matches(&address) && !found(PetAddress::from_keypair(&keypair)) {
+                return attempt;
+            }
+        }
+        max_attempts
+    }
+}
+
+/// Brute-force key search running on dedicated OS threads, away from the async runtime
+pub struct SearchEngine {
+    shared: Arc<Shared>,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+    worker_count: usize,
+}
+
+struct Shared {
+    queue: Mutex<VecDeque<Arc<dyn Job>>>,
+    available: Condvar,
+    shutdown: AtomicBool,
+}
+
+/// Type-erased queued search, so different search kinds share the workers
+trait Job: Send + Sync {
+    fn is_finished(&self) -> bool;
+    fn run(&self, max_attempts: u64);
+}
+
+struct SearchJob<S: KeySearch> {
+    search: S,
+    state: Arc<JobState>,
+    sender: mpsc::Sender<S::Output>,
+}
+
+struct JobState {
+    remaining: AtomicUsize,
+    attempts: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+/// Receiving end of a submitted search; dropping it cancels the search
+pub struct SearchHandle<T = PetAddress> {
+    state: Arc<JobState>,
+    receiver: mpsc::Receiver<T>,
+}
+
+/// Cloneable view of a running search, for watching progress or cancelling it
+/// from somewhere other than the task waiting on the [`SearchHandle`]
+#[derive(Clone)]
+pub struct SearchControl {
+    state: Arc<JobState>,
+}
+
+impl SearchEngine {
+    /// Spawn `worker_threads` search threads (0 = one per CPU) running at the given niceness
+    pub fn new(worker_threads: usize, nice: i32) -> Self {
+        let worker_count = if worker_threads == 0 {
+            std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+        } else {
+            worker_threads
+        };
+
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(VecDeque::new()),
+            available: Condvar::new(),
+            shutdown: AtomicBool::new(false),
+        });
+
+        let mut workers = Vec::with_capacity(worker_count);
+        for i in 0..worker_count {
+            let shared = Arc::clone(&shared);
+            let handle = std::thread::Builder::new()
+                .name(format!("pet-search-{}", i))
+                .spawn(move || {
+                    set_thread_niceness(nice);
+                    shared.run_worker();
+                })
+                .expect("Failed to spawn search worker thread");
+            workers.push(handle);
+        }
+
+        info!("Started search engine with {} worker threads (nice {})", worker_count, nice);
+
+        Self {
+            shared,
+            workers: Mutex::new(workers),
+            worker_count,
+        }
+    }
+
+    pub fn worker_count(&self) -> usize {
+        self.worker_count
+    }
+
+    /// Queue a search for `count` keypairs matching `pattern`
+    pub fn submit(&self, pattern: VanityPattern, count: usize) -> SearchHandle {
+        self.submit_search(KeypairSearch { pattern }, count)
+    }
+
+    /// Queue any kind of search for `count` results
+    pub fn submit_search<S: KeySearch>(&self, search: S, count: usize) -> SearchHandle<S::Output> {
+        let (sender, receiver) = mpsc::channel(count.max(1));
+        let state = Arc::new(JobState {
+            remaining: AtomicUsize::new(count),
+            attempts: AtomicU64::new(0),
+            cancelled: AtomicBool::new(false),
+        });
+        let job = Arc::new(SearchJob {
+            search,
+            state: Arc::clone(&state),
+            sender,
+        });
+
+        self.shared.queue.lock().unwrap().push_back(job);
+        self.shared.available.notify_all();
+
+        SearchHandle { state, receiver }
+    }
+
+    /// Stop all workers and wait for them to finish their current chunk
+    pub fn shutdown(&self) {
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+        self.shared.available.notify_all();
+
+        let workers: Vec<_> = self.workers.lock().unwrap().drain(..).collect();
+        for worker in workers {
+            if worker.join().is_err() {
+                warn!("Search worker thread panicked");
+            }
+        }
+    }
+}
+
+impl Drop for SearchEngine {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+impl Shared {
+    fn run_worker(&self) {
+        loop {
+            let job = {
+                let mut queue = self.queue.lock().unwrap();
+                loop {
+                    if self.shutdown.load(Ordering::SeqCst) {
+                        return;
+                    }
+
+                    // Finished jobs are dropped here, which closes their channel
+                    queue.retain(|job| !job.is_finished());
+
+                    // Rotate so concurrent jobs get a fair share of the workers
+                    if let Some(job) = queue.pop_front() {
+                        queue.push_back(Arc::clone(&job));
+                        break job;
+                    }
+
+                    queue = self.available.wait(queue).unwrap();
+                }
+            };
+
+            job.run(CHUNK_SIZE);
+        }
+    }
+}
+
+impl<S: KeySearch> Job for SearchJob<S> {
+    fn is_finished(&self) -> bool {
+        self.state.cancelled.load(Ordering::Relaxed)
+            || self.state.remaining.load(Ordering::Relaxed) == 0
+            || self.sender.is_closed()
+    }
+
+    fn run(&self, max_attempts: u64) {
+        if self.is_finished() {
+            return;
+        }
+
+        let mut results = Vec::new();
+        let attempts = self.search.search_chunk(max_attempts, &mut |output| {
+            // Only hand out as many results as were requested, even if several workers hit at once
+            let claimed = self.state.remaining
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |r| r.checked_sub(1))
+                .is_ok();
+            if claimed {
+                results.push(output);
+            }
+            !self.is_finished()
+        });
+
+        // Count the attempts before sending, so a receiver sees them along with the result
+        self.state.attempts.fetch_add(attempts, Ordering::Relaxed);
+        for output in results {
+            if self.sender.try_send(output).is_err() {
+                warn!("Failed to send search result, receiver is gone");
+            }
+        }
+    }
+}
+
+impl<T> SearchHandle<T> {
+    pub async fn recv(&mut self) -> Option<T> {
+        self.receiver.recv().await
+    }
+
+    /// Take an already found result without waiting
+    pub fn try_recv(&mut self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Candidates tried so far for this search
+    pub fn attempts(&self) -> u64 {
+        self.state.attempts.load(Ordering::Relaxed)
+    }
+
+    pub fn control(&self) -> SearchControl {
+        SearchControl { state: Arc::clone(&self.state) }
+    }
+}
+
+impl SearchControl {
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn attempts(&self) -> u64 {
+        self.state.attempts.load(Ordering::Relaxed)
+    }
+}
+
+impl<T> Drop for SearchHandle<T> {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_thread_niceness(nice: i32) {
+    if nice == 0 {
+        return;
+    }
+
+    // On Linux the niceness is a per-thread attribute, so this only affects the calling worker
+    let result = unsafe {
+        let tid = libc::syscall(libc::SYS_gettid) as libc::id_t;
+        libc::setpriority(libc::PRIO_PROCESS, tid, nice)
+    };
+    if result != 0 {
+        warn!("Failed to set search worker niceness to {}: {}", nice, std::io::Error::last_os_error());
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_thread_niceness(nice: i32) {
+    if nice != 0 {
+        warn!("Search worker niceness is only supported on Linux, ignoring nice = {}", nice);
+    }
+}
@@ -0,0 +1,198 @@
+//! Expected search effort for vanity patterns.
+//!
+//! An address is the base58 encoding of a uniformly random 32-byte public key, and its
+//! first characters are far from uniform: 2^256 is only about 17.2 times 58^43, so the
+//! usual 44-character addresses start with `2` to `J`, and every other first character
+//! comes from the one address in 17 that is a character shorter. Prefix odds are
+//! therefore counted over the actual range of key values. Suffix characters are the
+//! low base58 digits of the key and are as good as uniform.
+
+use super::pattern::{VanityPattern, BASE58_ALPHABET};
+
+/// Match probabilities the percentiles of an [`Estimate`] are given for
+pub const CONFIDENCE_LEVELS: [f64; 3] = [0.5, 0.9, 0.99];
+
+const KEY_BYTES: usize = 32;
+
+/// Digits in the base58 encoding of the largest 32-byte value
+const MAX_DIGITS: usize = 44;
+
+/// Effort needed to find an address matching a pattern, and how long it takes at a hash rate
+#[derive(Debug, Clone)]
+pub struct Estimate {
+    /// Expected number of attempts, see [`VanityPattern::difficulty`]
+    pub difficulty: f64,
+    /// Attempts per second the estimate is timed with, if one was measured
+    pub hash_rate: Option<f64>,
+    pub percentiles: Vec<Percentile>,
+}
+
+/// Attempts within which a match is found with a given probability
+#[derive(Debug, Clone)]
+pub struct Percentile {
+    pub probability: f64,
+    pub attempts: f64,
+}
+
+impl Estimate {
+    pub fn new(pattern: &VanityPattern, hash_rate: Option<f64>) -> Self {
+        let difficulty = pattern.difficulty();
+        Self {
+            difficulty,
+            hash_rate: hash_rate.filter(|rate| *rate > 0.0),
+            percentiles: CONFIDENCE_LEVELS
+                .iter()
+                .map(|&probability| Percentile {
+                    probability,
+                    attempts: attempts_for_probability(difficulty, probability),
+                })
+                .collect(),
+        }
+    }
+
+    /// Expected seconds until a match at `hash_rate`
+    pub fn eta_seconds(&self) -> Option<f64> {
+        self.seconds(self.difficulty)
+    }
+
+    /// Seconds `attempts` take at `hash_rate`
+    pub fn seconds(&self, attempts: f64) -> Option<f64> {
+        self.hash_rate.map(|rate| attempts / rate)
+    }
+}
+
+/// Attempts needed to find a match with probability `probability`, for a pattern that takes
+/// `difficulty` attempts on average. Each attempt is an independent draw, so this is the
+/// quantile of a geometric distribution.
+pub fn attempts_for_probability(difficulty: f64, probability: f64) -> f64 {
+    if difficulty <= 1.0 {
+        return 1.0;
+    }
+    (1.0 - probability).ln() / (-1.0 / difficulty).ln_1p()
+}
+
+/// Probability that the address of a random keypair matches `pattern`
+pub fn match_probability(pattern: &VanityPattern) -> f64 {
+    let prefix = pattern
+        .prefix
+        .as_deref()
+        .map_or(1.0, |prefix| prefix_probability(&digits(prefix, pattern.case_insensitive)));
+    let suffix = pattern.suffix.as_deref().map_or(1.0, |suffix| {
+        digits(suffix, pattern.case_insensitive)
+            .iter()
+            .map(|variants| variants.len() as f64 / 58.0)
+            .product()
+    });
+    prefix * suffix
+}
+
+/// Base58 digit values each character of a pattern part may take
+fn digits(part: &str, case_insensitive: bool) -> Vec<Vec<u32>> {
+    let value = |c: char| BASE58_ALPHABET.find(c).map(|i| i as u32);
+    part.chars()
+        .map(|c| {
+            if case_insensitive && c.is_ascii_alphabetic() {
+                [c.to_ascii_lowercase(), c.to_ascii_uppercase()].into_iter().filter_map(value).collect()
+            } else {
+                value(c).into_iter().collect()
+            }
+        })
+        .collect()
+}
+
+/// Probability that the encoding of a random 32-byte key starts with `prefix`.
+///
+/// Each leading zero byte encodes as a `1`, and the rest of the key as a base58 number
+/// without leading zeros, so the prefix is split at every possible count of zero bytes.
+fn prefix_probability(prefix: &[Vec<u32>]) -> f64 {
+    let mut probability = 0.0;
+    for zeros in 0..=prefix.len().min(KEY_BYTES) {
+        if zeros > 0 && !prefix[zeros - 1].contains(&0) {
+            break;
+        }
+        if zeros == prefix.len() {
+            // At least this many zero bytes, whatever follows
+            probability += 256f64.powi(-(zeros as i32));
+            break;
+        }
+        if zeros == KEY_BYTES {
+            break;
+        }
+
+        // Exactly `zeros` zero bytes, then a number with a non-zero leading byte
+        let low = 256f64.powi((KEY_BYTES - zeros - 1) as i32);
+        let high = 256f64.powi((KEY_BYTES - zeros) as i32);
+        let exactly = 256f64.powi(-(zeros as i32)) * 255.0 / 256.0;
+        probability += exactly * leading_digits_fraction(&prefix[zeros..], low, high);
+    }
+    probability
+}
+
+/// Fraction of the numbers in `[low, high)` whose base58 digits start with `prefix`
+fn leading_digits_fraction(prefix: &[Vec<u32>], low: f64, high: f64) -> f64 {
+    let mut count = 0.0;
+    for length in prefix.len()..=MAX_DIGITS {
+        let block = 58f64.powi((length - 1) as i32);
+        for &digit in prefix[0].iter().filter(|&&digit| digit > 0) {
+            count += count_in_range(&prefix[1..], digit as f64 * block, block, low, high);
+        }
+    }
+    count / (high - low)
+}
+
+/// Numbers in `[low, high)` within the block of `width` numbers from `start` whose
+/// following digits are `rest`. Only blocks straddling `low` or `high` are split further.
+fn count_in_range(rest: &[Vec<u32>], start: f64, width: f64, low: f64, high: f64) -> f64 {
+    let end = start + width;
+    if end <= low || start >= high {
+        return 0.0;
+    }
+    if start >= low && end <= high {
+        return rest.iter().map(|variants| variants.len() as f64 / 58.0).product::<f64>() * width;
+    }
+    let Some((variants, rest)) = rest.split_first() else {
+        return end.min(high) - start.max(low);
+    };
+
+    let width = width / 58.0;
+    variants
+        .iter()
+        .map(|&digit| count_in_range(rest, start + digit as f64 * width, width, low, high))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn first_character(c: char) -> f64 {
+        prefix_probability(&digits(&c.to_string(), false))
+    }
+
+    #[test]
+    fn first_character_probabilities_sum_to_one() {
+        let total: f64 = BASE58_ALPHABET.chars().map(first_character).sum();
+        assert!((total - 1.0).abs() < 1e-9, "{}", total);
+    }
+
+    #[test]
+    fn leading_one_is_a_zero_byte() {
+        assert!((first_character('1') - 1.0 / 256.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn characters_past_the_key_range_come_from_shorter_addresses() {
+        let odds = 1.0 / first_character('z');
+        assert!((odds - 999.0).abs() < 1.0, "1 in {}", odds);
+        assert!(first_character('2') > first_character('z') * 10.0);
+    }
+
+    #[test]
+    fn attempts_follow_the_geometric_quantile() {
+        assert_eq!(attempts_for_probability(1.0, 0.99), 1.0);
+        let median = attempts_for_probability(1e6, 0.5);
+        assert!((median / (1e6 * std::f64::consts::LN_2) - 1.0).abs() < 1e-5, "{}", median);
+        assert!(attempts_for_probability(1e6, 0.9) > median);
+        assert!(attempts_for_probability(1e6, 0.99) > attempts_for_probability(1e6, 0.9));
+    }
+}
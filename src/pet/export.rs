@@ -0,0 +1,88 @@
+use anyhow::{anyhow, bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use solana_sdk::signature::Keypair;
+use std::fmt;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+use super::address::PetAddress;
+
+/// Encoding of a secret key handed out in clear
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyFormat {
+    /// Base58 of the 64-byte keypair, as Phantom and Solflare import it
+    #[default]
+    Base58,
+    /// The 64 bytes as a JSON array, like the `id.json` written by `solana-keygen`
+    SolanaJson,
+    Hex,
+    Base64,
+}
+
+impl KeyFormat {
+    pub const ALL: [KeyFormat; 4] = [KeyFormat::Base58, KeyFormat::SolanaJson, KeyFormat::Hex, KeyFormat::Base64];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyFormat::Base58 => "base58",
+            KeyFormat::SolanaJson => "solana-json",
+            KeyFormat::Hex => "hex",
+            KeyFormat::Base64 => "base64",
+        }
+    }
+}
+
+impl fmt::Display for KeyFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for KeyFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        KeyFormat::ALL
+            .into_iter()
+            .find(|format| format.as_str() == s)
+            .ok_or_else(|| anyhow!("Unknown key format \"{}\", expected base58, solana-json, hex or base64", s))
+    }
+}
+
+/// The 64-byte keypair in some [`KeyFormat`]: text, or the byte array of a `solana-keygen` keypair file for `solana-json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum ExportedKey {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+/// Encode the 64-byte keypair of `address` in `format`
+pub fn export_key(address: &PetAddress, format: KeyFormat) -> Result<ExportedKey> {
+    let keypair = address.to_keypair().map_err(|e| anyhow!("Stored keypair is invalid: {}", e))?;
+    let bytes = keypair.to_bytes();
+
+    Ok(match format {
+        KeyFormat::Base58 => ExportedKey::Text(bs58::encode(bytes).into_string()),
+        KeyFormat::SolanaJson => ExportedKey::Bytes(bytes.to_vec()),
+        KeyFormat::Hex => ExportedKey::Text(hex::encode(bytes)),
+        KeyFormat::Base64 => ExportedKey::Text(STANDARD.encode(bytes)),
+    })
+}
+
+/// Decode a keypair encoded by [`export_key`]
+pub fn import_key(key: &ExportedKey, format: KeyFormat) -> Result<Keypair> {
+    let bytes = match (format, key) {
+        (KeyFormat::SolanaJson, ExportedKey::Bytes(bytes)) => bytes.clone(),
+        (KeyFormat::Base58, ExportedKey::Text(text)) => {
+            bs58::decode(text).into_vec().context("Key is not valid base58")?
+        }
+        (KeyFormat::Hex, ExportedKey::Text(text)) => hex::decode(text).context("Key is not valid hex")?,
+        (KeyFormat::Base64, ExportedKey::Text(text)) => STANDARD.decode(text).context("Key is not valid base64")?,
+        (KeyFormat::SolanaJson, ExportedKey::Text(_)) => bail!("solana-json keys are byte arrays, not text"),
+        (_, ExportedKey::Bytes(_)) => bail!("{} keys are text, not byte arrays", format),
+    };
+
+    Keypair::try_from(bytes.as_slice()).map_err(|e| anyhow!("Invalid keypair: {}", e))
+}
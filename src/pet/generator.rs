@@ -1,26 +1,156 @@
-use anyhow::Result;
-use std::sync::Arc;
-use tokio::sync::{mpsc, Mutex};
-use tokio::time::{sleep, Duration};
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::sync::{Mutex, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration, Instant};
 use tracing::{info, warn, error};
 
 use crate::config::PetGeneratorConfig;
+use crate::metrics::Metrics;
 use super::address::PetAddress;
+use super::engine::{SearchControl, SearchEngine};
+use super::mnemonic::MnemonicSearch;
+use super::pool::{PetPool, PoolKind, PoolSettings};
 use super::storage::PetStorage;
 
+/// How often a pool is checked when nothing wakes its loop earlier
+const RECHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Snapshot of one pool's generation, see [`PetGenerator::pool_status`]
+#[derive(Debug, Clone)]
+pub struct PoolStatus {
+    pub running: bool,
+    pub settings: PoolSettings,
+    /// Addresses waiting in the pool
+    pub available: usize,
+    pub batch: Option<BatchStatus>,
+    /// Addresses generated for the pool since the database was created
+    pub generated_total: u64,
+    pub last_error: Option<GeneratorError>,
+    /// Attempts per second of the batch in flight, or of the last batch when idle
+    pub hash_rate: Option<f64>,
+    /// Expected seconds until the pool is back at its target at `hash_rate`, unknown while paused
+    pub eta_seconds: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchStatus {
+    pub size: usize,
+    /// Addresses of this batch stored so far
+    pub generated: usize,
+    pub attempts: u64,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratorError {
+    pub message: String,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Settings a pool's generation loop follows, and a way to wake it up early
+struct PoolControl {
+    settings: RwLock<PoolSettings>,
+    wake: Notify,
+    /// Fill the pool up on the next wake even if it is above its low watermark
+    refill_requested: AtomicBool,
+}
+
+impl PoolControl {
+    fn settings(&self) -> PoolSettings {
+        *self.settings.read().unwrap()
+    }
+}
+
+/// What a pool's generation loop is doing, updated as it goes
+#[derive(Default)]
+struct PoolRuntime {
+    batch: Option<ActiveBatch>,
+    last_rate: Option<f64>,
+    last_error: Option<GeneratorError>,
+}
+
+struct ActiveBatch {
+    size: usize,
+    generated: usize,
+    control: SearchControl,
+    started: Instant,
+    started_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ActiveBatch {
+    /// Attempts per second so far, `None` until the workers report their first chunk
+    fn rate(&self) -> Option<f64> {
+        let attempts = self.control.attempts();
+        let elapsed = self.started.elapsed().as_secs_f64();
+        (attempts > 0 && elapsed > 0.0).then(|| attempts as f64 / elapsed)
+    }
+}
+
 pub struct PetGenerator {
     storage: Arc<PetStorage>,
-    config: PetGeneratorConfig,
+    engine: Arc<SearchEngine>,
+    pools: Vec<PetPool>,
     is_running: Arc<Mutex<bool>>,
+    metrics: Arc<Metrics>,
+    controls: HashMap<String, Arc<PoolControl>>,
+    runtime: Arc<DashMap<String, PoolRuntime>>,
+    /// Generation loop of every pool, awaited by [`stop`](Self::stop)
+    tasks: Mutex<Vec<JoinHandle<()>>>,
 }
 
 impl PetGenerator {
-    pub fn new(storage: Arc<PetStorage>, config: PetGeneratorConfig) -> Self {
-        Self {
+    pub fn new(storage: Arc<PetStorage>, config: PetGeneratorConfig, metrics: Arc<Metrics>) -> Result<Self> {
+        let pools = PetPool::from_configs(&config.pools())?;
+        
+        // Addresses stored before named pools existed belong to the default pool
+        let migrated = storage.migrate_legacy_addresses(&pools[0].name)?;
+        if migrated > 0 {
+            info!("Migrated {} legacy addresses into pool '{}'", migrated, pools[0].name);
+        }
+
+        // Databases written before the counter existed, or edited by hand, are recounted here
+        for pool in &pools {
+            if let Some(stored) = storage.repair_address_count(&pool.name)? {
+                warn!("Address count of pool '{}' was {}, corrected to {}",
+                      pool.name, stored, storage.count_addresses(&pool.name)?);
+            }
+        }
+        
+        let engine = Arc::new(SearchEngine::new(config.worker_threads, config.nice));
+
+        // Settings changed through the admin API win over the configuration file
+        let mut controls = HashMap::with_capacity(pools.len());
+        for pool in &pools {
+            let settings = match storage.get_pool_settings(&pool.name)? {
+                Some(settings) => {
+                    info!("Pool '{}' uses stored settings: pool_size {}, low_watermark {}, batch_size {}{}",
+                          pool.name, settings.pool_size, settings.low_watermark(), settings.batch_size,
+                          if settings.paused { ", paused" } else { "" });
+                    settings
+                }
+                None => pool.configured_settings(),
+            };
+            controls.insert(pool.name.clone(), Arc::new(PoolControl {
+                settings: RwLock::new(settings),
+                wake: Notify::new(),
+                refill_requested: AtomicBool::new(false),
+            }));
+        }
+        
+        Ok(Self {
             storage,
-            config,
+            engine,
+            pools,
             is_running: Arc::new(Mutex::new(false)),
-        }
+            metrics,
+            controls,
+            runtime: Arc::new(DashMap::new()),
+            tasks: Mutex::new(Vec::new()),
+        })
     }
     
     pub async fn start(&self) -> Result<()> {
@@ -32,112 +162,379 @@ impl PetGenerator {
             *running = true;
         }
         
-        info!("Starting Pet address generator");
-        
-        let storage = Arc::clone(&self.storage);
-        let config = self.config.clone();
-        let is_running = Arc::clone(&self.is_running);
-        
-        tokio::spawn(async move {
-            loop {
-                {
-                    let running = is_running.lock().await;
-                    if !*running {
+        let mut tasks = self.tasks.lock().await;
+        for pool in &self.pools {
+            info!("Starting Pet address generator for pool '{}' with pattern {}", pool.name, pool.pattern);
+            
+            let storage = Arc::clone(&self.storage);
+            let engine = Arc::clone(&self.engine);
+            let pool = pool.clone();
+            let is_running = Arc::clone(&self.is_running);
+            let metrics = Arc::clone(&self.metrics);
+            let runtime = Arc::clone(&self.runtime);
+            let control = Arc::clone(self.control(&pool)?);
+            
+            tasks.push(tokio::spawn(async move {
+                loop {
+                    if !*is_running.lock().await {
                         break;
                     }
-                }
-                
-                match storage.count_addresses() {
-                    Ok(count) => {
-                        if count < config.pool_size {
-                            let need_to_generate = config.pool_size - count;
-                            let batch_size = std::cmp::min(need_to_generate, config.batch_size);
+                    
+                    // Refilling starts below the low watermark and, once started, runs batch after batch
+                    // until the pool is back at its size, the high watermark
+                    let forced = control.refill_requested.swap(false, Ordering::SeqCst);
+                    let settings = control.settings();
+                    let start_refill = Self::count(&storage, &runtime, &pool)
+                        .is_some_and(|count| settings.starts_refill(count, forced));
+                    
+                    if start_refill {
+                        loop {
+                            if !*is_running.lock().await {
+                                break;
+                            }
+                            let Some(count) = Self::count(&storage, &runtime, &pool) else {
+                                break;
+                            };
+                            let Some(batch_size) = control.settings().next_batch(count) else {
+                                break;
+                            };
                             
-                            info!("Current address count: {}, generating {} more addresses", count, batch_size);
+                            info!("Pool '{}' address count: {}, generating {} more addresses", pool.name, count, batch_size);
                             
-                            Self::generate_batch(&storage, batch_size).await;
+                            Self::generate_batch(&engine, &storage, &metrics, &runtime, &is_running, &pool, batch_size).await;
                         }
                     }
-                    Err(e) => {
-                        error!("Failed to check address count: {}", e);
+                    
+                    // Claims, settings changes and refill requests wake the loop long before the next check is due
+                    tokio::select! {
+                        _ = sleep(RECHECK_INTERVAL) => {}
+                        _ = control.wake.notified() => {}
                     }
                 }
                 
-                sleep(Duration::from_secs(5)).await;
-            }
-            
-            info!("Pet address generator stopped");
-        });
+                info!("Pet address generator for pool '{}' stopped", pool.name);
+            }));
+        }
         
         Ok(())
     }
     
-    pub async fn stop(&self) {
-        let mut running = self.is_running.lock().await;
-        *running = false;
+    /// Stop every pool's generation loop. Batches in flight are cancelled, and the addresses
+    /// they already found are stored before this returns; flushing them is up to the caller.
+    pub async fn stop(&self) -> Result<()> {
+        *self.is_running.lock().await = false;
         info!("Stopping Pet address generator");
+        
+        // A batch registered after this sees `is_running` cleared and cancels itself
+        for state in self.runtime.iter() {
+            if let Some(batch) = &state.batch {
+                batch.control.cancel();
+            }
+        }
+        for control in self.controls.values() {
+            control.wake.notify_one();
+        }
+        
+        let tasks: Vec<_> = self.tasks.lock().await.drain(..).collect();
+        for task in tasks {
+            if let Err(e) = task.await {
+                error!("Pet address generator task failed: {}", e);
+            }
+        }
+        
+        info!("Pet address generator stopped");
+        Ok(())
+    }
+    
+    pub async fn is_running(&self) -> bool {
+        *self.is_running.lock().await
+    }
+    
+    fn control(&self, pool: &PetPool) -> Result<&Arc<PoolControl>> {
+        self.controls.get(&pool.name).with_context(|| format!("Unknown pool '{}'", pool.name))
     }
     
-    async fn generate_batch(storage: &PetStorage, count: usize) {
-        let (tx, mut rx) = mpsc::channel(count);
+    /// Current refill settings of `pool`
+    pub fn settings(&self, pool: &PetPool) -> Result<PoolSettings> {
+        Ok(self.control(pool)?.settings())
+    }
+    
+    /// Change the settings of `pool` with `update`, then persist and apply them. Reading,
+    /// changing and storing them happen under one lock, so concurrent changes never undo
+    /// each other. `update` returns false to leave them as they are, and `None` is returned.
+    /// Pausing cancels the batch in flight, keeping the addresses it already found.
+    pub fn update_settings(
+        &self,
+        pool: &PetPool,
+        update: impl FnOnce(&mut PoolSettings) -> bool,
+    ) -> Result<Option<PoolSettings>> {
+        let control = self.control(pool)?;
+        let settings = {
+            let mut current = control.settings.write().unwrap();
+            let mut settings = *current;
+            if !update(&mut settings) {
+                return Ok(None);
+            }
+            settings.validate()?;
+            self.storage.store_pool_settings(&pool.name, &settings)?;
+            *current = settings;
+            settings
+        };
+        info!("Pool '{}' settings changed: pool_size {}, low_watermark {}, batch_size {}{}",
+              pool.name, settings.pool_size, settings.low_watermark(), settings.batch_size,
+              if settings.paused { ", paused" } else { "" });
         
-        // Spawn generation tasks
-        for i in 0..count {
-            let tx = tx.clone();
-            tokio::spawn(async move {
-                info!("Starting generation task {}", i + 1);
-                
-                // Retry up to 3 times if generation fails
-                for retry in 1..=3 {
-                    match PetAddress::generate() {
-                        Some(address) => {
-                            info!("Generated Pet address ending with: {}", 
-                                  &address.address[address.address.len().saturating_sub(10)..]);
-                            if tx.send(address).await.is_err() {
-                                warn!("Failed to send generated address to channel");
-                            }
-                            break; // Success, exit retry loop
-                        }
-                        None => {
-                            warn!("Failed to generate Pet address in task {} (attempt {}/3)", i + 1, retry);
-                            if retry < 3 {
-                                // Wait a bit before retrying
-                                tokio::time::sleep(Duration::from_millis(100)).await;
-                            }
-                        }
-                    }
-                }
-            });
+        if settings.paused {
+            if let Some(batch) = self.runtime.get(&pool.name).as_ref().and_then(|state| state.batch.as_ref()) {
+                batch.control.cancel();
+            }
+        }
+        control.wake.notify_one();
+        Ok(Some(settings))
+    }
+    
+    /// Fill `pool` up to its size right away, even if it is above its low watermark
+    pub fn refill(&self, pool: &PetPool) -> Result<()> {
+        let control = self.control(pool)?;
+        control.refill_requested.store(true, Ordering::SeqCst);
+        control.wake.notify_one();
+        Ok(())
+    }
+    
+    /// Tell the generator addresses were taken from `pool`, so it can check its watermark right away
+    pub fn notify_claim(&self, pool: &PetPool) {
+        if let Some(control) = self.controls.get(&pool.name) {
+            control.wake.notify_one();
         }
+    }
+    
+    /// Delete every unclaimed address of `pool`; the generator then refills it as usual
+    pub fn clear(&self, pool: &PetPool) -> Result<usize> {
+        let removed = self.storage.clear_all_addresses(&pool.name)?;
+        warn!("Cleared {} unclaimed addresses from pool '{}'", removed, pool.name);
+        self.refill(pool)?;
+        Ok(removed)
+    }
+    
+    /// Live generation state of `pool`
+    pub async fn pool_status(&self, pool: &PetPool) -> Result<PoolStatus> {
+        let available = self.get_current_count(&pool.name).await?;
+        let generated_total = self.storage.generated_count(&pool.name)?;
+        let running = self.is_running().await;
+        let settings = self.settings(pool)?;
         
-        drop(tx); // Close the sender
+        let (batch, last_error, hash_rate) = match self.runtime.get(&pool.name) {
+            Some(runtime) => (
+                runtime.batch.as_ref().map(|batch| BatchStatus {
+                    size: batch.size,
+                    generated: batch.generated,
+                    attempts: batch.control.attempts(),
+                    started_at: batch.started_at,
+                }),
+                runtime.last_error.clone(),
+                runtime.batch.as_ref().and_then(ActiveBatch::rate).or(runtime.last_rate),
+            ),
+            None => (None, None, None),
+        };
+        
+        // Every missing address is an independent search of the pattern's difficulty
+        let missing = settings.pool_size.saturating_sub(available);
+        let eta_seconds = if missing == 0 {
+            Some(0.0)
+        } else if settings.paused {
+            None
+        } else {
+            hash_rate
+                .filter(|rate| *rate > 0.0)
+                .map(|rate| missing as f64 * pool.pattern.difficulty() / rate)
+        };
+        
+        Ok(PoolStatus {
+            running,
+            settings,
+            available,
+            batch,
+            generated_total,
+            last_error,
+            hash_rate,
+            eta_seconds,
+        })
+    }
+    
+    /// Addresses left in `pool`, `None` (with the error recorded) if they could not be counted
+    fn count(storage: &PetStorage, runtime: &DashMap<String, PoolRuntime>, pool: &PetPool) -> Option<usize> {
+        match storage.count_addresses(&pool.name) {
+            Ok(count) => Some(count),
+            Err(e) => {
+                error!("Failed to check address count for pool '{}': {}", pool.name, e);
+                Self::record_error(runtime, pool, format!("Failed to check address count: {}", e));
+                None
+            }
+        }
+    }
+    
+    fn record_error(runtime: &DashMap<String, PoolRuntime>, pool: &PetPool, message: String) {
+        runtime.entry(pool.name.clone()).or_default().last_error = Some(GeneratorError {
+            message,
+            at: chrono::Utc::now(),
+        });
+    }
+    
+    async fn generate_batch(
+        engine: &SearchEngine,
+        storage: &PetStorage,
+        metrics: &Metrics,
+        runtime: &DashMap<String, PoolRuntime>,
+        is_running: &Mutex<bool>,
+        pool: &PetPool,
+        count: usize,
+    ) {
+        let timer = metrics.batch_duration.with_label_values(&[&pool.name]).start_timer();
+        
+        // The search itself runs on the engine's worker threads, we only wait for results here
+        let mut search = match pool.kind {
+            PoolKind::Keypair => engine.submit(pool.pattern.clone(), count),
+            PoolKind::Mnemonic => engine.submit_search(MnemonicSearch { pattern: pool.pattern.clone() }, count),
+        };
+        runtime.entry(pool.name.clone()).or_default().batch = Some(ActiveBatch {
+            size: count,
+            generated: 0,
+            control: search.control(),
+            started: Instant::now(),
+            started_at: chrono::Utc::now(),
+        });
+        if !*is_running.lock().await {
+            search.cancel();
+        }
         
         // Collect generated addresses with timeout
         let mut generated_count = 0;
-        let timeout_duration = Duration::from_secs(30); // 30 second timeout per batch
-        let start_time = std::time::Instant::now();
+        let timeout_duration = pool.kind.batch_timeout();
+        let deadline = Instant::now() + timeout_duration;
         
-        while let Some(address) = rx.recv().await {
-            if start_time.elapsed() > timeout_duration {
-                warn!("Batch generation timed out after 30 seconds");
-                break;
-            }
-            
-            match storage.store_address(address) {
-                Ok(id) => {
-                    generated_count += 1;
-                    info!("Stored Pet address with ID: {}", id);
-                }
-                Err(e) => {
-                    error!("Failed to store Pet address: {}", e);
+        while generated_count < count {
+            let address = match tokio::time::timeout_at(deadline, search.recv()).await {
+                Ok(Some(address)) => address,
+                Ok(None) => break,
+                Err(_) => {
+                    warn!(
+                        "Batch generation timed out after {} seconds ({} attempts)",
+                        timeout_duration.as_secs(),
+                        search.attempts()
+                    );
+                    metrics.batch_timeouts.with_label_values(&[&pool.name]).inc();
+                    search.cancel();
+                    
+                    // Keep whatever was already found before giving up
+                    while let Some(address) = search.try_recv() {
+                        if Self::store_generated(storage, runtime, pool, address) {
+                            generated_count += 1;
+                        }
+                    }
+                    Self::record_error(runtime, pool, format!(
+                        "Batch timed out after {} seconds with {} of {} addresses",
+                        timeout_duration.as_secs(), generated_count, count
+                    ));
+                    break;
                 }
+            };
+            
+            info!("Generated Pet address ending with: {}",
+                  &address.address[address.address.len().saturating_sub(10)..]);
+            if Self::store_generated(storage, runtime, pool, address) {
+                generated_count += 1;
+            }
+        }
+        
+        timer.observe_duration();
+        if let Some(mut state) = runtime.get_mut(&pool.name) {
+            if let Some(batch) = state.batch.take() {
+                state.last_rate = batch.rate();
             }
         }
+        metrics.keys_generated.with_label_values(&[&pool.name]).inc_by(generated_count as u64);
+        metrics.search_attempts.with_label_values(&[&pool.name]).inc_by(search.attempts());
         
-        info!("Generated and stored {} Pet addresses in batch", generated_count);
+        info!("Generated and stored {} Pet addresses in batch for pool '{}' ({} attempts)",
+              generated_count, pool.name, search.attempts());
+    }
+    
+    fn store_generated(
+        storage: &PetStorage,
+        runtime: &DashMap<String, PoolRuntime>,
+        pool: &PetPool,
+        address: PetAddress,
+    ) -> bool {
+        match storage.store_generated_address(&pool.name, address) {
+            Ok(id) => {
+                info!("Stored Pet address with ID {} in pool '{}'", id, pool.name);
+                if let Some(batch) = runtime.get_mut(&pool.name).as_mut().and_then(|state| state.batch.as_mut()) {
+                    batch.generated += 1;
+                }
+                true
+            }
+            Err(e) => {
+                error!("Failed to store Pet address: {}", e);
+                Self::record_error(runtime, pool, format!("Failed to store address: {}", e));
+                false
+            }
+        }
+    }
+    
+    /// Search engine shared by the pools, for running other searches on the same workers
+    pub fn engine(&self) -> Arc<SearchEngine> {
+        Arc::clone(&self.engine)
+    }
+    
+    /// Storage the pools are kept in
+    pub fn storage(&self) -> Arc<PetStorage> {
+        Arc::clone(&self.storage)
+    }
+    
+    /// Measured keypair attempts per second, for timing other keypair searches on this machine.
+    /// While every keypair pool is idle, the fastest of their last batches stands in for the
+    /// batches in flight. `None` until one has run.
+    pub fn hash_rate(&self) -> Option<f64> {
+        self.running_hash_rate().or_else(|| {
+            self.keypair_runtimes()
+                .filter_map(|runtime| runtime.last_rate)
+                .max_by(f64::total_cmp)
+        })
+    }
+
+    /// Combined attempts per second of the keypair batches in flight, which share the workers
+    pub fn running_hash_rate(&self) -> Option<f64> {
+        self.keypair_runtimes()
+            .filter_map(|runtime| runtime.batch.as_ref().and_then(ActiveBatch::rate))
+            .reduce(|total, rate| total + rate)
+    }
+
+    /// Keypair batches in flight
+    pub fn running_batches(&self) -> usize {
+        self.keypair_runtimes().filter(|runtime| runtime.batch.is_some()).count()
+    }
+
+    fn keypair_runtimes(&self) -> impl Iterator<Item = dashmap::mapref::one::Ref<'_, String, PoolRuntime>> {
+        self.pools
+            .iter()
+            .filter(|pool| pool.kind == PoolKind::Keypair)
+            .filter_map(|pool| self.runtime.get(&pool.name))
+    }
+
+    pub fn pools(&self) -> &[PetPool] {
+        &self.pools
+    }
+    
+    pub fn pool(&self, name: &str) -> Option<&PetPool> {
+        self.pools.iter().find(|p| p.name == name)
+    }
+    
+    /// The pool served by the un-named `/pet/*` endpoints
+    pub fn default_pool(&self) -> &PetPool {
+        &self.pools[0]
     }
     
-    pub async fn get_current_count(&self) -> Result<usize> {
-        self.storage.count_addresses()
+    pub async fn get_current_count(&self, pool: &str) -> Result<usize> {
+        self.storage.count_addresses(pool)
     }
 }
\ No newline at end of file
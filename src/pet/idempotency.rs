@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use super::address::PetAddressInfo;
+use super::lease::Lease;
+use super::storage::PetStorage;
+
+/// How often idempotency records past their retention window are deleted
+const PURGE_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Claim made earlier under the same idempotency key
+#[derive(Debug, Clone)]
+pub struct IdempotentClaim {
+    pub pool: String,
+    pub address: PetAddressInfo,
+    /// Every address of a batch claim, `address` being the first
+    pub batch: Vec<PetAddressInfo>,
+    /// Set when the claim was a lease
+    pub lease: Option<Lease>,
+}
+
+/// What a request carrying an idempotency key should do
+#[derive(Debug)]
+pub enum IdempotencyState {
+    /// The key is new and now reserved for this request
+    Reserved,
+    /// A request with this key is still being processed
+    Pending { fingerprint: String },
+    /// A request with this key already completed
+    Completed { fingerprint: String, claim: Box<IdempotentClaim> },
+}
+
+/// Periodically delete idempotency records older than `retention`
+pub fn spawn_idempotency_purge(storage: Arc<PetStorage>, retention: chrono::Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(PURGE_INTERVAL);
+        loop {
+            ticker.tick().await;
+
+            match storage.purge_idempotency_keys(chrono::Utc::now() - retention) {
+                Ok(0) => {}
+                Ok(purged) => info!("Purged {} expired idempotency keys", purged),
+                Err(e) => error!("Failed to purge idempotency keys: {}", e),
+            }
+        }
+    })
+}
@@ -0,0 +1,340 @@
+use anyhow::Result;
+use dashmap::DashMap;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+use crate::config::JobsConfig;
+use super::address::PetAddress;
+use super::engine::{KeySearch, KeypairSearch, SearchControl, SearchEngine};
+use super::estimate::Estimate;
+use super::pattern::VanityPattern;
+use super::split_key::{PartialKey, SplitKeySearch};
+use super::storage::PetStorage;
+
+/// How often a running job writes its attempt count back to storage
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// A client submitted search for a single address matching a custom pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VanityJob {
+    pub id: String,
+    pub pattern: VanityPattern,
+    /// Id of the API key the job was submitted with; only that key can see or cancel it
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Expected number of attempts, see [`VanityPattern::difficulty`]
+    pub difficulty: f64,
+    pub status: JobStatus,
+    /// Attempts made so far, as of the last progress update
+    pub attempts: u64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub result: Option<PetAddress>,
+    /// Client public point of a split-key job, which searches for a partial scalar instead of a keypair
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_point: Option<String>,
+    /// Found partial scalar, once a split-key job has completed. Useless without the client's
+    /// secret, so it is not sealed like `result`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partial_key: Option<PartialKey>,
+}
+
+impl VanityJob {
+    pub fn new(pattern: VanityPattern, difficulty: f64, api_key: Option<&str>) -> Self {
+        // Polling by id hands out the keypair, so make it unguessable
+        let mut id = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut id);
+
+        let now = chrono::Utc::now();
+        Self {
+            id: hex::encode(id),
+            pattern,
+            api_key: api_key.map(str::to_string),
+            difficulty,
+            status: JobStatus::Running,
+            attempts: 0,
+            created_at: now,
+            updated_at: now,
+            finished_at: None,
+            result: None,
+            public_point: None,
+            partial_key: None,
+        }
+    }
+
+    /// Whether a request made with `api_key` may see this job; jobs submitted without a key are
+    /// open to anyone holding the id
+    pub fn is_visible_to(&self, api_key: Option<&str>) -> bool {
+        self.api_key.is_none() || self.api_key.as_deref() == api_key
+    }
+}
+
+/// A job together with its live progress
+#[derive(Debug, Clone)]
+pub struct JobProgress {
+    pub job: VanityJob,
+    /// Attempts per second since the job was (re)started, while it is running
+    pub rate: Option<f64>,
+    /// Expected seconds until a match at the current rate. Key search is memoryless,
+    /// so this does not shrink as attempts accumulate.
+    pub eta_seconds: Option<f64>,
+}
+
+#[derive(Clone)]
+struct ActiveJob {
+    control: SearchControl,
+    started_at: Instant,
+    /// Attempts persisted before this run, when the job was resumed after a restart
+    base_attempts: u64,
+    /// Split-key attempts are far cheaper than keypair attempts and say nothing about the keypair rate
+    split_key: bool,
+}
+
+impl ActiveJob {
+    fn attempts(&self) -> u64 {
+        self.base_attempts + self.control.attempts()
+    }
+
+    /// Attempts per second of this run, `None` until the workers report their first chunk
+    fn rate(&self) -> Option<f64> {
+        let attempts = self.control.attempts();
+        let elapsed = self.started_at.elapsed().as_secs_f64();
+        (attempts > 0 && elapsed > 0.0).then(|| attempts as f64 / elapsed)
+    }
+}
+
+/// Runs custom vanity jobs on the generator's search engine and keeps them in storage
+pub struct JobManager {
+    storage: Arc<PetStorage>,
+    engine: Arc<SearchEngine>,
+    limits: JobsConfig,
+    active: Arc<DashMap<String, ActiveJob>>,
+}
+
+impl JobManager {
+    pub fn new(storage: Arc<PetStorage>, engine: Arc<SearchEngine>, limits: JobsConfig) -> Self {
+        Self {
+            storage,
+            engine,
+            limits,
+            active: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Whether a job with this estimate is within the configured limits. Until a hash rate
+    /// has been measured the time limit cannot be checked, so only jobs within
+    /// `max_difficulty` are admitted, and none when that is disabled.
+    pub fn admits(&self, estimate: &Estimate) -> bool {
+        let max_difficulty = self.limits.max_difficulty;
+        if max_difficulty > 0.0 && estimate.difficulty > max_difficulty {
+            return false;
+        }
+
+        let max_eta = self.limits.max_eta_seconds;
+        if max_eta == 0 {
+            return true;
+        }
+        match estimate.eta_seconds() {
+            Some(eta) => eta <= max_eta as f64,
+            None => max_difficulty > 0.0,
+        }
+    }
+
+    /// Combined keypair attempts per second of the running jobs, `None` until one has reported
+    pub fn hash_rate(&self) -> Option<f64> {
+        self.active
+            .iter()
+            .filter(|active| !active.split_key)
+            .filter_map(|active| active.rate())
+            .reduce(|total, rate| total + rate)
+    }
+
+    /// Keypair jobs currently searching
+    pub fn running(&self) -> usize {
+        self.active.iter().filter(|active| !active.split_key).count()
+    }
+
+    /// Persist and start a job for `pattern`, owned by `api_key` when given
+    pub fn submit(&self, pattern: VanityPattern, api_key: Option<&str>) -> Result<VanityJob> {
+        let difficulty = pattern.difficulty();
+        self.start(VanityJob::new(pattern, difficulty, api_key))
+    }
+
+    /// Persist and start a split-key job, searching for a partial scalar that turns the
+    /// client's `public_point` into an address matching `pattern`
+    pub fn submit_split_key(&self, public_point: &str, pattern: VanityPattern, api_key: Option<&str>) -> Result<VanityJob> {
+        SplitKeySearch::new(public_point, pattern.clone())?;
+
+        let difficulty = pattern.difficulty();
+        let mut job = VanityJob::new(pattern, difficulty, api_key);
+        job.public_point = Some(public_point.to_string());
+        self.start(job)
+    }
+
+    fn start(&self, job: VanityJob) -> Result<VanityJob> {
+        self.storage.store_job(&job)?;
+
+        info!(
+            "Started {}vanity job {} for pattern {} (difficulty {:.0})",
+            if job.public_point.is_some() { "split-key " } else { "" },
+            job.id,
+            job.pattern,
+            job.difficulty
+        );
+        self.run(&job)?;
+
+        Ok(job)
+    }
+
+    /// Restart jobs that were still running when the server last stopped
+    pub fn resume(&self) -> Result<usize> {
+        let mut resumed = 0;
+        for job in self.storage.list_jobs()? {
+            if job.status == JobStatus::Running && !self.active.contains_key(&job.id) {
+                if let Err(e) = self.run(&job) {
+                    error!("Failed to resume vanity job {}: {}", job.id, e);
+                    continue;
+                }
+                resumed += 1;
+            }
+        }
+
+        if resumed > 0 {
+            info!("Resumed {} vanity jobs", resumed);
+        }
+        Ok(resumed)
+    }
+
+    pub fn get(&self, id: &str) -> Result<Option<JobProgress>> {
+        let Some(mut job) = self.storage.get_job(id)? else {
+            return Ok(None);
+        };
+
+        let active = self.active.get(id).map(|active| active.clone());
+        let (rate, eta_seconds) = match active {
+            Some(active) if job.status == JobStatus::Running => {
+                job.attempts = job.attempts.max(active.attempts());
+
+                let rate = active.rate().unwrap_or(0.0);
+                let eta = (rate > 0.0).then(|| job.difficulty / rate);
+                (Some(rate), eta)
+            }
+            _ => (None, None),
+        };
+
+        Ok(Some(JobProgress { job, rate, eta_seconds }))
+    }
+
+    /// Cancel a running job. Returns the job as it is afterwards, whatever its status,
+    /// or `None` if there is no such job.
+    pub fn cancel(&self, id: &str) -> Result<Option<VanityJob>> {
+        let active = self.active.remove(id).map(|(_, active)| active);
+        if let Some(active) = &active {
+            active.control.cancel();
+        }
+
+        self.storage.update_job(id, |job| {
+            if job.status == JobStatus::Running {
+                job.status = JobStatus::Cancelled;
+                job.finished_at = Some(chrono::Utc::now());
+                if let Some(active) = &active {
+                    job.attempts = job.attempts.max(active.attempts());
+                }
+            }
+        })
+    }
+
+    fn run(&self, job: &VanityJob) -> Result<()> {
+        match &job.public_point {
+            Some(public_point) => {
+                let search = SplitKeySearch::new(public_point, job.pattern.clone())?;
+                self.spawn(job, search, true, |job, partial| job.partial_key = Some(partial));
+            }
+            None => {
+                let search = KeypairSearch { pattern: job.pattern.clone() };
+                self.spawn(job, search, false, |job, address| job.result = Some(address));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run `search` for `job` until it finds one match, which `complete` stores on the job
+    fn spawn<S>(&self, job: &VanityJob, search: S, split_key: bool, complete: fn(&mut VanityJob, S::Output))
+    where
+        S: KeySearch,
+        S::Output: Clone,
+    {
+        let mut handle = self.engine.submit_search(search, 1);
+        let active = ActiveJob {
+            control: handle.control(),
+            started_at: Instant::now(),
+            base_attempts: job.attempts,
+            split_key,
+        };
+        self.active.insert(job.id.clone(), active.clone());
+
+        let id = job.id.clone();
+        let storage = Arc::clone(&self.storage);
+        let active_jobs = Arc::clone(&self.active);
+
+        tokio::spawn(async move {
+            let mut progress = tokio::time::interval(PROGRESS_INTERVAL);
+            progress.tick().await;
+
+            loop {
+                tokio::select! {
+                    found = handle.recv() => {
+                        // `None` means the job was cancelled or the engine shut down; in the
+                        // latter case it stays running in storage and is resumed on restart
+                        let attempts = active.attempts();
+                        let result = storage.update_job(&id, |job| {
+                            job.attempts = job.attempts.max(attempts);
+                            if let (Some(found), JobStatus::Running) = (&found, job.status) {
+                                job.status = JobStatus::Completed;
+                                job.finished_at = Some(chrono::Utc::now());
+                                complete(job, found.clone());
+                            }
+                        });
+
+                        match result {
+                            Ok(_) if found.is_some() => info!("Vanity job {} completed after {} attempts", id, attempts),
+                            Ok(_) => {}
+                            Err(e) => error!("Failed to store result of vanity job {}: {}", id, e),
+                        }
+                        break;
+                    }
+                    _ = progress.tick() => {
+                        let attempts = active.attempts();
+                        if let Err(e) = storage.update_job(&id, |job| job.attempts = job.attempts.max(attempts)) {
+                            error!("Failed to store progress of vanity job {}: {}", id, e);
+                        }
+                    }
+                }
+            }
+
+            active_jobs.remove(&id);
+        });
+    }
+}
@@ -0,0 +1,69 @@
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use super::storage::PetStorage;
+
+/// Reservation of a pooled address, pending confirmation by the client
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lease {
+    pub id: String,
+    pub pool: String,
+    pub address_id: u64,
+    /// Id of the API key the address was leased with
+    pub api_key: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    /// When the claim quota was charged for this lease; a release refunds that day and month
+    #[serde(default)]
+    pub charged_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl Lease {
+    pub fn new(
+        pool: &str,
+        address_id: u64,
+        api_key: Option<&str>,
+        created_at: chrono::DateTime<chrono::Utc>,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        // The lease id is all a client needs to confirm or release, so make it unguessable
+        let mut id = [0u8; 16];
+        rand::thread_rng().fill_bytes(&mut id);
+
+        Self {
+            id: hex::encode(id),
+            pool: pool.to_string(),
+            address_id,
+            api_key: api_key.map(str::to_string),
+            created_at,
+            expires_at,
+            charged_at: None,
+        }
+    }
+
+    pub fn is_expired(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Periodically return expired leases to their pools
+pub fn spawn_lease_reaper(storage: Arc<PetStorage>, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+
+            match storage.release_expired_leases(chrono::Utc::now()) {
+                Ok(released) if !released.is_empty() => {
+                    info!("Returned {} expired leases to their pools", released.len());
+                }
+                Ok(_) => {}
+                Err(e) => error!("Failed to release expired leases: {}", e),
+            }
+        }
+    })
+}
@@ -0,0 +1,85 @@
+//! Vanity addresses backed by a BIP39 seed phrase.
+//!
+//! Each candidate is a random 12-word mnemonic, stretched into a seed with PBKDF2
+//! and derived along the path Phantom and Solflare use for the first account,
+//! `m/44'/501'/0'/0'` (SLIP-0010 ed25519). A match can be restored in any such
+//! wallet from the phrase alone.
+//!
+//! The 2048 PBKDF2 rounds make every attempt thousands of times slower than a
+//! plain keypair search, so these run as pools of their own with short patterns.
+
+use anyhow::{anyhow, Result};
+use bip39::Mnemonic;
+use rand::RngCore;
+use solana_derivation_path::DerivationPath;
+use solana_keypair::seed_derivable::keypair_from_seed_and_derivation_path;
+use solana_sdk::signature::{Keypair, Signer};
+
+use super::address::PetAddress;
+use super::engine::KeySearch;
+use super::pattern::VanityPattern;
+
+/// Derivation path of the first account in Phantom and Solflare
+pub const SOLANA_DERIVATION_PATH: &str = "m/44'/501'/0'/0'";
+
+/// Most mnemonics a worker tries before checking the queue again
+const CHUNK_SIZE: u64 = 64;
+
+/// Entropy of a 12-word mnemonic
+const ENTROPY_BYTES: usize = 16;
+
+/// Random mnemonics whose derived public key matches a pattern
+pub struct MnemonicSearch {
+    pub pattern: VanityPattern,
+}
+
+impl KeySearch for MnemonicSearch {
+    type Output = PetAddress;
+
+    fn search_chunk(&self, max_attempts: u64, found: &mut dyn FnMut(PetAddress) -> bool) -> u64 {
+        let path = solana_path();
+        let attempts = max_attempts.min(CHUNK_SIZE);
+
+        for attempt in 1..=attempts {
+            let mnemonic = random_mnemonic();
+            let keypair = match derive(&mnemonic, &path) {
+                Ok(keypair) => keypair,
+                Err(e) => {
+                    tracing::warn!("Failed to derive keypair from mnemonic: {}", e);
+                    continue;
+                }
+            };
+
+            if self.pattern.matches(&keypair.pubkey().to_string())
+                && !found(PetAddress::from_mnemonic(&keypair, &mnemonic.to_string(), SOLANA_DERIVATION_PATH))
+            {
+                return attempt;
+            }
+        }
+        attempts
+    }
+}
+
+/// Restore the keypair of a mnemonic-backed address, as a wallet importing the phrase would
+pub fn derive_keypair(phrase: &str, derivation_path: &str) -> Result<Keypair> {
+    let mnemonic = Mnemonic::parse(phrase).map_err(|e| anyhow!("Invalid mnemonic: {}", e))?;
+    let path = DerivationPath::from_absolute_path_str(derivation_path)
+        .map_err(|e| anyhow!("Invalid derivation path \"{}\": {}", derivation_path, e))?;
+    derive(&mnemonic, &path)
+}
+
+fn derive(mnemonic: &Mnemonic, path: &DerivationPath) -> Result<Keypair> {
+    let seed = mnemonic.to_seed("");
+    keypair_from_seed_and_derivation_path(&seed, Some(path.clone()))
+        .map_err(|e| anyhow!("Failed to derive keypair: {}", e))
+}
+
+fn random_mnemonic() -> Mnemonic {
+    let mut entropy = [0u8; ENTROPY_BYTES];
+    rand::thread_rng().fill_bytes(&mut entropy);
+    Mnemonic::from_entropy(&entropy).expect("16 bytes is a valid mnemonic entropy length")
+}
+
+fn solana_path() -> DerivationPath {
+    DerivationPath::new_bip44(Some(0), Some(0))
+}
@@ -1,7 +1,36 @@
+pub mod archive;
+pub mod audit;
+pub mod crypto;
+pub mod delivery;
+pub mod engine;
+pub mod estimate;
+pub mod export;
 pub mod generator;
+pub mod idempotency;
+pub mod job;
+pub mod lease;
+pub mod mnemonic;
 pub mod storage;
 pub mod address;
+pub mod api_key;
+pub mod pattern;
+pub mod pool;
+pub mod split_key;
 
-pub use generator::PetGenerator;
+pub use archive::{ArchiveManifest, ArchiveRecord, ImportSummary};
+pub use audit::{spawn_audit_purge, AuditEntry, AuditKind, Claimant};
+pub use crypto::{MasterKey, SealedSecret};
+pub use engine::{KeySearch, KeypairSearch, SearchControl, SearchEngine, SearchHandle};
+pub use estimate::{attempts_for_probability, Estimate, Percentile};
+pub use export::{export_key, import_key, ExportedKey, KeyFormat};
+pub use generator::{BatchStatus, GeneratorError, PetGenerator, PoolStatus};
+pub use idempotency::{spawn_idempotency_purge, IdempotencyState, IdempotentClaim};
+pub use job::{JobManager, JobProgress, JobStatus, VanityJob};
+pub use lease::{spawn_lease_reaper, Lease};
 pub use storage::PetStorage;
-pub use address::{PetAddress, PetAddressInfo};
\ No newline at end of file
+pub use address::{ClaimRecord, PetAddress, PetAddressInfo};
+pub use api_key::{ApiKey, Scope};
+pub use pattern::VanityPattern;
+pub use mnemonic::{derive_keypair, MnemonicSearch, SOLANA_DERIVATION_PATH};
+pub use pool::{PetPool, PoolKind, PoolSettings};
+pub use split_key::{CombinedKey, PartialKey, SplitKeySearch, SplitKeySecret};
\ No newline at end of file
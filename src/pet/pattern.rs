@@ -0,0 +1,102 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::config::PatternConfig;
+
+use super::estimate::match_probability;
+
+/// Characters used by Solana's base58 address encoding
+pub const BASE58_ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Vanity pattern an address has to match before it is stored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VanityPattern {
+    pub prefix: Option<String>,
+    pub suffix: Option<String>,
+    pub case_insensitive: bool,
+}
+
+impl VanityPattern {
+    pub fn new(prefix: Option<String>, suffix: Option<String>, case_insensitive: bool) -> Result<Self> {
+        let prefix = prefix.filter(|p| !p.is_empty());
+        let suffix = suffix.filter(|s| !s.is_empty());
+
+        if prefix.is_none() && suffix.is_none() {
+            bail!("Vanity pattern needs at least a prefix or a suffix");
+        }
+
+        for part in prefix.iter().chain(suffix.iter()) {
+            validate_base58(part, case_insensitive)?;
+        }
+
+        let pattern = Self {
+            prefix,
+            suffix,
+            case_insensitive,
+        };
+        if match_probability(&pattern) <= 0.0 {
+            bail!("No Solana address can match the vanity pattern {}", pattern);
+        }
+        Ok(pattern)
+    }
+
+    /// Expected number of random keys to try before one matches, taking into account
+    /// that the leading characters of base58 addresses are not uniform
+    pub fn difficulty(&self) -> f64 {
+        1.0 / match_probability(self)
+    }
+
+    pub fn matches(&self, address: &str) -> bool {
+        if self.case_insensitive {
+            let address = address.to_ascii_lowercase();
+            self.prefix.as_ref().is_none_or(|p| address.starts_with(&p.to_ascii_lowercase()))
+                && self.suffix.as_ref().is_none_or(|s| address.ends_with(&s.to_ascii_lowercase()))
+        } else {
+            self.prefix.as_ref().is_none_or(|p| address.starts_with(p.as_str()))
+                && self.suffix.as_ref().is_none_or(|s| address.ends_with(s.as_str()))
+        }
+    }
+}
+
+impl std::fmt::Display for VanityPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}*{}",
+            self.prefix.as_deref().unwrap_or(""),
+            self.suffix.as_deref().unwrap_or("")
+        )?;
+        if self.case_insensitive {
+            write!(f, " (case-insensitive)")?;
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<&PatternConfig> for VanityPattern {
+    type Error = anyhow::Error;
+
+    fn try_from(config: &PatternConfig) -> Result<Self> {
+        Self::new(config.prefix.clone(), config.suffix.clone(), config.case_insensitive)
+    }
+}
+
+fn validate_base58(part: &str, case_insensitive: bool) -> Result<()> {
+    for c in part.chars() {
+        let valid = if case_insensitive {
+            BASE58_ALPHABET.contains(c.to_ascii_lowercase())
+                || BASE58_ALPHABET.contains(c.to_ascii_uppercase())
+        } else {
+            BASE58_ALPHABET.contains(c)
+        };
+
+        if !valid {
+            bail!(
+                "Invalid character '{}' in vanity pattern \"{}\": base58 addresses never contain 0, O, I or l",
+                c,
+                part
+            );
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::config::PoolConfig;
+use super::pattern::VanityPattern;
+
+/// A named address pool with its own pattern and refill targets
+#[derive(Debug, Clone)]
+pub struct PetPool {
+    pub name: String,
+    pub kind: PoolKind,
+    pub pattern: VanityPattern,
+    pub pool_size: usize,
+    /// Refilling starts when fewer addresses are left, `pool_size` when not set
+    pub low_watermark: Option<usize>,
+    pub batch_size: usize,
+}
+
+/// How the keypairs of a pool are generated
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PoolKind {
+    /// Random keypairs
+    #[default]
+    Keypair,
+    /// Keypairs derived from random BIP39 mnemonics, which wallets can import as a seed phrase
+    Mnemonic,
+}
+
+impl PoolKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PoolKind::Keypair => "keypair",
+            PoolKind::Mnemonic => "mnemonic",
+        }
+    }
+
+    /// How long a refill batch may search before it is given up
+    pub fn batch_timeout(&self) -> Duration {
+        match self {
+            PoolKind::Keypair => Duration::from_secs(30),
+            // Every attempt runs PBKDF2, so even short patterns take minutes
+            PoolKind::Mnemonic => Duration::from_secs(600),
+        }
+    }
+}
+
+/// Refill settings of a pool that can be changed at runtime, persisted once changed
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PoolSettings {
+    /// Refilling stops once the pool holds this many addresses, the high watermark
+    pub pool_size: usize,
+    /// Refilling starts when fewer addresses are left, `pool_size` when not set
+    #[serde(default)]
+    pub low_watermark: Option<usize>,
+    pub batch_size: usize,
+    /// No new batches are started while paused
+    #[serde(default)]
+    pub paused: bool,
+}
+
+impl PoolSettings {
+    pub fn low_watermark(&self) -> usize {
+        self.low_watermark.unwrap_or(self.pool_size)
+    }
+
+    /// Whether a pool holding `count` addresses starts refilling: below the low watermark,
+    /// or below its size when a refill was `forced`
+    pub fn starts_refill(&self, count: usize, forced: bool) -> bool {
+        !self.paused && (count < self.low_watermark() || (forced && count < self.pool_size))
+    }
+
+    /// Size of the next batch of a refill in progress, `None` once the pool is back at its
+    /// size, the high watermark, or paused
+    pub fn next_batch(&self, count: usize) -> Option<usize> {
+        if self.paused || count >= self.pool_size {
+            return None;
+        }
+        Some(std::cmp::min(self.pool_size - count, self.batch_size))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.batch_size == 0 {
+            bail!("batch_size must be greater than 0");
+        }
+        if self.low_watermark() > self.pool_size {
+            bail!("low_watermark must not be greater than pool_size");
+        }
+        Ok(())
+    }
+}
+
+impl PetPool {
+    /// Settings taken from the configuration file
+    pub fn configured_settings(&self) -> PoolSettings {
+        PoolSettings {
+            pool_size: self.pool_size,
+            low_watermark: self.low_watermark,
+            batch_size: self.batch_size,
+            paused: false,
+        }
+    }
+
+    pub fn from_configs(configs: &[PoolConfig]) -> Result<Vec<Self>> {
+        if configs.is_empty() {
+            bail!("At least one address pool must be configured");
+        }
+
+        let mut pools: Vec<Self> = Vec::with_capacity(configs.len());
+        for config in configs {
+            let pool = Self::try_from(config)?;
+            if pools.iter().any(|p| p.name == pool.name) {
+                bail!("Duplicate address pool name \"{}\"", pool.name);
+            }
+            pools.push(pool);
+        }
+
+        Ok(pools)
+    }
+}
+
+impl TryFrom<&PoolConfig> for PetPool {
+    type Error = anyhow::Error;
+
+    fn try_from(config: &PoolConfig) -> Result<Self> {
+        if config.name.is_empty()
+            || !config.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!(
+                "Invalid pool name \"{}\": only ASCII letters, digits, '-' and '_' are allowed",
+                config.name
+            );
+        }
+
+        if config.batch_size == 0 {
+            bail!("Pool \"{}\" must have a batch_size greater than 0", config.name);
+        }
+
+        if config.low_watermark.is_some_and(|low| low > config.pool_size) {
+            bail!("Pool \"{}\" must have a low_watermark no greater than its pool_size", config.name);
+        }
+
+        let pattern = VanityPattern::try_from(&config.pattern)
+            .map_err(|e| anyhow::anyhow!("Pool \"{}\": {}", config.name, e))?;
+
+        Ok(Self {
+            name: config.name.clone(),
+            kind: config.kind,
+            pattern,
+            pool_size: config.pool_size,
+            low_watermark: config.low_watermark,
+            batch_size: config.batch_size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(pool_size: usize, low_watermark: Option<usize>) -> PoolSettings {
+        PoolSettings { pool_size, low_watermark, batch_size: 10, paused: false }
+    }
+
+    #[test]
+    fn refill_starts_below_the_low_watermark() {
+        let settings = settings(100, Some(80));
+        assert!(!settings.starts_refill(80, false));
+        assert!(settings.starts_refill(79, false));
+        assert!(settings.starts_refill(0, false));
+    }
+
+    #[test]
+    fn without_a_low_watermark_any_claim_starts_a_refill() {
+        let settings = settings(100, None);
+        assert!(settings.starts_refill(99, false));
+        assert!(!settings.starts_refill(100, false));
+    }
+
+    #[test]
+    fn forced_refill_starts_anywhere_below_the_size() {
+        let settings = settings(100, Some(80));
+        assert!(settings.starts_refill(95, true));
+        assert!(!settings.starts_refill(100, true));
+        assert!(!settings.starts_refill(120, true));
+    }
+
+    #[test]
+    fn started_refill_runs_up_to_the_high_watermark() {
+        let settings = settings(100, Some(80));
+        assert_eq!(settings.next_batch(75), Some(10));
+        assert_eq!(settings.next_batch(85), Some(10), "refill stopped above the low watermark");
+        assert_eq!(settings.next_batch(95), Some(5));
+        assert_eq!(settings.next_batch(100), None);
+        assert_eq!(settings.next_batch(120), None);
+    }
+
+    #[test]
+    fn paused_pools_never_refill() {
+        let settings = PoolSettings { paused: true, ..settings(100, Some(80)) };
+        assert!(!settings.starts_refill(0, true));
+        assert_eq!(settings.next_batch(0), None);
+    }
+
+    #[test]
+    fn low_watermark_must_not_exceed_the_size() {
+        assert!(settings(100, Some(100)).validate().is_ok());
+        assert!(settings(100, Some(101)).validate().is_err());
+        assert!(PoolSettings { batch_size: 0, ..settings(100, None) }.validate().is_err());
+    }
+}
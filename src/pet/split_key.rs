@@ -0,0 +1,182 @@
+//! Split-key ("trustless") vanity generation.
+//!
+//! The client keeps a secret scalar `a` and submits only its public point `A = a·B`.
+//! The server searches for a partial scalar `k` such that `A + k·B` encodes to an
+//! address matching the pattern, and returns `k`. The final secret `a + k` is only
+//! ever known to the client.
+//!
+//! The combined key is a raw ed25519 scalar rather than a seed, so it cannot be turned
+//! into a seed-based Solana `Keypair`; [`CombinedKey`] signs with it directly.
+
+use anyhow::{anyhow, bail, Context, Result};
+use curve25519_dalek::constants::ED25519_BASEPOINT_TABLE;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::BasepointTable;
+use ed25519_dalek::hazmat::{raw_sign, ExpandedSecretKey};
+use ed25519_dalek::VerifyingKey;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha512};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+
+use super::engine::KeySearch;
+use super::pattern::VanityPattern;
+
+/// Result of a split-key search: the server's share of the secret and the resulting address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialKey {
+    /// Base58 encoded partial scalar `k`
+    pub partial_scalar: String,
+    /// Address of `A + k·B`
+    pub address: String,
+}
+
+/// Search for a partial scalar whose combination with the client's point matches a pattern
+pub struct SplitKeySearch {
+    client_point: EdwardsPoint,
+    pattern: VanityPattern,
+}
+
+impl SplitKeySearch {
+    /// `client_point` is the client's public point as a base58 string, like a Solana public key
+    pub fn new(client_point: &str, pattern: VanityPattern) -> Result<Self> {
+        Ok(Self {
+            client_point: parse_point(client_point)?,
+            pattern,
+        })
+    }
+}
+
+impl KeySearch for SplitKeySearch {
+    type Output = PartialKey;
+
+    fn search_chunk(&self, max_attempts: u64, found: &mut dyn FnMut(PartialKey) -> bool) -> u64 {
+        // Random start per chunk, then walk P, P + B, P + 2B, ... which is far cheaper
+        // than a scalar multiplication per attempt
+        let start = random_scalar();
+        let mut point = self.client_point + ED25519_BASEPOINT_TABLE * &start;
+        let basepoint = ED25519_BASEPOINT_TABLE.basepoint();
+
+        for attempt in 0..max_attempts {
+            let address = bs58::encode(point.compress().as_bytes()).into_string();
+            if self.pattern.matches(&address) {
+                let partial = start + Scalar::from(attempt);
+                let result = PartialKey {
+                    partial_scalar: bs58::encode(partial.as_bytes()).into_string(),
+                    address,
+                };
+                if !found(result) {
+                    return attempt + 1;
+                }
+            }
+            point += basepoint;
+        }
+
+        max_attempts
+    }
+}
+
+/// Client-side secret share of a split key
+pub struct SplitKeySecret {
+    scalar: Scalar,
+}
+
+impl SplitKeySecret {
+    pub fn generate() -> Self {
+        Self { scalar: random_scalar() }
+    }
+
+    /// Restore a secret share from its base58 encoding
+    pub fn from_base58(encoded: &str) -> Result<Self> {
+        Ok(Self { scalar: parse_scalar(encoded)? })
+    }
+
+    pub fn to_base58(&self) -> String {
+        bs58::encode(self.scalar.as_bytes()).into_string()
+    }
+
+    /// Public point to submit to the server
+    pub fn public_point(&self) -> String {
+        let point = ED25519_BASEPOINT_TABLE * &self.scalar;
+        bs58::encode(point.compress().as_bytes()).into_string()
+    }
+
+    /// Combine with the server's partial scalar into the final signing key
+    pub fn combine(&self, partial: &PartialKey) -> Result<CombinedKey> {
+        let key = CombinedKey::new(self.scalar + parse_scalar(&partial.partial_scalar)?);
+        if key.pubkey().to_string() != partial.address {
+            bail!("Combined key does not match the address returned by the server");
+        }
+        Ok(key)
+    }
+}
+
+/// Final split-key secret, able to sign for the vanity address
+pub struct CombinedKey {
+    expanded: ExpandedSecretKey,
+    verifying_key: VerifyingKey,
+}
+
+impl CombinedKey {
+    fn new(scalar: Scalar) -> Self {
+        // Ed25519 needs a secret nonce prefix next to the scalar; derive it from the scalar
+        let mut hasher = Sha512::new();
+        hasher.update(b"petaddr-split-key-prefix");
+        hasher.update(scalar.as_bytes());
+        let mut hash_prefix = [0u8; 32];
+        hash_prefix.copy_from_slice(&hasher.finalize()[..32]);
+
+        let expanded = ExpandedSecretKey { scalar, hash_prefix };
+        let verifying_key = VerifyingKey::from(&expanded);
+        Self { expanded, verifying_key }
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        Pubkey::new_from_array(self.verifying_key.to_bytes())
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Signature {
+        let signature = raw_sign::<Sha512>(&self.expanded, message, &self.verifying_key);
+        Signature::from(signature.to_bytes())
+    }
+
+    /// 64-byte expanded secret key (scalar followed by nonce prefix)
+    pub fn to_expanded_bytes(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(self.expanded.scalar.as_bytes());
+        bytes[32..].copy_from_slice(&self.expanded.hash_prefix);
+        bytes
+    }
+}
+
+fn random_scalar() -> Scalar {
+    let mut bytes = [0u8; 64];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    Scalar::from_bytes_mod_order_wide(&bytes)
+}
+
+fn parse_scalar(encoded: &str) -> Result<Scalar> {
+    let bytes: [u8; 32] = bs58::decode(encoded)
+        .into_vec()
+        .context("Scalar is not valid base58")?
+        .try_into()
+        .map_err(|_| anyhow!("Scalar must be 32 bytes"))?;
+    Option::from(Scalar::from_canonical_bytes(bytes)).ok_or_else(|| anyhow!("Scalar is not canonical"))
+}
+
+fn parse_point(encoded: &str) -> Result<EdwardsPoint> {
+    let bytes: [u8; 32] = bs58::decode(encoded)
+        .into_vec()
+        .context("Public point is not valid base58")?
+        .try_into()
+        .map_err(|_| anyhow!("Public point must be 32 bytes"))?;
+    let point = CompressedEdwardsY(bytes)
+        .decompress()
+        .ok_or_else(|| anyhow!("Public point is not on the ed25519 curve"))?;
+    if point.is_small_order() || !point.is_torsion_free() {
+        bail!("Public point must be a prime-order ed25519 point");
+    }
+    Ok(point)
+}
@@ -1,25 +1,291 @@
-use anyhow::{Result, Context};
-use sled::Db;
+use anyhow::{bail, Result, Context};
+use serde::{Deserialize, Serialize};
+use sled::transaction::{ConflictableTransactionResult, TransactionalTree};
+use sled::{Db, Transactional, Tree};
+use std::collections::HashSet;
 use std::path::Path;
+use std::sync::Arc;
 
-use super::address::{PetAddress, PetAddressInfo};
+use super::address::{ClaimRecord, PetAddress, PetAddressInfo};
+use super::api_key::ApiKey;
+use super::audit::{AuditEntry, AuditKind, Claimant};
+use super::crypto::{MasterKey, SealedSecret};
+use super::idempotency::{IdempotencyState, IdempotentClaim};
+use super::job::VanityJob;
+use super::lease::Lease;
+use super::pool::PoolSettings;
+
+/// Key prefix used before named pools existed
+const LEGACY_ADDRESS_PREFIX: &str = "address:";
+/// One entry per master key version, used to detect a wrong key at startup
+const KEY_CHECK_PREFIX: &str = "encryption:check:";
+const KEY_CHECK_AAD: &[u8] = b"petaddr:key-check";
+const JOB_PREFIX: &str = "job:";
+const CLAIM_PREFIX: &str = "claim:";
+const API_KEY_PREFIX: &str = "api_key:";
+const API_KEY_USAGE_PREFIX: &str = "api_key_usage:";
+const LEASE_PREFIX: &str = "lease:";
+const IDEMPOTENCY_PREFIX: &str = "idempotency:";
+/// Lifetime number of addresses generated per pool
+const GENERATED_PREFIX: &str = "generated:";
+/// Pool settings changed through the admin API, overriding the configuration file
+const POOL_SETTINGS_PREFIX: &str = "pool_settings:";
+/// Addresses in each pool, updated in the same transaction as every insert and removal
+const POOL_COUNT_PREFIX: &str = "pool_count:";
+/// Append-only log of handed out addresses, keyed by claim time then id
+const AUDIT_TREE: &str = "audit";
+/// `{public_key}:{audit key}` for every audit entry
+const AUDIT_BY_PUBLIC_KEY_TREE: &str = "audit_by_public_key";
+
+/// Address record as persisted; `private_key` and `mnemonic` are emptied when they are sealed
+#[derive(Clone, Serialize, Deserialize)]
+struct StoredAddressInfo {
+    #[serde(flatten)]
+    info: PetAddressInfo,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sealed_private_key: Option<SealedSecret>,
+}
+
+/// Sealed secret of a mnemonic-backed address
+#[derive(Serialize, Deserialize)]
+struct MnemonicSecret {
+    private_key: String,
+    mnemonic: String,
+}
+
+/// Job record as persisted; the result's `private_key` is emptied when it is sealed
+#[derive(Serialize, Deserialize)]
+struct StoredJob {
+    #[serde(flatten)]
+    job: VanityJob,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sealed_private_key: Option<SealedSecret>,
+}
+
+/// Leased address, kept exactly as it was stored in the pool so it can be put back
+#[derive(Serialize, Deserialize)]
+struct StoredLease {
+    #[serde(flatten)]
+    lease: Lease,
+    address: StoredAddressInfo,
+}
+
+/// Idempotency key; `result` is written in the same transaction as the claim it belongs to
+#[derive(Serialize, Deserialize)]
+struct StoredIdempotency {
+    /// Request parameters the key was first used with
+    fingerprint: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<StoredIdempotentClaim>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredIdempotentClaim {
+    pool: String,
+    address: StoredAddressInfo,
+    /// Every address of a batch claim, `address` being the first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    batch: Vec<StoredAddressInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lease: Option<Lease>,
+}
+
+/// Audit entry ready to be written inside a claim transaction
+struct AuditRow {
+    key: Vec<u8>,
+    value: Vec<u8>,
+    index_key: Vec<u8>,
+}
 
 #[derive(Clone)]
 pub struct PetStorage {
     db: Db,
+    audit: Tree,
+    audit_by_public_key: Tree,
     counter_key: &'static str,
+    master_key: Option<Arc<MasterKey>>,
 }
 
 impl PetStorage {
     pub fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        Self::open(db_path, None)
+    }
+    
+    /// Open the database, sealing private keys with `master_key` when one is given.
+    ///
+    /// Fails if the database was encrypted with a different master key, or if it holds
+    /// encrypted keys and no master key is given.
+    pub fn open<P: AsRef<Path>>(db_path: P, master_key: Option<MasterKey>) -> Result<Self> {
         let db = sled::open(db_path)?;
-        Ok(Self {
+        let storage = Self {
+            audit: db.open_tree(AUDIT_TREE)?,
+            audit_by_public_key: db.open_tree(AUDIT_BY_PUBLIC_KEY_TREE)?,
             db,
             counter_key: "counter",
-        })
+            master_key: master_key.map(Arc::new),
+        };
+        storage.verify_master_key()?;
+        Ok(storage)
+    }
+    
+    fn verify_master_key(&self) -> Result<()> {
+        let versions = self.key_check_versions()?;
+        
+        let Some(master_key) = &self.master_key else {
+            if !versions.is_empty() {
+                bail!("Database contains encrypted keys but encryption is disabled");
+            }
+            return Ok(());
+        };
+        
+        match self.db.get(Self::key_check_key(master_key.version()))? {
+            Some(value) => {
+                let sealed: SealedSecret = serde_json::from_slice(&value)
+                    .context("Failed to deserialize key check")?;
+                master_key.open(&sealed, KEY_CHECK_AAD)
+                    .context("Master key does not match the key this database was encrypted with")?;
+            }
+            None if !versions.is_empty() => {
+                bail!(
+                    "Database is encrypted with master key version(s) {:?}, not version {}; run `reencrypt` to rotate keys",
+                    versions,
+                    master_key.version()
+                );
+            }
+            None => self.write_key_check(master_key)?,
+        }
+        
+        Ok(())
+    }
+    
+    fn key_check_key(version: u32) -> String {
+        format!("{}{:010}", KEY_CHECK_PREFIX, version)
+    }
+    
+    fn key_check_versions(&self) -> Result<Vec<u32>> {
+        self.db.scan_prefix(KEY_CHECK_PREFIX)
+            .keys()
+            .map(|key| {
+                let key = key?;
+                let version = std::str::from_utf8(&key[KEY_CHECK_PREFIX.len()..])?.parse()?;
+                Ok(version)
+            })
+            .collect()
+    }
+    
+    fn write_key_check(&self, master_key: &MasterKey) -> Result<()> {
+        let sealed = master_key.seal(b"petaddr", KEY_CHECK_AAD)?;
+        self.db.insert(Self::key_check_key(master_key.version()), serde_json::to_vec(&sealed)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+    
+    /// Seal the private key (and mnemonic) of `address` in place when a master key is loaded
+    fn seal_private_key(&self, address: &mut PetAddress) -> Result<Option<SealedSecret>> {
+        let Some(master_key) = &self.master_key else {
+            return Ok(None);
+        };
+        
+        let sealed = master_key.seal(&Self::take_secret(address)?, address.public_key.as_bytes())?;
+        Ok(Some(sealed))
+    }
+    
+    fn open_private_key(&self, address: &mut PetAddress, sealed: Option<SealedSecret>) -> Result<()> {
+        if let Some(sealed) = sealed {
+            let master_key = self.master_key.as_ref()
+                .context("Private key is encrypted but no master key is loaded")?;
+            let secret = master_key.open(&sealed, address.public_key.as_bytes())?;
+            Self::restore_secret(address, secret)?;
+        }
+        Ok(())
+    }
+    
+    /// Remove the secrets from `address`, returning what has to be sealed: the bare private key,
+    /// or the private key and mnemonic as JSON for mnemonic-backed addresses
+    fn take_secret(address: &mut PetAddress) -> Result<Vec<u8>> {
+        let private_key = std::mem::take(&mut address.private_key);
+        match address.mnemonic.take() {
+            Some(mnemonic) => serde_json::to_vec(&MnemonicSecret { private_key, mnemonic })
+                .context("Failed to serialize mnemonic secret"),
+            None => Ok(private_key.into_bytes()),
+        }
     }
     
-    pub fn store_address(&self, address: PetAddress) -> Result<u64> {
+    /// Put secrets removed by [`Self::take_secret`] back; the derivation path stays in clear and tells the two forms apart
+    fn restore_secret(address: &mut PetAddress, secret: Vec<u8>) -> Result<()> {
+        if address.derivation_path.is_some() {
+            let secret: MnemonicSecret = serde_json::from_slice(&secret)
+                .context("Decrypted mnemonic secret is not valid")?;
+            address.private_key = secret.private_key;
+            address.mnemonic = Some(secret.mnemonic);
+        } else {
+            address.private_key = String::from_utf8(secret)
+                .context("Decrypted private key is not valid UTF-8")?;
+        }
+        Ok(())
+    }
+    
+    fn encode_address(&self, address_info: &PetAddressInfo) -> Result<Vec<u8>> {
+        let mut info = address_info.clone();
+        let sealed_private_key = self.seal_private_key(&mut info.address)?;
+        let record = StoredAddressInfo { info, sealed_private_key };
+        
+        serde_json::to_vec(&record).context("Failed to serialize address info")
+    }
+    
+    fn decode_address(&self, value: &[u8]) -> Result<PetAddressInfo> {
+        let record: StoredAddressInfo = serde_json::from_slice(value)
+            .context("Failed to deserialize address info")?;
+        
+        let mut info = record.info;
+        self.open_private_key(&mut info.address, record.sealed_private_key)?;
+        
+        Ok(info)
+    }
+    
+    fn encode_job(&self, job: &VanityJob) -> Result<Vec<u8>> {
+        let mut job = job.clone();
+        let sealed_private_key = match job.result.as_mut() {
+            Some(result) => self.seal_private_key(result)?,
+            None => None,
+        };
+        let record = StoredJob { job, sealed_private_key };
+        
+        serde_json::to_vec(&record).context("Failed to serialize job")
+    }
+    
+    fn decode_job(&self, value: &[u8]) -> Result<VanityJob> {
+        let record: StoredJob = serde_json::from_slice(value)
+            .context("Failed to deserialize job")?;
+        
+        let mut job = record.job;
+        if let Some(result) = job.result.as_mut() {
+            self.open_private_key(result, record.sealed_private_key)?;
+        }
+        
+        Ok(job)
+    }
+    
+    fn address_prefix(pool: &str) -> String {
+        format!("pool:{}:address:", pool)
+    }
+    
+    fn address_key(pool: &str, id: u64) -> String {
+        format!("{}{:010}", Self::address_prefix(pool), id)
+    }
+    
+    pub fn store_address(&self, pool: &str, address: PetAddress) -> Result<u64> {
+        self.store_new_address(pool, address, false)
+    }
+    
+    /// Store an address the generator found, counting it towards the pool's lifetime total
+    /// in the same transaction
+    pub fn store_generated_address(&self, pool: &str, address: PetAddress) -> Result<u64> {
+        self.store_new_address(pool, address, true)
+    }
+    
+    fn store_new_address(&self, pool: &str, address: PetAddress, generated: bool) -> Result<u64> {
         let id = self.next_id()?;
         let address_info = PetAddressInfo {
             id,
@@ -27,66 +293,1117 @@ impl PetStorage {
             created_at: chrono::Utc::now(),
         };
         
-        let key = format!("address:{:010}", id);
-        let value = serde_json::to_vec(&address_info)
-            .context("Failed to serialize address info")?;
-        
-        self.db.insert(key.as_bytes(), value)?;
+        self.insert_address(pool, &address_info, generated)?;
         self.db.flush()?;
         
         Ok(id)
     }
     
-    pub fn get_next_address(&self) -> Result<Option<PetAddressInfo>> {
-        for result in self.db.scan_prefix(b"address:") {
-            let (_key, value) = result?;
-            let address_info: PetAddressInfo = serde_json::from_slice(&value)
+    /// Store an address exported from another database, keeping its creation time.
+    ///
+    /// With `preserve_id` the address keeps its id, which must never have been issued by this
+    /// database; the id counter is moved past it. Otherwise it gets the next free id.
+    pub fn import_address(&self, pool: &str, address_info: &PetAddressInfo, preserve_id: bool) -> Result<u64> {
+        let mut address_info = address_info.clone();
+        if !preserve_id {
+            address_info.id = self.next_id()?;
+            self.insert_address(pool, &address_info, false)?;
+            return Ok(address_info.id);
+        }
+        
+        let id = address_info.id;
+        let key = Self::address_key(pool, id);
+        let value = self.encode_address(&address_info)?;
+        let count_key = Self::pool_count_key(pool);
+        
+        // The id check and the counter move share the insert's transaction, so an address
+        // stored meanwhile can never be issued the same id
+        let imported = self.db
+            .transaction(|tx| -> ConflictableTransactionResult<bool> {
+                let last_id = tx.get(self.counter_key)?.map(|v| decode_u64(&v)).unwrap_or(0);
+                if id <= last_id {
+                    return Ok(false);
+                }
+                tx.insert(self.counter_key, &id.to_be_bytes())?;
+                if tx.insert(key.as_bytes(), value.as_slice())?.is_none() {
+                    Self::adjust_count(tx, &count_key, 1)?;
+                }
+                Ok(true)
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to import address: {:?}", e))?;
+        if !imported {
+            bail!("Address id {} was already issued by this database", id);
+        }
+        
+        Ok(id)
+    }
+    
+    /// Insert an address and count it, and with `generated` count it as generated as well
+    fn insert_address(&self, pool: &str, address_info: &PetAddressInfo, generated: bool) -> Result<()> {
+        let key = Self::address_key(pool, address_info.id);
+        let value = self.encode_address(address_info)?;
+        let count_key = Self::pool_count_key(pool);
+        let generated_key = Self::generated_key(pool);
+        
+        self.db
+            .transaction(|tx| -> ConflictableTransactionResult<()> {
+                if tx.insert(key.as_bytes(), value.as_slice())?.is_none() {
+                    Self::adjust_count(tx, &count_key, 1)?;
+                    if generated {
+                        Self::adjust_count(tx, &generated_key, 1)?;
+                    }
+                }
+                Ok(())
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to store address: {:?}", e))?;
+        
+        Ok(())
+    }
+    
+    /// Every unclaimed address of `pool`, oldest first, with its private key opened
+    pub fn list_addresses(&self, pool: &str) -> Result<Vec<PetAddressInfo>> {
+        self.db.scan_prefix(Self::address_prefix(pool))
+            .values()
+            .map(|value| self.decode_address(&value?))
+            .collect()
+    }
+    
+    /// Ids and public keys of the unclaimed addresses of `pool`, oldest first; needs no master key
+    pub fn list_public_keys(&self, pool: &str) -> Result<Vec<(u64, String)>> {
+        self.db.scan_prefix(Self::address_prefix(pool))
+            .values()
+            .map(|value| {
+                let record: StoredAddressInfo = serde_json::from_slice(&value?)
+                    .context("Failed to deserialize address info")?;
+                Ok((record.info.id, record.info.address.public_key))
+            })
+            .collect()
+    }
+    
+    /// Remove one unclaimed address of `pool`, returning whether it was there
+    pub fn remove_address(&self, pool: &str, id: u64) -> Result<bool> {
+        let key = Self::address_key(pool, id);
+        let count_key = Self::pool_count_key(pool);
+        
+        let existed = self.db
+            .transaction(|tx| -> ConflictableTransactionResult<bool> {
+                let existed = tx.remove(key.as_bytes())?.is_some();
+                if existed {
+                    Self::adjust_count(tx, &count_key, -1)?;
+                }
+                Ok(existed)
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to remove address: {:?}", e))?;
+        
+        Ok(existed)
+    }
+    
+    /// Public keys this database has ever handed out or holds: pooled in any pool, leased or claimed
+    pub fn known_public_keys(&self) -> Result<HashSet<String>> {
+        let mut public_keys = HashSet::new();
+        
+        for result in self.db.scan_prefix("pool:") {
+            let (key, value) = result?;
+            if !key.windows(b":address:".len()).any(|w| w == b":address:") {
+                continue;
+            }
+            let record: StoredAddressInfo = serde_json::from_slice(&value)
                 .context("Failed to deserialize address info")?;
+            public_keys.insert(record.info.address.public_key);
+        }
+        
+        for result in self.db.scan_prefix(LEASE_PREFIX) {
+            let (_, value) = result?;
+            let record: StoredLease = serde_json::from_slice(&value)
+                .context("Failed to deserialize lease")?;
+            public_keys.insert(record.address.info.address.public_key);
+        }
+        
+        for result in self.db.scan_prefix(CLAIM_PREFIX) {
+            let (_, value) = result?;
+            let record: ClaimRecord = serde_json::from_slice(&value)
+                .context("Failed to deserialize claim record")?;
+            public_keys.insert(record.public_key);
+        }
+        
+        Ok(public_keys)
+    }
+    
+    /// Highest address id issued so far
+    pub fn last_id(&self) -> Result<u64> {
+        Ok(self.db.get(self.counter_key)?.map(|v| decode_u64(&v)).unwrap_or(0))
+    }
+    
+    /// Atomically claim the oldest address of `pool`, removing it from storage
+    pub fn get_next_address(&self, pool: &str) -> Result<Option<PetAddressInfo>> {
+        self.claim_next_address(pool, &Claimant::default(), None)
+    }
+    
+    /// Like [`get_next_address`](Self::get_next_address), recording the claim and an audit entry for `claimant`.
+    /// With an `idempotency_key` reserved through [`reserve_idempotency_key`](Self::reserve_idempotency_key),
+    /// the claimed address is stored under it in the same transaction.
+    pub fn claim_next_address(
+        &self,
+        pool: &str,
+        claimant: &Claimant,
+        idempotency_key: Option<&str>,
+    ) -> Result<Option<PetAddressInfo>> {
+        loop {
+            let Some(result) = self.db.scan_prefix(Self::address_prefix(pool)).next() else {
+                return Ok(None);
+            };
+            let (key, value) = result?;
+            
+            // Decode before removing so an undecryptable record is never lost
+            let address_info = self.decode_address(&value)?;
+            
+            let claim = ClaimRecord {
+                address_id: address_info.id,
+                pool: pool.to_string(),
+                public_key: address_info.address.public_key.clone(),
+                api_key: claimant.api_key.clone(),
+                claimed_at: chrono::Utc::now(),
+            };
+            let claim_key = Self::claim_key(address_info.id);
+            let claim_value = serde_json::to_vec(&claim)?;
+            let audit = self.audit_row(AuditKind::Claim, &claim, claimant)?;
+            let idempotency = self.complete_idempotency(idempotency_key, pool, &value, None)?;
+            let count_key = Self::pool_count_key(pool);
+            
+            // Only the caller whose transaction still finds the entry gets to hand it out
+            let claimed = (&*self.db, &self.audit, &self.audit_by_public_key)
+                .transaction(|(tx, audit_tx, index_tx)| -> ConflictableTransactionResult<bool> {
+                    if tx.get(&key)?.as_deref() != Some(&value[..]) {
+                        return Ok(false);
+                    }
+                    tx.remove(&key)?;
+                    Self::adjust_count(tx, &count_key, -1)?;
+                    tx.insert(claim_key.as_bytes(), claim_value.as_slice())?;
+                    if let Some((idempotency_key, idempotency_value)) = &idempotency {
+                        tx.insert(idempotency_key.as_bytes(), idempotency_value.as_slice())?;
+                    }
+                    Self::write_audit(audit_tx, index_tx, &audit)?;
+                    Ok(true)
+                })
+                .map_err(|e| anyhow::anyhow!("Failed to claim address: {:?}", e))?;
             
-            // Remove this address from storage after retrieving
-            let key = format!("address:{:010}", address_info.id);
-            self.db.remove(key.as_bytes())?;
+            if claimed {
+                return Ok(Some(address_info));
+            }
+            // Another request claimed this entry first, try the next one
+        }
+    }
+    
+    /// Claim the `count` oldest addresses of `pool` in one transaction. With `partial`, fewer
+    /// are claimed when the pool runs short; otherwise nothing is claimed unless all `count` are there.
+    pub fn claim_addresses(
+        &self,
+        pool: &str,
+        count: usize,
+        claimant: &Claimant,
+        partial: bool,
+    ) -> Result<Vec<PetAddressInfo>> {
+        self.claim_addresses_with(pool, count, claimant, partial, None, Ok)
+    }
+    
+    /// Like [`claim_addresses`](Self::claim_addresses), first turning every address into what is handed
+    /// out with `prepare`, so that a failure there leaves them all in the pool. With an `idempotency_key`
+    /// reserved through [`reserve_idempotency_key`](Self::reserve_idempotency_key), the batch is stored
+    /// under it in the same transaction.
+    pub fn claim_addresses_with<T, F>(
+        &self,
+        pool: &str,
+        count: usize,
+        claimant: &Claimant,
+        partial: bool,
+        idempotency_key: Option<&str>,
+        prepare: F,
+    ) -> Result<Vec<T>>
+    where
+        F: Fn(PetAddressInfo) -> Result<T>,
+    {
+        loop {
+            let entries = self.db.scan_prefix(Self::address_prefix(pool))
+                .take(count)
+                .collect::<sled::Result<Vec<_>>>()?;
+            if entries.is_empty() || (entries.len() < count && !partial) {
+                return Ok(Vec::new());
+            }
+            
+            let claimed_at = chrono::Utc::now();
+            let mut addresses = Vec::with_capacity(entries.len());
+            let mut claims = Vec::with_capacity(entries.len());
+            let mut audit = Vec::with_capacity(entries.len());
+            for (_, value) in &entries {
+                let address_info = self.decode_address(value)?;
+                let claim = ClaimRecord {
+                    address_id: address_info.id,
+                    pool: pool.to_string(),
+                    public_key: address_info.address.public_key.clone(),
+                    api_key: claimant.api_key.clone(),
+                    claimed_at,
+                };
+                claims.push((Self::claim_key(address_info.id), serde_json::to_vec(&claim)?));
+                audit.push(self.audit_row(AuditKind::Claim, &claim, claimant)?);
+                addresses.push(prepare(address_info)?);
+            }
+            
+            let idempotency = self.complete_batch_idempotency(idempotency_key, pool, &entries)?;
+            let count_key = Self::pool_count_key(pool);
+            
+            // Either every scanned entry is still there and all are claimed, or none is
+            let claimed = (&*self.db, &self.audit, &self.audit_by_public_key)
+                .transaction(|(tx, audit_tx, index_tx)| -> ConflictableTransactionResult<bool> {
+                    for (key, value) in &entries {
+                        if tx.get(key)?.as_deref() != Some(&value[..]) {
+                            return Ok(false);
+                        }
+                    }
+                    for ((key, _), (claim_key, claim_value)) in entries.iter().zip(&claims) {
+                        tx.remove(key)?;
+                        tx.insert(claim_key.as_bytes(), claim_value.as_slice())?;
+                    }
+                    Self::adjust_count(tx, &count_key, -(entries.len() as i64))?;
+                    if let Some((idempotency_key, idempotency_value)) = &idempotency {
+                        tx.insert(idempotency_key.as_bytes(), idempotency_value.as_slice())?;
+                    }
+                    for row in &audit {
+                        Self::write_audit(audit_tx, index_tx, row)?;
+                    }
+                    Ok(true)
+                })
+                .map_err(|e| anyhow::anyhow!("Failed to claim addresses: {:?}", e))?;
+            
+            if claimed {
+                self.db.flush()?;
+                return Ok(addresses);
+            }
+            // Some entries were claimed concurrently, scan again
+        }
+    }
+    
+    /// Audit entry for `claim`, keyed so entries sort by claim time
+    fn audit_row(&self, kind: AuditKind, claim: &ClaimRecord, claimant: &Claimant) -> Result<AuditRow> {
+        let entry = AuditEntry {
+            id: self.db.generate_id()?,
+            kind,
+            pool: claim.pool.clone(),
+            address_id: claim.address_id,
+            public_key: claim.public_key.clone(),
+            api_key: claimant.api_key.clone(),
+            client_ip: claimant.client_ip.clone(),
+            request_id: claimant.request_id.clone(),
+            claimed_at: claim.claimed_at,
+        };
+        
+        let key = Self::audit_key(entry.claimed_at, entry.id);
+        let mut index_key = format!("{}:", entry.public_key).into_bytes();
+        index_key.extend_from_slice(&key);
+        
+        Ok(AuditRow { key: key.to_vec(), value: serde_json::to_vec(&entry)?, index_key })
+    }
+    
+    fn audit_key(at: chrono::DateTime<chrono::Utc>, id: u64) -> [u8; 16] {
+        let nanos = at.timestamp_nanos_opt().unwrap_or(i64::MAX).max(0) as u64;
+        let mut key = [0u8; 16];
+        key[..8].copy_from_slice(&nanos.to_be_bytes());
+        key[8..].copy_from_slice(&id.to_be_bytes());
+        key
+    }
+    
+    fn write_audit(
+        audit: &TransactionalTree,
+        index: &TransactionalTree,
+        row: &AuditRow,
+    ) -> ConflictableTransactionResult<()> {
+        audit.insert(row.key.as_slice(), row.value.as_slice())?;
+        index.insert(row.index_key.as_slice(), row.key.as_slice())?;
+        Ok(())
+    }
+    
+    /// Audit entries from `from` up to `to`, oldest first
+    pub fn list_audit(
+        &self,
+        from: Option<chrono::DateTime<chrono::Utc>>,
+        to: Option<chrono::DateTime<chrono::Utc>>,
+        limit: usize,
+    ) -> Result<Vec<AuditEntry>> {
+        let start = from.map_or([0u8; 16], |from| Self::audit_key(from, 0));
+        let end = to.map_or([u8::MAX; 16], |to| Self::audit_key(to, u64::MAX));
+        
+        self.audit.range(start..=end)
+            .values()
+            .take(limit)
+            .map(|value| serde_json::from_slice(&value?).context("Failed to deserialize audit entry"))
+            .collect()
+    }
+    
+    /// Every audit entry for `public_key`, oldest first
+    pub fn audit_for_public_key(&self, public_key: &str) -> Result<Vec<AuditEntry>> {
+        let mut entries = Vec::new();
+        for result in self.audit_by_public_key.scan_prefix(format!("{}:", public_key)) {
+            let (_, audit_key) = result?;
+            // Index rows can outlive their entry if a purge was interrupted
+            if let Some(value) = self.audit.get(&audit_key)? {
+                entries.push(serde_json::from_slice(&value).context("Failed to deserialize audit entry")?);
+            }
+        }
+        Ok(entries)
+    }
+    
+    /// Delete audit entries claimed before `cutoff`
+    pub fn purge_audit(&self, cutoff: chrono::DateTime<chrono::Utc>) -> Result<usize> {
+        let mut purged = 0;
+        for result in self.audit.range(..Self::audit_key(cutoff, 0)) {
+            let (key, value) = result?;
+            let entry: AuditEntry = serde_json::from_slice(&value).context("Failed to deserialize audit entry")?;
             
-            return Ok(Some(address_info));
+            let mut index_key = format!("{}:", entry.public_key).into_bytes();
+            index_key.extend_from_slice(&key);
+            self.audit_by_public_key.remove(index_key)?;
+            self.audit.remove(&key)?;
+            purged += 1;
         }
         
-        Ok(None)
+        if purged > 0 {
+            self.db.flush()?;
+        }
+        Ok(purged)
+    }
+    
+    fn claim_key(address_id: u64) -> String {
+        format!("{}{:010}", CLAIM_PREFIX, address_id)
     }
     
-    pub fn count_addresses(&self) -> Result<usize> {
-        let count = self.db.scan_prefix(b"address:").count();
+    pub fn get_claim(&self, address_id: u64) -> Result<Option<ClaimRecord>> {
+        self.db.get(Self::claim_key(address_id))?
+            .map(|value| serde_json::from_slice(&value).context("Failed to deserialize claim record"))
+            .transpose()
+    }
+    
+    /// Write everything still buffered by sled to disk, returning the bytes written
+    pub fn flush(&self) -> Result<usize> {
+        Ok(self.db.flush()?)
+    }
+    
+    /// Size of the database files on disk, in bytes
+    pub fn size_on_disk(&self) -> Result<u64> {
+        Ok(self.db.size_on_disk()?)
+    }
+    
+    fn pool_count_key(pool: &str) -> String {
+        format!("{}{}", POOL_COUNT_PREFIX, pool)
+    }
+    
+    /// Add `delta` to a counter, such as a pool's address count, within the transaction that
+    /// inserts or removes the addresses
+    fn adjust_count(tx: &TransactionalTree, count_key: &str, delta: i64) -> ConflictableTransactionResult<()> {
+        let count = tx.get(count_key.as_bytes())?.map(|v| decode_u64(&v)).unwrap_or(0);
+        let count = count.saturating_add_signed(delta);
+        tx.insert(count_key.as_bytes(), &count.to_be_bytes())?;
+        Ok(())
+    }
+    
+    /// Addresses in `pool`, read from the maintained counter
+    pub fn count_addresses(&self, pool: &str) -> Result<usize> {
+        let count = self.db.get(Self::pool_count_key(pool))?.map(|v| decode_u64(&v)).unwrap_or(0);
+        Ok(count as usize)
+    }
+    
+    /// Addresses in `pool`, counted by scanning every entry
+    pub fn recount_addresses(&self, pool: &str) -> Result<usize> {
+        let count = self.db.scan_prefix(Self::address_prefix(pool)).count();
         Ok(count)
     }
     
-    pub fn clear_all_addresses(&self) -> Result<()> {
-        let keys: Vec<_> = self.db.scan_prefix(b"address:")
-            .map(|result| result.unwrap().0)
-            .collect();
+    /// Set the counter of `pool` to a full recount, returning the previous value if it had drifted.
+    ///
+    /// Meant to be run at startup; inserts or claims running at the same time may be miscounted.
+    pub fn repair_address_count(&self, pool: &str) -> Result<Option<usize>> {
+        let stored = self.count_addresses(pool)?;
+        let actual = self.recount_addresses(pool)?;
+        if stored == actual {
+            return Ok(None);
+        }
         
+        self.db.insert(Self::pool_count_key(pool).as_bytes(), &(actual as u64).to_be_bytes())?;
+        self.db.flush()?;
+        Ok(Some(stored))
+    }
+    
+    fn generated_key(pool: &str) -> String {
+        format!("{}{}", GENERATED_PREFIX, pool)
+    }
+    
+    /// Addresses generated for `pool` since the database was created, claimed or not
+    pub fn generated_count(&self, pool: &str) -> Result<u64> {
+        Ok(self.db
+            .get(Self::generated_key(pool))?
+            .map(|v| decode_u64(&v))
+            .unwrap_or(0))
+    }
+    
+    /// Remove every unclaimed address of `pool`, returning how many were removed
+    pub fn clear_all_addresses(&self, pool: &str) -> Result<usize> {
+        let keys: Vec<_> = self.db.scan_prefix(Self::address_prefix(pool))
+            .keys()
+            .collect::<Result<_, _>>()?;
+        
+        let count_key = Self::pool_count_key(pool);
+        let mut removed = 0;
         for key in keys {
-            self.db.remove(&key)?;
+            let existed = self.db
+                .transaction(|tx| -> ConflictableTransactionResult<bool> {
+                    let existed = tx.remove(&key)?.is_some();
+                    if existed {
+                        Self::adjust_count(tx, &count_key, -1)?;
+                    }
+                    Ok(existed)
+                })
+                .map_err(|e| anyhow::anyhow!("Failed to remove address: {:?}", e))?;
+            if existed {
+                removed += 1;
+            }
+        }
+        
+        self.db.flush()?;
+        Ok(removed)
+    }
+    
+    fn pool_settings_key(pool: &str) -> String {
+        format!("{}{}", POOL_SETTINGS_PREFIX, pool)
+    }
+    
+    /// Settings stored for `pool` by [`store_pool_settings`](Self::store_pool_settings), if any
+    pub fn get_pool_settings(&self, pool: &str) -> Result<Option<PoolSettings>> {
+        self.db.get(Self::pool_settings_key(pool))?
+            .map(|value| serde_json::from_slice(&value).context("Failed to deserialize pool settings"))
+            .transpose()
+    }
+    
+    pub fn store_pool_settings(&self, pool: &str, settings: &PoolSettings) -> Result<()> {
+        self.db.insert(Self::pool_settings_key(pool).as_bytes(), serde_json::to_vec(settings)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+    
+    fn job_key(id: &str) -> String {
+        format!("{}{}", JOB_PREFIX, id)
+    }
+    
+    /// Persist a new job
+    pub fn store_job(&self, job: &VanityJob) -> Result<()> {
+        self.db.insert(Self::job_key(&job.id).as_bytes(), self.encode_job(job)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+    
+    pub fn get_job(&self, id: &str) -> Result<Option<VanityJob>> {
+        self.db.get(Self::job_key(id))?
+            .map(|value| self.decode_job(&value))
+            .transpose()
+    }
+    
+    pub fn list_jobs(&self) -> Result<Vec<VanityJob>> {
+        self.db.scan_prefix(JOB_PREFIX)
+            .values()
+            .map(|value| self.decode_job(&value?))
+            .collect()
+    }
+    
+    /// Apply `update` to a job atomically, retrying if it changed concurrently.
+    ///
+    /// Returns the updated job, or `None` if it does not exist.
+    pub fn update_job<F>(&self, id: &str, mut update: F) -> Result<Option<VanityJob>>
+    where
+        F: FnMut(&mut VanityJob),
+    {
+        let key = Self::job_key(id);
+        loop {
+            let Some(value) = self.db.get(&key)? else {
+                return Ok(None);
+            };
+            
+            let mut job = self.decode_job(&value)?;
+            update(&mut job);
+            job.updated_at = chrono::Utc::now();
+            
+            if self.db.compare_and_swap(&key, Some(&value), Some(self.encode_job(&job)?))?.is_ok() {
+                self.db.flush()?;
+                return Ok(Some(job));
+            }
+        }
+    }
+    
+    fn lease_key(id: &str) -> String {
+        format!("{}{}", LEASE_PREFIX, id)
+    }
+    
+    /// Reserve the oldest address of `pool` for `ttl`. It leaves the pool like a claim, but
+    /// goes back when the lease is released or expires instead of being confirmed.
+    /// `charged_at` is when the claim quota was charged for it, which a release refunds.
+    pub fn lease_next_address(
+        &self,
+        pool: &str,
+        claimant: &Claimant,
+        ttl: chrono::Duration,
+        charged_at: chrono::DateTime<chrono::Utc>,
+        idempotency_key: Option<&str>,
+    ) -> Result<Option<(Lease, PetAddressInfo)>> {
+        loop {
+            let Some(result) = self.db.scan_prefix(Self::address_prefix(pool)).next() else {
+                return Ok(None);
+            };
+            let (key, value) = result?;
+            
+            let address_info = self.decode_address(&value)?;
+            let address: StoredAddressInfo = serde_json::from_slice(&value)
+                .context("Failed to deserialize address info")?;
+            
+            let now = chrono::Utc::now();
+            let mut lease = Lease::new(pool, address_info.id, claimant.api_key.as_deref(), now, now + ttl);
+            lease.charged_at = Some(charged_at);
+            let lease_key = Self::lease_key(&lease.id);
+            let handed_out = ClaimRecord {
+                address_id: address_info.id,
+                pool: pool.to_string(),
+                public_key: address.info.address.public_key.clone(),
+                api_key: claimant.api_key.clone(),
+                claimed_at: now,
+            };
+            let audit = self.audit_row(AuditKind::Lease, &handed_out, claimant)?;
+            let lease_value = serde_json::to_vec(&StoredLease { lease: lease.clone(), address })?;
+            let idempotency = self.complete_idempotency(idempotency_key, pool, &value, Some(&lease))?;
+            let count_key = Self::pool_count_key(pool);
+            
+            let leased = (&*self.db, &self.audit, &self.audit_by_public_key)
+                .transaction(|(tx, audit_tx, index_tx)| -> ConflictableTransactionResult<bool> {
+                    if tx.get(&key)?.as_deref() != Some(&value[..]) {
+                        return Ok(false);
+                    }
+                    tx.remove(&key)?;
+                    Self::adjust_count(tx, &count_key, -1)?;
+                    tx.insert(lease_key.as_bytes(), lease_value.as_slice())?;
+                    if let Some((idempotency_key, idempotency_value)) = &idempotency {
+                        tx.insert(idempotency_key.as_bytes(), idempotency_value.as_slice())?;
+                    }
+                    Self::write_audit(audit_tx, index_tx, &audit)?;
+                    Ok(true)
+                })
+                .map_err(|e| anyhow::anyhow!("Failed to lease address: {:?}", e))?;
+            
+            if leased {
+                self.db.flush()?;
+                return Ok(Some((lease, address_info)));
+            }
+        }
+    }
+    
+    pub fn get_lease(&self, id: &str) -> Result<Option<Lease>> {
+        self.db.get(Self::lease_key(id))?
+            .map(|value| {
+                let record: StoredLease = serde_json::from_slice(&value).context("Failed to deserialize lease")?;
+                Ok(record.lease)
+            })
+            .transpose()
+    }
+    
+    /// A lease with its address, private key included, for handing out on confirmation
+    pub fn get_leased_address(&self, id: &str) -> Result<Option<(Lease, PetAddressInfo)>> {
+        let Some(value) = self.db.get(Self::lease_key(id))? else {
+            return Ok(None);
+        };
+        let record: StoredLease = serde_json::from_slice(&value).context("Failed to deserialize lease")?;
+        let address_info = self.decode_address(&serde_json::to_vec(&record.address)?)?;
+        Ok(Some((record.lease, address_info)))
+    }
+    
+    /// Finalise a lease into a claim. Returns `None` if the lease no longer exists.
+    pub fn confirm_lease(&self, id: &str) -> Result<Option<ClaimRecord>> {
+        self.finish_lease(id, |record| {
+            let claim = ClaimRecord {
+                address_id: record.lease.address_id,
+                pool: record.lease.pool.clone(),
+                public_key: record.address.info.address.public_key.clone(),
+                api_key: record.lease.api_key.clone(),
+                claimed_at: chrono::Utc::now(),
+            };
+            let value = serde_json::to_vec(&claim).map_err(|e| anyhow::anyhow!(e))?;
+            Ok((Self::claim_key(claim.address_id), value, claim))
+        })
+    }
+    
+    /// Put a leased address back into its pool, refunding the claim quota it used in the day
+    /// and month it was charged. Returns `None` if the lease no longer exists.
+    pub fn release_lease(&self, id: &str) -> Result<Option<Lease>> {
+        let released = self.finish_lease(id, |record| {
+            let key = Self::address_key(&record.lease.pool, record.lease.address_id);
+            let value = serde_json::to_vec(&record.address).map_err(|e| anyhow::anyhow!(e))?;
+            Ok((key, value, record.lease.clone()))
+        })?;
+        
+        if let Some(lease) = &released {
+            if let Some(api_key) = &lease.api_key {
+                self.refund_claim_quota(api_key, 1, lease.charged_at.unwrap_or(lease.created_at))?;
+            }
+        }
+        Ok(released)
+    }
+    
+    /// Release every lease that expired before `now`
+    pub fn release_expired_leases(&self, now: chrono::DateTime<chrono::Utc>) -> Result<Vec<Lease>> {
+        let expired: Vec<Lease> = self.db.scan_prefix(LEASE_PREFIX)
+            .values()
+            .map(|value| {
+                let record: StoredLease = serde_json::from_slice(&value?).context("Failed to deserialize lease")?;
+                Ok(record.lease)
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|lease| lease.is_expired(now))
+            .collect();
+        
+        let mut released = Vec::with_capacity(expired.len());
+        for lease in expired {
+            // Confirmed or released since the scan
+            if let Some(lease) = self.release_lease(&lease.id)? {
+                released.push(lease);
+            }
+        }
+        Ok(released)
+    }
+    
+    /// Remove a lease and write the record `finish` builds from it, in one transaction
+    fn finish_lease<T, F>(&self, id: &str, finish: F) -> Result<Option<T>>
+    where
+        F: Fn(&StoredLease) -> Result<(String, Vec<u8>, T)>,
+    {
+        let lease_key = Self::lease_key(id);
+        let Some(value) = self.db.get(&lease_key)? else {
+            return Ok(None);
+        };
+        let record: StoredLease = serde_json::from_slice(&value).context("Failed to deserialize lease")?;
+        let (key, new_value, result) = finish(&record)?;
+        // A released address goes back into its pool and counts again
+        let returned_to_pool = key.starts_with(&Self::address_prefix(&record.lease.pool));
+        let count_key = Self::pool_count_key(&record.lease.pool);
+        
+        let finished = self.db
+            .transaction(|tx| -> ConflictableTransactionResult<bool> {
+                if tx.get(lease_key.as_bytes())?.as_deref() != Some(&value[..]) {
+                    return Ok(false);
+                }
+                tx.remove(lease_key.as_bytes())?;
+                if tx.insert(key.as_bytes(), new_value.as_slice())?.is_none() && returned_to_pool {
+                    Self::adjust_count(tx, &count_key, 1)?;
+                }
+                Ok(true)
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to finish lease: {:?}", e))?;
+        
+        if !finished {
+            // Someone else confirmed or released it first
+            return Ok(None);
         }
         
         self.db.flush()?;
+        Ok(Some(result))
+    }
+    
+    fn idempotency_record_key(key: &str) -> String {
+        format!("{}{}", IDEMPOTENCY_PREFIX, key)
+    }
+    
+    /// Reserve `key` for a request with `fingerprint`, unless it was used within `retention`.
+    /// A reservation is completed by a claim or lease made with the key, or undone with
+    /// [`release_idempotency_key`](Self::release_idempotency_key).
+    pub fn reserve_idempotency_key(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        retention: chrono::Duration,
+    ) -> Result<IdempotencyState> {
+        let record_key = Self::idempotency_record_key(key);
+        let pending = serde_json::to_vec(&StoredIdempotency {
+            fingerprint: fingerprint.to_string(),
+            created_at: chrono::Utc::now(),
+            result: None,
+        })?;
+        
+        loop {
+            let current = self.db.get(&record_key)?;
+            if let Some(value) = &current {
+                let record: StoredIdempotency = serde_json::from_slice(value)
+                    .context("Failed to deserialize idempotency key")?;
+                
+                // Past the retention window the key is free to be used again
+                if record.created_at + retention > chrono::Utc::now() {
+                    let Some(result) = record.result else {
+                        return Ok(IdempotencyState::Pending { fingerprint: record.fingerprint });
+                    };
+                    
+                    let open = |stored: StoredAddressInfo| -> Result<PetAddressInfo> {
+                        let mut address = stored.info;
+                        self.open_private_key(&mut address.address, stored.sealed_private_key)?;
+                        Ok(address)
+                    };
+                    let claim = IdempotentClaim {
+                        pool: result.pool,
+                        address: open(result.address)?,
+                        batch: result.batch.into_iter().map(open).collect::<Result<_>>()?,
+                        lease: result.lease,
+                    };
+                    return Ok(IdempotencyState::Completed { fingerprint: record.fingerprint, claim: Box::new(claim) });
+                }
+            }
+            
+            if self.db.compare_and_swap(&record_key, current, Some(pending.as_slice()))?.is_ok() {
+                return Ok(IdempotencyState::Reserved);
+            }
+            // Another request with the same key got there first, look at what it stored
+        }
+    }
+    
+    /// Drop a reservation whose request did not claim anything, so the key can be retried
+    pub fn release_idempotency_key(&self, key: &str) -> Result<()> {
+        let record_key = Self::idempotency_record_key(key);
+        let Some(value) = self.db.get(&record_key)? else {
+            return Ok(());
+        };
+        let record: StoredIdempotency = serde_json::from_slice(&value)
+            .context("Failed to deserialize idempotency key")?;
+        
+        if record.result.is_none() {
+            let _ = self.db.compare_and_swap(&record_key, Some(value), None as Option<&[u8]>)?;
+        }
         Ok(())
     }
     
+    /// Delete idempotency keys first used before `cutoff`
+    pub fn purge_idempotency_keys(&self, cutoff: chrono::DateTime<chrono::Utc>) -> Result<usize> {
+        let mut purged = 0;
+        for result in self.db.scan_prefix(IDEMPOTENCY_PREFIX) {
+            let (key, value) = result?;
+            let record: StoredIdempotency = serde_json::from_slice(&value)
+                .context("Failed to deserialize idempotency key")?;
+            
+            if record.created_at < cutoff
+                && self.db.compare_and_swap(&key, Some(value), None as Option<&[u8]>)?.is_ok()
+            {
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+    
+    /// Completed record for a reserved idempotency key, storing the address exactly as it was in the pool
+    fn complete_idempotency(
+        &self,
+        key: Option<&str>,
+        pool: &str,
+        stored_address: &[u8],
+        lease: Option<&Lease>,
+    ) -> Result<Option<(String, Vec<u8>)>> {
+        let Some(key) = key else {
+            return Ok(None);
+        };
+        self.idempotency_result(key, StoredIdempotentClaim {
+            pool: pool.to_string(),
+            address: serde_json::from_slice(stored_address).context("Failed to deserialize address info")?,
+            batch: Vec::new(),
+            lease: lease.cloned(),
+        })
+        .map(Some)
+    }
+    
+    /// Like [`complete_idempotency`](Self::complete_idempotency), for a batch claim of the stored `entries`
+    fn complete_batch_idempotency(
+        &self,
+        key: Option<&str>,
+        pool: &str,
+        entries: &[(sled::IVec, sled::IVec)],
+    ) -> Result<Option<(String, Vec<u8>)>> {
+        let Some(key) = key else {
+            return Ok(None);
+        };
+        let batch = entries
+            .iter()
+            .map(|(_, value)| serde_json::from_slice(value).context("Failed to deserialize address info"))
+            .collect::<Result<Vec<StoredAddressInfo>>>()?;
+        let Some(first) = batch.first() else {
+            bail!("Empty batch claim");
+        };
+        self.idempotency_result(key, StoredIdempotentClaim {
+            pool: pool.to_string(),
+            address: first.clone(),
+            batch,
+            lease: None,
+        })
+        .map(Some)
+    }
+    
+    /// Record of the reserved idempotency `key`, completed with `result`
+    fn idempotency_result(&self, key: &str, result: StoredIdempotentClaim) -> Result<(String, Vec<u8>)> {
+        let record_key = Self::idempotency_record_key(key);
+        let value = self.db.get(&record_key)?
+            .context("Idempotency key was not reserved")?;
+        
+        let mut record: StoredIdempotency = serde_json::from_slice(&value)
+            .context("Failed to deserialize idempotency key")?;
+        record.result = Some(result);
+        
+        Ok((record_key, serde_json::to_vec(&record)?))
+    }
+    
+    fn api_key_key(id: &str) -> String {
+        format!("{}{}", API_KEY_PREFIX, id)
+    }
+    
+    pub fn store_api_key(&self, api_key: &ApiKey) -> Result<()> {
+        self.db.insert(Self::api_key_key(&api_key.id).as_bytes(), serde_json::to_vec(api_key)?)?;
+        self.db.flush()?;
+        Ok(())
+    }
+    
+    pub fn get_api_key(&self, id: &str) -> Result<Option<ApiKey>> {
+        self.db.get(Self::api_key_key(id))?
+            .map(|value| serde_json::from_slice(&value).context("Failed to deserialize API key"))
+            .transpose()
+    }
+    
+    pub fn list_api_keys(&self) -> Result<Vec<ApiKey>> {
+        self.db.scan_prefix(API_KEY_PREFIX)
+            .values()
+            .map(|value| serde_json::from_slice(&value?).context("Failed to deserialize API key"))
+            .collect()
+    }
+    
+    /// Delete an API key and its usage counters. Returns false if there was no such key.
+    pub fn delete_api_key(&self, id: &str) -> Result<bool> {
+        let existed = self.db.remove(Self::api_key_key(id))?.is_some();
+        
+        let usage_keys: Vec<_> = self.db.scan_prefix(format!("{}{}:", API_KEY_USAGE_PREFIX, id))
+            .keys()
+            .collect::<Result<_, _>>()?;
+        for key in usage_keys {
+            self.db.remove(key)?;
+        }
+        
+        self.db.flush()?;
+        Ok(existed)
+    }
+    
+    /// Usage counter keys of `id` for the UTC day and month of `at`
+    fn usage_keys(id: &str, at: chrono::DateTime<chrono::Utc>) -> (String, String) {
+        (
+            format!("{}{}:day:{}", API_KEY_USAGE_PREFIX, id, at.format("%Y-%m-%d")),
+            format!("{}{}:month:{}", API_KEY_USAGE_PREFIX, id, at.format("%Y-%m")),
+        )
+    }
+    
+    /// Addresses claimed with `id` today and this month
+    pub fn api_key_usage(&self, id: &str) -> Result<(u64, u64)> {
+        let (day_key, month_key) = Self::usage_keys(id, chrono::Utc::now());
+        let day = self.db.get(day_key)?.map(|v| decode_u64(&v)).unwrap_or(0);
+        let month = self.db.get(month_key)?.map(|v| decode_u64(&v)).unwrap_or(0);
+        Ok((day, month))
+    }
+    
+    /// Count `count` claims against the quotas of `api_key` for the day and month of `at`, unless
+    /// that would exceed one. Returns false, without counting anything, when over quota.
+    pub fn consume_claim_quota(&self, api_key: &ApiKey, count: u64, at: chrono::DateTime<chrono::Utc>) -> Result<bool> {
+        self.update_usage(&api_key.id, at, |day, month| {
+            let over_daily = api_key.daily_quota.is_some_and(|quota| day + count > quota);
+            let over_monthly = api_key.monthly_quota.is_some_and(|quota| month + count > quota);
+            (!over_daily && !over_monthly).then_some((day + count, month + count))
+        })
+    }
+    
+    /// Give back quota consumed at `charged_at` for claims that did not happen
+    pub fn refund_claim_quota(&self, api_key_id: &str, count: u64, charged_at: chrono::DateTime<chrono::Utc>) -> Result<()> {
+        self.update_usage(api_key_id, charged_at, |day, month| {
+            Some((day.saturating_sub(count), month.saturating_sub(count)))
+        })?;
+        Ok(())
+    }
+    
+    fn update_usage<F>(&self, id: &str, at: chrono::DateTime<chrono::Utc>, update: F) -> Result<bool>
+    where
+        F: Fn(u64, u64) -> Option<(u64, u64)>,
+    {
+        let (day_key, month_key) = Self::usage_keys(id, at);
+        
+        self.db
+            .transaction(|tx| -> ConflictableTransactionResult<bool> {
+                let day = tx.get(day_key.as_bytes())?.map(|v| decode_u64(&v)).unwrap_or(0);
+                let month = tx.get(month_key.as_bytes())?.map(|v| decode_u64(&v)).unwrap_or(0);
+                
+                let Some((day, month)) = update(day, month) else {
+                    return Ok(false);
+                };
+                tx.insert(day_key.as_bytes(), &day.to_be_bytes())?;
+                tx.insert(month_key.as_bytes(), &month.to_be_bytes())?;
+                Ok(true)
+            })
+            .map_err(|e| anyhow::anyhow!("Failed to update API key usage: {:?}", e))
+    }
+    
+    /// Move addresses stored under the pre-pool `address:` prefix into `pool`
+    pub fn migrate_legacy_addresses(&self, pool: &str) -> Result<usize> {
+        let mut migrated = 0;
+        
+        for result in self.db.scan_prefix(LEGACY_ADDRESS_PREFIX) {
+            let (key, value) = result?;
+            let address_info: PetAddressInfo = serde_json::from_slice(&value)
+                .context("Failed to deserialize legacy address info")?;
+            
+            let new_key = Self::address_key(pool, address_info.id);
+            let count_key = Self::pool_count_key(pool);
+            self.db
+                .transaction(|tx| -> ConflictableTransactionResult<()> {
+                    if tx.insert(new_key.as_bytes(), &value)?.is_none() {
+                        Self::adjust_count(tx, &count_key, 1)?;
+                    }
+                    tx.remove(&key)?;
+                    Ok(())
+                })
+                .map_err(|e| anyhow::anyhow!("Failed to migrate legacy address: {:?}", e))?;
+            
+            migrated += 1;
+        }
+        
+        if migrated > 0 {
+            self.db.flush()?;
+        }
+        
+        Ok(migrated)
+    }
+    
+    /// Seal every stored private key (pooled addresses, job results, leases and idempotent claims) with `new_key`, encrypting plaintext records and
+    /// re-wrapping the data keys of records sealed with the currently loaded key.
+    ///
+    /// Records already sealed with `new_key`'s version are left alone, so an interrupted run can
+    /// simply be repeated with the same keys. Meant to be run offline, while no server is using the database.
+    pub fn reencrypt(&self, new_key: &MasterKey) -> Result<usize> {
+        if self.master_key.as_ref().is_some_and(|current| current.version() == new_key.version()) {
+            bail!("The new master key needs a different version than the current one ({})", new_key.version());
+        }
+        
+        let mut reencrypted = 0;
+        
+        for result in self.db.scan_prefix("pool:") {
+            let (key, value) = result?;
+            if !key.windows(b":address:".len()).any(|w| w == b":address:") {
+                continue;
+            }
+            
+            let mut record: StoredAddressInfo = serde_json::from_slice(&value)
+                .context("Failed to deserialize address info")?;
+            
+            if !self.reseal(&mut record.sealed_private_key, &mut record.info.address, new_key)? {
+                continue;
+            }
+            
+            self.db.insert(&key, serde_json::to_vec(&record)?)?;
+            reencrypted += 1;
+        }
+        
+        for result in self.db.scan_prefix(JOB_PREFIX) {
+            let (key, value) = result?;
+            let mut record: StoredJob = serde_json::from_slice(&value)
+                .context("Failed to deserialize job")?;
+            let Some(address) = record.job.result.as_mut() else {
+                continue;
+            };
+            
+            if !self.reseal(&mut record.sealed_private_key, address, new_key)? {
+                continue;
+            }
+            
+            self.db.insert(&key, serde_json::to_vec(&record)?)?;
+            reencrypted += 1;
+        }
+        
+        for result in self.db.scan_prefix(LEASE_PREFIX) {
+            let (key, value) = result?;
+            let mut record: StoredLease = serde_json::from_slice(&value)
+                .context("Failed to deserialize lease")?;
+            
+            let address = &mut record.address;
+            if !self.reseal(&mut address.sealed_private_key, &mut address.info.address, new_key)? {
+                continue;
+            }
+            
+            self.db.insert(&key, serde_json::to_vec(&record)?)?;
+            reencrypted += 1;
+        }
+        
+        for result in self.db.scan_prefix(IDEMPOTENCY_PREFIX) {
+            let (key, value) = result?;
+            let mut record: StoredIdempotency = serde_json::from_slice(&value)
+                .context("Failed to deserialize idempotency key")?;
+            let Some(result) = record.result.as_mut() else {
+                continue;
+            };
+            
+            // A batch claim keeps every address next to the first one, and replays open them all
+            let mut resealed = 0;
+            for address in std::iter::once(&mut result.address).chain(result.batch.iter_mut()) {
+                if self.reseal(&mut address.sealed_private_key, &mut address.info.address, new_key)? {
+                    resealed += 1;
+                }
+            }
+            if resealed == 0 {
+                continue;
+            }
+            
+            self.db.insert(&key, serde_json::to_vec(&record)?)?;
+            reencrypted += resealed;
+        }
+        
+        for version in self.key_check_versions()? {
+            self.db.remove(Self::key_check_key(version))?;
+        }
+        self.write_key_check(new_key)?;
+        
+        Ok(reencrypted)
+    }
+    
+    /// Re-wrap a sealed private key for `new_key`, or seal a plaintext one with it.
+    /// Returns false, changing nothing, if it is already sealed with `new_key`'s version.
+    fn reseal(&self, sealed: &mut Option<SealedSecret>, address: &mut PetAddress, new_key: &MasterKey) -> Result<bool> {
+        let resealed = match sealed.as_ref() {
+            Some(current) if current.key_version == new_key.version() => return Ok(false),
+            Some(current) => {
+                let master_key = self.master_key.as_ref()
+                    .context("Database holds encrypted keys but no current master key is loaded")?;
+                master_key.rewrap(current, new_key)?
+            }
+            None => new_key.seal(&Self::take_secret(address)?, address.public_key.as_bytes())?,
+        };
+        
+        *sealed = Some(resealed);
+        Ok(true)
+    }
+    
     fn next_id(&self) -> Result<u64> {
+        self.next_counter(self.counter_key)
+    }
+    
+    fn next_counter(&self, counter_key: &str) -> Result<u64> {
         let id = self.db
-            .update_and_fetch(self.counter_key, |old| {
-                let current = old
-                    .map(|bytes| {
-                        let mut array = [0u8; 8];
-                        array.copy_from_slice(bytes);
-                        u64::from_be_bytes(array)
-                    })
-                    .unwrap_or(0);
+            .update_and_fetch(counter_key, |old| {
+                let current = old.map(decode_u64).unwrap_or(0);
                 Some((current + 1).to_be_bytes().to_vec())
             })?
             .context("Failed to update counter")?;
         
-        let mut array = [0u8; 8];
-        array.copy_from_slice(&id);
-        Ok(u64::from_be_bytes(array))
+        Ok(decode_u64(&id))
     }
+}
+
+fn decode_u64(bytes: &[u8]) -> u64 {
+    let mut array = [0u8; 8];
+    array.copy_from_slice(bytes);
+    u64::from_be_bytes(array)
 }
\ No newline at end of file
@@ -1,8 +1,11 @@
-use axum::{routing::get, Router};
+use axum::{routing::{delete, get, patch, post}, Router};
 use std::sync::Arc;
-use crate::handlers::{health_check, detailed_health_check, get_server_time, get_multi_timezone, get_pet_address, get_pet_status, PetAppState};
+use crate::handlers::{health_check, detailed_health_check, get_server_time, get_multi_timezone, get_pet_address, claim_pet_addresses, get_pet_status, get_pool_address, get_pool_status, list_pools, search_split_key, create_job, get_job, cancel_job, estimate_pattern, create_api_key, list_api_keys, delete_api_key, list_audit, get_address_audit, pause_generator, resume_generator, update_pool, pause_pool, resume_pool, refill_pool, clear_pool, create_lease, confirm_lease, release_lease, get_metrics, PetAppState};
 use crate::config::AppConfig;
 
+/// Routes that need the Pet app state
+pub type PetRouter = Router<Arc<PetAppState>>;
+
 pub fn health_routes() -> Router {
     Router::new()
         .route("/health", get(health_check))
@@ -15,32 +18,83 @@ pub fn time_routes() -> Router {
         .route("/time/zones", get(get_multi_timezone))
 }
 
-pub fn pet_routes() -> Router<Arc<PetAppState>> {
+pub fn pet_routes() -> PetRouter {
     Router::new()
         .route("/pet/address", get(get_pet_address))
+        .route("/pet/addresses", post(claim_pet_addresses))
+        .route("/pools/{name}/address", get(get_pool_address))
+        .route("/claims", post(create_lease))
+        .route("/claims/{id}/confirm", post(confirm_lease))
+        .route("/claims/{id}/release", post(release_lease))
+}
+
+pub fn metrics_routes() -> PetRouter {
+    Router::new().route("/metrics", get(get_metrics))
 }
 
-pub fn pet_status_routes() -> Router<Arc<PetAppState>> {
+pub fn pet_status_routes() -> PetRouter {
     Router::new()
         .route("/pet/status", get(get_pet_status))
+        .route("/pools", get(list_pools))
+        .route("/pools/{name}/status", get(get_pool_status))
 }
 
-pub fn api_routes(config: &AppConfig) -> (Router, Router<Arc<PetAppState>>, Router<Arc<PetAppState>>) {
-    let api_prefix = &config.api_base_url();
-    
-    let time_api = Router::new().nest(api_prefix, time_routes());
-    let pet_api = Router::new().nest(api_prefix, pet_routes());
-    let pet_status_api = Router::new().nest(api_prefix, pet_status_routes());
-    
-    (time_api, pet_api, pet_status_api)
+pub fn job_routes() -> PetRouter {
+    Router::new()
+        .route("/jobs", post(create_job))
+        .route("/split-key", post(search_split_key))
 }
 
-pub fn create_routes(config: &AppConfig) -> (Router, Router<Arc<PetAppState>>, Router<Arc<PetAppState>>) {
-    let (time_api, pet_api, pet_status_api) = api_routes(config);
+pub fn job_status_routes() -> PetRouter {
+    Router::new()
+        .route("/jobs/{id}", get(get_job).delete(cancel_job))
+        .route("/estimate", get(estimate_pattern))
+}
+
+pub fn admin_routes() -> PetRouter {
+    Router::new()
+        .route("/admin/api-keys", get(list_api_keys).post(create_api_key))
+        .route("/admin/api-keys/{id}", delete(delete_api_key))
+        .route("/admin/audit", get(list_audit))
+        .route("/admin/audit/{public_key}", get(get_address_audit))
+        .route("/admin/generator/pause", post(pause_generator))
+        .route("/admin/generator/resume", post(resume_generator))
+        .route("/admin/pools/{name}", patch(update_pool))
+        .route("/admin/pools/{name}/pause", post(pause_pool))
+        .route("/admin/pools/{name}/resume", post(resume_pool))
+        .route("/admin/pools/{name}/refill", post(refill_pool))
+        .route("/admin/pools/{name}/clear", post(clear_pool))
+}
+
+/// API routes grouped by the scope they need, so each group can get its own auth and rate limit
+pub struct ApiRoutes {
+    /// Health and time routes, never limited
+    pub base: Router,
+    /// Prometheus scrape endpoint, outside the API prefix like the health checks; needs a status key
+    pub metrics: PetRouter,
+    pub claim: PetRouter,
+    pub status: PetRouter,
+    /// Job submissions, which start searches and are limited on their own
+    pub jobs: PetRouter,
+    /// Job polling, cancellation and estimates, which need the jobs scope but are not limited
+    pub job_status: PetRouter,
+    pub admin: PetRouter,
+}
+
+pub fn create_routes(config: &AppConfig) -> ApiRoutes {
+    let api_prefix = &config.api_base_url();
     
-    let base_routes = Router::new()
+    let base = Router::new()
         .merge(health_routes())
-        .merge(time_api);
+        .nest(api_prefix, time_routes());
     
-    (base_routes, pet_api, pet_status_api)
-}
\ No newline at end of file
+    ApiRoutes {
+        base,
+        metrics: metrics_routes(),
+        claim: Router::new().nest(api_prefix, pet_routes()),
+        status: Router::new().nest(api_prefix, pet_status_routes()),
+        jobs: Router::new().nest(api_prefix, job_routes()),
+        job_status: Router::new().nest(api_prefix, job_status_routes()),
+        admin: Router::new().nest(api_prefix, admin_routes()),
+    }
+}
@@ -2,7 +2,7 @@ use std::env;
 
 pub fn load_env() -> anyhow::Result<()> {
     // Try to load .env file
-    if let Err(_) = dotenvy::dotenv() {
+    if dotenvy::dotenv().is_err() {
         tracing::warn!(".env file not found, using system environment variables");
     }
     Ok(())
@@ -23,7 +23,7 @@ pub fn validate_timezone(tz: &str) -> bool {
     }
     
     if tz.len() > 3 {
-        if !tz.chars().nth(3).map_or(false, |c| c == ':') {
+        if tz.chars().nth(3) != Some(':') {
             return false;
         }
         
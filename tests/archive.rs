@@ -0,0 +1,162 @@
+use PetAddr::pet::archive::{export_records, import_archive, read_archive, write_archive};
+use PetAddr::pet::{derive_keypair, PetAddress, PetPool, PetStorage, PoolKind, VanityPattern, SOLANA_DERIVATION_PATH};
+
+const PASSPHRASE: &str = "correct horse battery staple";
+
+/// First account of the BIP39 all-zero test vector, whose address ends in `k`
+const TEST_MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+fn pool(name: &str, kind: PoolKind) -> PetPool {
+    PetPool {
+        name: name.to_string(),
+        kind,
+        pattern: VanityPattern::new(None, Some("k".to_string()), true).unwrap(),
+        pool_size: 10,
+        low_watermark: None,
+        batch_size: 10,
+    }
+}
+
+fn storage(dir: &tempfile::TempDir, name: &str) -> PetStorage {
+    PetStorage::new(dir.path().join(name)).unwrap()
+}
+
+fn fill(storage: &PetStorage, pool: &PetPool, count: usize) -> Vec<String> {
+    (0..count)
+        .map(|_| {
+            let address = PetAddress::generate(&pool.pattern).unwrap();
+            let public_key = address.public_key.clone();
+            storage.store_address(&pool.name, address).unwrap();
+            public_key
+        })
+        .collect()
+}
+
+fn archive(storage: &PetStorage, pools: &[&str]) -> Vec<u8> {
+    let pools: Vec<String> = pools.iter().map(|pool| pool.to_string()).collect();
+    let records = export_records(storage, &pools).unwrap();
+    let mut archive = Vec::new();
+    write_archive(&mut archive, &records, PASSPHRASE).unwrap();
+    archive
+}
+
+fn public_keys(storage: &PetStorage, pool: &str) -> Vec<String> {
+    storage.list_public_keys(pool).unwrap().into_iter().map(|(_, key)| key).collect()
+}
+
+#[test]
+fn archive_round_trips_between_databases() {
+    let dir = tempfile::tempdir().unwrap();
+    let pet = pool("pet", PoolKind::Keypair);
+    let source = storage(&dir, "source");
+    let stored = fill(&source, &pet, 3);
+
+    let archive = archive(&source, &["pet"]);
+    let (manifest, records) = read_archive(archive.as_slice(), PASSPHRASE).unwrap();
+    assert_eq!(manifest.count, 3);
+    assert_eq!(manifest.pools["pet"], 3);
+    assert_eq!(records.len(), 3);
+    let text = String::from_utf8(archive).unwrap();
+    assert!(!text.contains(&records[0].info.address.private_key), "the archive holds a clear text key");
+
+    let destination = storage(&dir, "destination");
+    let summary = import_archive(&destination, &[pet], &records, None, false).unwrap();
+    assert_eq!((summary.imported, summary.duplicates, summary.mismatched), (3, 0, 0));
+    assert_eq!(public_keys(&destination, "pet"), stored);
+    assert_eq!(destination.get_next_address("pet").unwrap().unwrap().address.private_key, records[0].info.address.private_key);
+}
+
+#[test]
+fn wrong_passphrase_or_tampering_fails_the_import() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = storage(&dir, "source");
+    fill(&source, &pool("pet", PoolKind::Keypair), 2);
+    let archive = String::from_utf8(archive(&source, &["pet"])).unwrap();
+    let (header, body) = archive.split_once('\n').unwrap();
+
+    assert!(read_archive(archive.as_bytes(), "wrong passphrase").is_err());
+
+    let tampered_header = header.replace("\"count\":2", "\"count\":1");
+    assert_ne!(tampered_header, header);
+    let tampered = format!("{}\n{}", tampered_header, body);
+    assert!(read_archive(tampered.as_bytes(), PASSPHRASE).is_err(), "an edited manifest was accepted");
+
+    let mut body = body.as_bytes().to_vec();
+    body[10] = if body[10] == b'A' { b'B' } else { b'A' };
+    let tampered = format!("{}\n{}", header, String::from_utf8(body).unwrap());
+    assert!(read_archive(tampered.as_bytes(), PASSPHRASE).is_err(), "an edited body was accepted");
+}
+
+#[test]
+fn known_addresses_are_skipped() {
+    let dir = tempfile::tempdir().unwrap();
+    let pet = pool("pet", PoolKind::Keypair);
+    let source = storage(&dir, "source");
+    fill(&source, &pet, 3);
+    let (_, records) = read_archive(archive(&source, &["pet"]).as_slice(), PASSPHRASE).unwrap();
+
+    // Claimed addresses count as known as well
+    source.get_next_address("pet").unwrap().unwrap();
+    let summary = import_archive(&source, std::slice::from_ref(&pet), &records, None, false).unwrap();
+    assert_eq!((summary.imported, summary.duplicates), (0, 3));
+
+    let other = pool("other", PoolKind::Keypair);
+    let destination = storage(&dir, "destination");
+    import_archive(&destination, std::slice::from_ref(&other), &records, Some("other"), false).unwrap();
+    let summary = import_archive(&destination, &[other], &records, Some("other"), false).unwrap();
+    assert_eq!((summary.imported, summary.duplicates), (0, 3));
+    assert_eq!(destination.count_addresses("other").unwrap(), 3);
+}
+
+#[test]
+fn ids_are_renumbered_unless_preserved() {
+    let dir = tempfile::tempdir().unwrap();
+    let pet = pool("pet", PoolKind::Keypair);
+    let source = storage(&dir, "source");
+    fill(&source, &pet, 2);
+    let (_, records) = read_archive(archive(&source, &["pet"]).as_slice(), PASSPHRASE).unwrap();
+    let archived: Vec<u64> = records.iter().map(|record| record.info.id).collect();
+
+    // Ids this database already issued are refused, and nothing is imported
+    let busy = storage(&dir, "busy");
+    fill(&busy, &pet, 1);
+    assert!(import_archive(&busy, std::slice::from_ref(&pet), &records, None, true).is_err());
+    assert_eq!(busy.count_addresses("pet").unwrap(), 1);
+
+    let renumbered = import_archive(&busy, std::slice::from_ref(&pet), &records, None, false).unwrap();
+    assert_eq!(renumbered.imported, 2);
+    let ids: Vec<u64> = busy.list_public_keys("pet").unwrap().into_iter().map(|(id, _)| id).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+
+    let fresh = storage(&dir, "fresh");
+    import_archive(&fresh, std::slice::from_ref(&pet), &records, None, true).unwrap();
+    let ids: Vec<u64> = fresh.list_public_keys("pet").unwrap().into_iter().map(|(id, _)| id).collect();
+    assert_eq!(ids, archived);
+    assert_eq!(fresh.last_id().unwrap(), *archived.last().unwrap());
+    assert!(fresh.store_address("pet", PetAddress::generate(&pet.pattern).unwrap()).unwrap() > *archived.last().unwrap());
+}
+
+#[test]
+fn addresses_only_go_to_pools_of_their_kind() {
+    let dir = tempfile::tempdir().unwrap();
+    let source = storage(&dir, "source");
+    let keypair = derive_keypair(TEST_MNEMONIC, SOLANA_DERIVATION_PATH).unwrap();
+    source
+        .store_address("seed", PetAddress::from_mnemonic(&keypair, TEST_MNEMONIC, SOLANA_DERIVATION_PATH))
+        .unwrap();
+    fill(&source, &pool("pet", PoolKind::Keypair), 1);
+    let (_, seeds) = read_archive(archive(&source, &["seed"]).as_slice(), PASSPHRASE).unwrap();
+    let (_, keypairs) = read_archive(archive(&source, &["pet"]).as_slice(), PASSPHRASE).unwrap();
+
+    let destination = storage(&dir, "destination");
+    let pools = [pool("seed", PoolKind::Mnemonic), pool("pet", PoolKind::Keypair)];
+    assert!(import_archive(&destination, &pools, &seeds, Some("pet"), false).is_err());
+    assert!(import_archive(&destination, &pools, &keypairs, Some("seed"), false).is_err());
+    assert_eq!(destination.count_addresses("pet").unwrap() + destination.count_addresses("seed").unwrap(), 0);
+
+    assert_eq!(import_archive(&destination, &pools, &seeds, None, false).unwrap().imported, 1);
+    let restored = destination.get_next_address("seed").unwrap().unwrap().address;
+    assert_eq!(restored.mnemonic.as_deref(), Some(TEST_MNEMONIC));
+}
+
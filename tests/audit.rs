@@ -0,0 +1,80 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use PetAddr::pet::{ApiKey, Scope};
+
+use common::TestApp;
+
+#[tokio::test]
+async fn claims_are_recorded_without_their_secret() {
+    let app = TestApp::new(|_| {}).await;
+    let stored = app.fill_pool("pet", 2);
+    let claim = app.api_key(&[Scope::Claim]);
+    let admin = app.api_key(&[Scope::Admin]);
+
+    let claimed = app
+        .request(Method::GET, "/api/v1/pet/address", Some(&claim), &[("x-request-id", "req-42")], None)
+        .await;
+    assert_eq!(claimed.status, StatusCode::OK);
+    let private_key = claimed.data()["private_key"].as_str().unwrap().to_string();
+    assert_eq!(app.post("/api/v1/claims", Some(&claim), None).await.status, StatusCode::OK);
+
+    let audit = app.get(&format!("/api/v1/admin/audit/{}", stored[0]), Some(&admin)).await;
+    assert_eq!(audit.status, StatusCode::OK);
+    assert!(!audit.body.contains(&private_key), "audit log holds the private key");
+    let entries = audit.data();
+    assert_eq!(entries.as_array().unwrap().len(), 1);
+    assert_eq!(entries[0]["kind"], "claim");
+    assert_eq!(entries[0]["pool"], "pet");
+    assert_eq!(entries[0]["api_key"], ApiKey::parse_id(&claim).unwrap());
+    assert_eq!(entries[0]["request_id"], "req-42");
+
+    let leased = app.get(&format!("/api/v1/admin/audit/{}", stored[1]), Some(&admin)).await.data();
+    assert_eq!(leased[0]["kind"], "lease");
+
+    assert_eq!(app.get("/api/v1/admin/audit/unknown", Some(&admin)).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.get("/api/v1/admin/audit", Some(&claim)).await.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn audit_can_be_listed_by_time_range() {
+    let app = TestApp::new(|_| {}).await;
+    let stored = app.fill_pool("pet", 3);
+    let claim = app.api_key(&[Scope::Claim]);
+    let admin = app.api_key(&[Scope::Admin]);
+    for _ in 0..3 {
+        assert_eq!(app.get("/api/v1/pet/address", Some(&claim)).await.status, StatusCode::OK);
+    }
+
+    let all = app.get("/api/v1/admin/audit", Some(&admin)).await.data();
+    let public_keys: Vec<_> = all.as_array().unwrap().iter().map(|entry| entry["public_key"].clone()).collect();
+    assert_eq!(public_keys, stored, "entries are not oldest first");
+
+    let limited = app.get("/api/v1/admin/audit?limit=2", Some(&admin)).await.data();
+    assert_eq!(limited.as_array().unwrap().len(), 2);
+
+    let tomorrow = (chrono::Utc::now() + chrono::Duration::days(1)).format("%Y-%m-%dT%H:%M:%SZ");
+    let future = app.get(&format!("/api/v1/admin/audit?from={}", tomorrow), Some(&admin)).await.data();
+    assert!(future.as_array().unwrap().is_empty());
+    let past = app.get(&format!("/api/v1/admin/audit?to={}", "2020-01-01T00:00:00Z"), Some(&admin)).await.data();
+    assert!(past.as_array().unwrap().is_empty());
+
+    for query in ["from=yesterday", "limit=0", "limit=1001"] {
+        let invalid = app.get(&format!("/api/v1/admin/audit?{}", query), Some(&admin)).await;
+        assert_eq!(invalid.status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+}
+
+#[tokio::test]
+async fn purge_drops_entries_past_retention() {
+    let app = TestApp::new(|_| {}).await;
+    let stored = app.fill_pool("pet", 1);
+    let storage = app.storage();
+    storage.get_next_address("pet").unwrap().unwrap();
+    assert_eq!(storage.audit_for_public_key(&stored[0]).unwrap().len(), 1);
+
+    assert_eq!(storage.purge_audit(chrono::Utc::now() - chrono::Duration::hours(1)).unwrap(), 0);
+    assert_eq!(storage.purge_audit(chrono::Utc::now() + chrono::Duration::seconds(1)).unwrap(), 1);
+    assert!(storage.audit_for_public_key(&stored[0]).unwrap().is_empty());
+    assert!(storage.list_audit(None, None, 100).unwrap().is_empty());
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use axum::http::StatusCode;
+use PetAddr::config::{AuthConfig, RateLimitRule};
+use PetAddr::pet::{ApiKey, Scope};
+
+use common::TestApp;
+
+#[test]
+fn only_status_is_open_by_default() {
+    assert_eq!(AuthConfig::default().anonymous, vec![Scope::Status]);
+}
+
+#[test]
+fn keys_verify_only_against_their_own_hash() {
+    let (key, record) = ApiKey::generate("test".to_string(), vec![Scope::Claim], None, None, None).unwrap();
+    assert_eq!(ApiKey::parse_id(&key), Some(record.id.as_str()));
+    assert!(record.verify(&key));
+
+    let (other, _) = ApiKey::generate("other".to_string(), vec![Scope::Claim], None, None, None).unwrap();
+    assert!(!record.verify(&other));
+    assert!(!record.verify(&key[..key.len() - 1]));
+    assert!(!record.verify(""));
+}
+
+#[tokio::test]
+async fn requests_without_a_key_only_reach_status_routes() {
+    let app = TestApp::new(|_| {}).await;
+    app.fill_pool("pet", 1);
+
+    assert_eq!(app.get("/api/v1/pet/status", None).await.status, StatusCode::OK);
+
+    let missing = app.get("/api/v1/pet/address", None).await;
+    assert_eq!(missing.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(missing.headers["www-authenticate"], "Bearer");
+    assert_eq!(app.get("/api/v1/estimate?suffix=Pet", None).await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.get("/api/v1/admin/api-keys", None).await.status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn unknown_or_tampered_keys_are_rejected() {
+    let app = TestApp::new(|_| {}).await;
+    let key = app.api_key(&[Scope::Status]);
+
+    for presented in ["not-a-key", "pa_000000000000_abc", &format!("{}x", key)] {
+        let response = app.get("/api/v1/pet/status", Some(presented)).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED, "{}", presented);
+    }
+
+    let bearer = app
+        .request(axum::http::Method::GET, "/api/v1/pet/status", None, &[("authorization", &format!("Bearer {}", key))], None)
+        .await;
+    assert_eq!(bearer.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn keys_only_reach_the_groups_of_their_scopes() {
+    let app = TestApp::new(|_| {}).await;
+    app.fill_pool("pet", 1);
+    let status = app.api_key(&[Scope::Status]);
+    let admin = app.api_key(&[Scope::Admin]);
+
+    let forbidden = app.get("/api/v1/pet/address", Some(&status)).await;
+    assert_eq!(forbidden.status, StatusCode::FORBIDDEN);
+    assert!(forbidden.json()["message"].as_str().unwrap().contains("claim"));
+    assert_eq!(app.get("/api/v1/admin/api-keys", Some(&status)).await.status, StatusCode::FORBIDDEN);
+
+    // Admin keys can use every group
+    assert_eq!(app.get("/api/v1/admin/api-keys", Some(&admin)).await.status, StatusCode::OK);
+    assert_eq!(app.get("/api/v1/pet/address", Some(&admin)).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn claims_beyond_the_daily_quota_are_refused() {
+    let app = TestApp::new(|_| {}).await;
+    app.fill_pool("pet", 3);
+    let key = app.api_key_with(&[Scope::Claim], |record| record.daily_quota = Some(2));
+
+    assert_eq!(app.get("/api/v1/pet/address", Some(&key)).await.status, StatusCode::OK);
+    assert_eq!(app.get("/api/v1/pet/address", Some(&key)).await.status, StatusCode::OK);
+    assert_eq!(app.get("/api/v1/pet/address", Some(&key)).await.status, StatusCode::TOO_MANY_REQUESTS);
+
+    // The refused claim left its address in the pool for other keys
+    let other = app.api_key(&[Scope::Claim]);
+    assert_eq!(app.get("/api/v1/pet/address", Some(&other)).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn keys_with_their_own_rate_limit_are_counted_per_key() {
+    let app = TestApp::new(|_| {}).await;
+    let limited = app.api_key_with(&[Scope::Status], |record| {
+        record.rate_limit = Some(RateLimitRule { max_requests: 1, window_seconds: 60 });
+    });
+
+    let first = app.get("/api/v1/pet/status", Some(&limited)).await;
+    assert_eq!(first.status, StatusCode::OK);
+    assert_eq!(first.headers["ratelimit-limit"], "1");
+    assert_eq!(app.get("/api/v1/pet/status", Some(&limited)).await.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(app.get("/api/v1/pet/status", None).await.status, StatusCode::OK);
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use PetAddr::pet::{Claimant, Scope};
+
+use common::TestApp;
+
+const POOL: &str = "pet";
+
+fn public_keys(addresses: &Value) -> Vec<String> {
+    addresses
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|address| address["public_key"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn short_pool_claims_nothing_unless_partial_is_allowed() {
+    let app = TestApp::new(|_| {}).await;
+    let stored = app.fill_pool(POOL, 3);
+    let key = app.api_key(&[Scope::Claim]);
+
+    let short = app.post("/api/v1/pet/addresses", Some(&key), Some(json!({ "count": 5 }))).await;
+    assert_eq!(short.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.storage().count_addresses(POOL).unwrap(), 3, "a failed batch took addresses");
+
+    let partial = app
+        .post("/api/v1/pet/addresses", Some(&key), Some(json!({ "count": 5, "allow_partial": true })))
+        .await;
+    assert_eq!(partial.status, StatusCode::OK, "{}", partial.body);
+    let data = partial.data();
+    assert_eq!((data["requested"].as_u64(), data["claimed"].as_u64()), (Some(5), Some(3)));
+    assert_eq!(data["partial"], true);
+    assert_eq!(public_keys(&data["addresses"]), stored);
+
+    for count in [0, 101] {
+        let invalid = app.post("/api/v1/pet/addresses", Some(&key), Some(json!({ "count": count }))).await;
+        assert_eq!(invalid.status, StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn batch_can_be_streamed_as_ndjson() {
+    let app = TestApp::new(|_| {}).await;
+    let stored = app.fill_pool(POOL, 2);
+    let key = app.api_key(&[Scope::Claim]);
+
+    let response = app
+        .request(
+            Method::POST,
+            "/api/v1/pet/addresses",
+            Some(&key),
+            &[("accept", "application/x-ndjson")],
+            Some(json!({ "count": 3, "allow_partial": true, "format": "hex" })),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK, "{}", response.body);
+    assert_eq!(response.headers["content-type"], "application/x-ndjson");
+    assert_eq!(response.headers["x-claim-requested"], "3");
+    assert_eq!(response.headers["x-claim-count"], "2");
+    assert_eq!(response.headers["x-claim-partial"], "true");
+
+    let lines: Vec<Value> = response.body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(public_keys(&Value::Array(lines.clone())), stored);
+    assert!(lines.iter().all(|line| line["key_format"] == "hex"));
+}
+
+#[tokio::test]
+async fn batch_retries_replay_the_same_addresses() {
+    let app = TestApp::new(|_| {}).await;
+    app.fill_pool(POOL, 4);
+    let key = app.api_key(&[Scope::Claim]);
+    let claim_as = |body: Value| {
+        app.request(Method::POST, "/api/v1/pet/addresses", Some(&key), &[("idempotency-key", "batch-1")], Some(body))
+    };
+    let claim = |count: usize| claim_as(json!({ "count": count }));
+
+    let first = claim(2).await;
+    assert_eq!(first.status, StatusCode::OK, "{}", first.body);
+    let retry = claim(2).await;
+    assert_eq!(retry.status, StatusCode::OK);
+    assert_eq!(public_keys(&retry.data()["addresses"]), public_keys(&first.data()["addresses"]));
+    assert_eq!(claim(3).await.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(claim_as(json!({ "count": 2, "format": "hex" })).await.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    assert_eq!(app.storage().count_addresses(POOL).unwrap(), 2, "the retry claimed more addresses");
+}
+
+#[tokio::test]
+async fn failing_to_prepare_an_address_leaves_the_batch_in_the_pool() {
+    let app = TestApp::new(|_| {}).await;
+    app.fill_pool(POOL, 3);
+    let storage = app.storage();
+
+    // The second address fails, after the first was already prepared
+    let prepared = std::cell::Cell::new(0);
+    let failed = storage.claim_addresses_with(POOL, 3, &Claimant::default(), false, None, |address| {
+        prepared.set(prepared.get() + 1);
+        if prepared.get() == 2 {
+            anyhow::bail!("sealing failed");
+        }
+        Ok(address)
+    });
+    assert!(failed.is_err());
+    assert_eq!(storage.count_addresses(POOL).unwrap(), 3);
+    assert_eq!(storage.claim_addresses(POOL, 3, &Claimant::default(), false).unwrap().len(), 3);
+}
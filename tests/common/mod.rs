@@ -0,0 +1,163 @@
+//! Test server built from the real router, on a temporary database
+#![allow(dead_code)]
+
+use axum::body::Body;
+use axum::http::{HeaderMap, Method, Request, StatusCode};
+use axum::Router;
+use serde_json::Value;
+use solana_sdk::signature::Keypair;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+use PetAddr::config::AppConfig;
+use PetAddr::pet::{ApiKey, PetAddress, PetGenerator, PetStorage, Scope};
+
+const BASE_CONFIG: &str = r#"
+[server]
+host = "127.0.0.1"
+port = 0
+
+[api]
+base_path = "/api"
+version = "v1"
+
+[logging]
+level = "warn"
+format = "json"
+
+[swagger]
+enabled = false
+path = "/swagger-ui"
+title = "PetAddr"
+description = "PetAddr"
+version = "1.0.0"
+
+[pet_generator]
+pool_size = 10
+batch_size = 5
+db_path = ""
+
+[rate_limit]
+max_requests_per_minute = 1000
+window_seconds = 60
+"#;
+
+pub struct TestApp {
+    pub router: Router,
+    pub generator: Arc<PetGenerator>,
+    pub config: AppConfig,
+    _dir: tempfile::TempDir,
+}
+
+/// Response of [`TestApp::request`], with the body parsed as JSON when it is JSON
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+impl TestResponse {
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap_or_else(|e| panic!("{}: {}", e, self.body))
+    }
+
+    /// The `data` field of an `ApiResponse`
+    pub fn data(&self) -> Value {
+        self.json()["data"].clone()
+    }
+}
+
+/// Configuration of a test server; the generator is never started
+pub fn config() -> AppConfig {
+    config::Config::builder()
+        .add_source(config::File::from_str(BASE_CONFIG, config::FileFormat::Toml))
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap()
+}
+
+/// Admit any custom job, so tests can submit searches that never finish
+pub fn unlimited_jobs(config: &mut AppConfig) {
+    config.jobs.max_eta_seconds = 0;
+    config.jobs.max_difficulty = 0.0;
+}
+
+impl TestApp {
+    pub async fn new(configure: impl FnOnce(&mut AppConfig)) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = config();
+        config.pet_generator.db_path = dir.path().join("db").to_string_lossy().into_owned();
+        configure(&mut config);
+
+        let (router, generator) = PetAddr::create_app(config.clone()).await.unwrap();
+        Self { router, generator, config, _dir: dir }
+    }
+
+    pub fn storage(&self) -> Arc<PetStorage> {
+        self.generator.storage()
+    }
+
+    /// Store `count` fresh addresses in `pool`, returning their public keys
+    pub fn fill_pool(&self, pool: &str, count: usize) -> Vec<String> {
+        (0..count)
+            .map(|_| {
+                let address = PetAddress::from_keypair(&Keypair::new());
+                let public_key = address.public_key.clone();
+                self.storage().store_address(pool, address).unwrap();
+                public_key
+            })
+            .collect()
+    }
+
+    /// Create an API key with `scopes`, returning the key to present
+    pub fn api_key(&self, scopes: &[Scope]) -> String {
+        self.api_key_with(scopes, |_| {})
+    }
+
+    pub fn api_key_with(&self, scopes: &[Scope], configure: impl FnOnce(&mut ApiKey)) -> String {
+        let (key, mut record) = ApiKey::generate("test".to_string(), scopes.to_vec(), None, None, None).unwrap();
+        configure(&mut record);
+        self.storage().store_api_key(&record).unwrap();
+        key
+    }
+
+    pub async fn get(&self, uri: &str, key: Option<&str>) -> TestResponse {
+        self.request(Method::GET, uri, key, &[], None).await
+    }
+
+    pub async fn post(&self, uri: &str, key: Option<&str>, body: Option<Value>) -> TestResponse {
+        self.request(Method::POST, uri, key, &[], body).await
+    }
+
+    pub async fn request(
+        &self,
+        method: Method,
+        uri: &str,
+        key: Option<&str>,
+        headers: &[(&str, &str)],
+        body: Option<Value>,
+    ) -> TestResponse {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(key) = key {
+            request = request.header("x-api-key", key);
+        }
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        let request = match body {
+            Some(body) => request
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        TestResponse { status, headers, body: String::from_utf8_lossy(&body).into_owned() }
+    }
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use axum::http::StatusCode;
+use solana_sdk::signature::{Keypair, Signer};
+use PetAddr::pet::delivery::{generate_recipient_key, open_sealed_keypair, parse_recipient_key, seal_keypair};
+use PetAddr::pet::{PetAddress, Scope};
+
+#[test]
+fn sealed_keypair_opens_with_recipient_secret() {
+    let keypair = Keypair::new();
+    let address = PetAddress::from_keypair(&keypair);
+
+    let (secret, public) = generate_recipient_key();
+    let recipient = parse_recipient_key(&public).unwrap();
+    let sealed = seal_keypair(&recipient, &address).unwrap();
+
+    let opened = open_sealed_keypair(&secret, &sealed).unwrap();
+    assert_eq!(opened.pubkey(), keypair.pubkey());
+    assert_eq!(opened.to_bytes(), keypair.to_bytes());
+
+    let (other_secret, _) = generate_recipient_key();
+    assert!(open_sealed_keypair(&other_secret, &sealed).is_err());
+}
+
+#[tokio::test]
+async fn claims_seal_the_keypair_to_the_recipient() {
+    let app = common::TestApp::new(|_| {}).await;
+    let stored = app.fill_pool("pet", 2);
+    let key = app.api_key(&[Scope::Claim]);
+    let (secret, _) = generate_recipient_key();
+    let recipient = hex::encode(secret.public_key().as_bytes());
+
+    let claimed = app.get(&format!("/api/v1/pet/address?recipient_key={}", recipient), Some(&key)).await;
+    assert_eq!(claimed.status, StatusCode::OK, "{}", claimed.body);
+    let data = claimed.data();
+    assert!(data.get("private_key").is_none(), "the keypair was sent in clear");
+    assert_eq!(data["encryption"], "x25519-xsalsa20poly1305-sealedbox");
+    assert_eq!(data["public_key"], stored[0].as_str());
+
+    let opened = open_sealed_keypair(&secret, data["sealed_keypair"].as_str().unwrap()).unwrap();
+    assert_eq!(opened.pubkey().to_string(), stored[0]);
+
+    for query in ["recipient_key=not-a-key", &format!("recipient_key={}&format=hex", recipient)] {
+        let refused = app.get(&format!("/api/v1/pet/address?{}", query), Some(&key)).await;
+        assert_eq!(refused.status, StatusCode::BAD_REQUEST, "{}", query);
+    }
+    assert_eq!(app.storage().count_addresses("pet").unwrap(), 1, "a refused claim took an address");
+}
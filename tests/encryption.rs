@@ -0,0 +1,162 @@
+use std::path::Path;
+
+use solana_sdk::signature::Keypair;
+use PetAddr::pet::{Claimant, IdempotencyState, MasterKey, PetAddress, PetAddressInfo, PetStorage};
+
+const POOL: &str = "pet";
+
+/// Master key `version` made of 32 `byte`s
+type KeySpec = (u32, u8);
+
+fn key((version, byte): KeySpec) -> MasterKey {
+    MasterKey::from_bytes(version, &[byte; 32]).unwrap()
+}
+
+/// Run `open` until the database lock is free; sled lets go of it in the background after a drop
+fn retry_locked<T, E: std::fmt::Display>(mut open: impl FnMut() -> Result<T, E>) -> Result<T, E> {
+    for _ in 0..100 {
+        match open() {
+            Err(e) if e.to_string().contains("could not acquire lock") => {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            result => return result,
+        }
+    }
+    open()
+}
+
+fn open(path: &Path, master_key: Option<KeySpec>) -> anyhow::Result<PetStorage> {
+    retry_locked(|| PetStorage::open(path, master_key.map(key)))
+}
+
+fn store_addresses(storage: &PetStorage, count: usize) -> Vec<String> {
+    (0..count)
+        .map(|_| {
+            let address = PetAddress::from_keypair(&Keypair::new());
+            let private_key = address.private_key.clone();
+            storage.store_address(POOL, address).unwrap();
+            private_key
+        })
+        .collect()
+}
+
+fn claim_all(storage: &PetStorage) -> Vec<String> {
+    std::iter::from_fn(|| storage.get_next_address(POOL).unwrap())
+        .map(|info| info.address.private_key)
+        .collect()
+}
+
+#[test]
+fn sealed_secret_opens_only_with_its_key_and_record() {
+    let master_key = key((1, 7));
+    let sealed = master_key.seal(b"secret", b"record-a").unwrap();
+
+    assert_eq!(master_key.open(&sealed, b"record-a").unwrap(), b"secret");
+    assert!(master_key.open(&sealed, b"record-b").is_err(), "opened under another record's AAD");
+    assert!(key((1, 8)).open(&sealed, b"record-a").is_err(), "opened with another key");
+    assert!(key((2, 7)).open(&sealed, b"record-a").is_err(), "opened with another key version");
+}
+
+#[test]
+fn private_keys_are_not_stored_in_clear() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db");
+    let stored = {
+        let storage = open(&path, Some((1, 7))).unwrap();
+        let stored = store_addresses(&storage, 3);
+        storage.flush().unwrap();
+        stored
+    };
+
+    let raw = retry_locked(|| sled::open(&path)).unwrap();
+    for entry in raw.iter() {
+        let (_, value) = entry.unwrap();
+        let value = String::from_utf8_lossy(&value);
+        assert!(stored.iter().all(|private_key| !value.contains(private_key.as_str())));
+    }
+}
+
+#[test]
+fn database_refuses_the_wrong_key_or_no_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db");
+    {
+        let storage = open(&path, Some((1, 7))).unwrap();
+        store_addresses(&storage, 1);
+    }
+
+    assert!(open(&path, Some((1, 8))).is_err());
+    assert!(open(&path, Some((2, 7))).is_err());
+    assert!(open(&path, None).is_err());
+
+    let storage = open(&path, Some((1, 7))).unwrap();
+    assert_eq!(claim_all(&storage).len(), 1);
+}
+
+#[test]
+fn reencrypt_moves_every_record_to_the_new_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db");
+    let stored = {
+        let storage = open(&path, Some((1, 7))).unwrap();
+        let stored = store_addresses(&storage, 5);
+        assert_eq!(storage.reencrypt(&key((2, 9))).unwrap(), 5);
+
+        // A repeated run, as after an interruption, skips what is already done
+        assert_eq!(storage.reencrypt(&key((2, 9))).unwrap(), 0);
+        stored
+    };
+
+    assert!(open(&path, Some((1, 7))).is_err(), "old key still accepted");
+    let storage = open(&path, Some((2, 9))).unwrap();
+    assert_eq!(claim_all(&storage), stored);
+}
+
+#[test]
+fn reencrypt_encrypts_a_plaintext_database() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db");
+    let stored = {
+        let storage = open(&path, None).unwrap();
+        let stored = store_addresses(&storage, 3);
+        assert_eq!(storage.reencrypt(&key((1, 7))).unwrap(), 3);
+        stored
+    };
+
+    assert!(open(&path, None).is_err(), "encrypted database opened without a key");
+    let storage = open(&path, Some((1, 7))).unwrap();
+    assert_eq!(claim_all(&storage), stored);
+}
+
+#[test]
+fn reencrypt_needs_a_new_key_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = open(&dir.path().join("db"), Some((1, 7))).unwrap();
+    store_addresses(&storage, 1);
+
+    assert!(storage.reencrypt(&key((1, 9))).is_err());
+}
+
+#[test]
+fn reencrypt_keeps_batch_claims_replayable() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("db");
+    let retention = chrono::Duration::hours(1);
+    let claimed = {
+        let storage = open(&path, Some((1, 7))).unwrap();
+        store_addresses(&storage, 3);
+        assert!(matches!(storage.reserve_idempotency_key("batch-1", "batch", retention).unwrap(), IdempotencyState::Reserved));
+        let claimed = storage.claim_addresses_with(POOL, 2, &Claimant::default(), false, Some("batch-1"), Ok).unwrap();
+
+        // The address left in the pool, then the idempotency record: its first address and the whole batch
+        assert_eq!(storage.reencrypt(&key((2, 9))).unwrap(), 4);
+        claimed
+    };
+
+    let storage = open(&path, Some((2, 9))).unwrap();
+    let IdempotencyState::Completed { claim, .. } = storage.reserve_idempotency_key("batch-1", "batch", retention).unwrap() else {
+        panic!("batch claim was not replayed");
+    };
+    let private_keys = |addresses: &[PetAddressInfo]| addresses.iter().map(|info| info.address.private_key.clone()).collect::<Vec<_>>();
+    assert_eq!(private_keys(&claim.batch), private_keys(&claimed));
+}
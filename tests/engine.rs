@@ -0,0 +1,63 @@
+use std::time::{Duration, Instant};
+
+use PetAddr::pet::{SearchEngine, VanityPattern};
+
+fn suffix(suffix: &str) -> VanityPattern {
+    VanityPattern::new(None, Some(suffix.to_string()), false).unwrap()
+}
+
+#[tokio::test]
+async fn search_delivers_the_requested_matches() {
+    let engine = SearchEngine::new(2, 0);
+    assert_eq!(engine.worker_count(), 2);
+
+    let mut handle = engine.submit(suffix("p"), 3);
+    let mut found = Vec::new();
+    while let Some(address) = tokio::time::timeout(Duration::from_secs(30), handle.recv()).await.unwrap() {
+        found.push(address);
+    }
+
+    assert_eq!(found.len(), 3);
+    assert!(found.iter().all(|address| address.address.ends_with('p')));
+    assert!(handle.attempts() >= 3);
+}
+
+#[tokio::test]
+async fn searches_share_the_workers() {
+    let engine = SearchEngine::new(1, 0);
+
+    // A search that never finishes does not starve the ones queued after it
+    let _endless = engine.submit(suffix("zzzzzzzz"), 1);
+    let mut handle = engine.submit(suffix("p"), 1);
+
+    let found = tokio::time::timeout(Duration::from_secs(30), handle.recv()).await.unwrap();
+    assert!(found.unwrap().address.ends_with('p'));
+}
+
+#[tokio::test]
+async fn cancelled_search_stops_counting_attempts() {
+    let engine = SearchEngine::new(2, 0);
+    let handle = engine.submit(suffix("zzzzzzzz"), 1);
+    let control = handle.control();
+
+    while control.attempts() == 0 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    control.cancel();
+    // Workers finish the chunk they are in
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let attempts = handle.attempts();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert_eq!(handle.attempts(), attempts);
+}
+
+#[tokio::test]
+async fn search_does_not_block_the_async_runtime() {
+    let engine = SearchEngine::new(2, 0);
+    let _handle = engine.submit(suffix("zzzzzzzz"), 1);
+
+    // The test runs on a single-threaded runtime, which a search on it would stall
+    let started = Instant::now();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(started.elapsed() < Duration::from_millis(500), "took {:?}", started.elapsed());
+}
@@ -0,0 +1,85 @@
+use solana_sdk::signature::{Keypair, Signer};
+use PetAddr::pet::{export_key, import_key, ExportedKey, KeyFormat, PetAddress};
+
+#[test]
+fn every_key_format_round_trips() {
+    let keypair = Keypair::new();
+    let address = PetAddress::from_keypair(&keypair);
+
+    for format in KeyFormat::ALL {
+        let exported = export_key(&address, format).unwrap();
+
+        // What a client would get back from the JSON response
+        let json = serde_json::to_string(&exported).unwrap();
+        let parsed: ExportedKey = serde_json::from_str(&json).unwrap();
+
+        let imported = import_key(&parsed, format).unwrap();
+        assert_eq!(imported.pubkey(), keypair.pubkey(), "{}", format);
+        assert_eq!(imported.to_bytes(), keypair.to_bytes(), "{}", format);
+    }
+}
+
+#[test]
+fn solana_json_matches_a_keygen_file() {
+    let keypair = Keypair::new();
+    let address = PetAddress::from_keypair(&keypair);
+
+    let exported = export_key(&address, KeyFormat::SolanaJson).unwrap();
+    let json = serde_json::to_string(&exported).unwrap();
+
+    // solana-keygen writes the 64 keypair bytes as a plain JSON array
+    let bytes: Vec<u8> = serde_json::from_str(&json).unwrap();
+    assert_eq!(bytes, keypair.to_bytes().to_vec());
+}
+
+#[test]
+fn text_formats_match_their_encodings() {
+    let keypair = Keypair::new();
+    let address = PetAddress::from_keypair(&keypair);
+
+    assert_eq!(
+        export_key(&address, KeyFormat::Base58).unwrap(),
+        ExportedKey::Text(keypair.to_base58_string())
+    );
+    assert_eq!(
+        export_key(&address, KeyFormat::Hex).unwrap(),
+        ExportedKey::Text(hex::encode(keypair.to_bytes()))
+    );
+}
+
+#[test]
+fn mismatched_or_corrupt_keys_are_rejected() {
+    let keypair = Keypair::new();
+    let address = PetAddress::from_keypair(&keypair);
+
+    let text = export_key(&address, KeyFormat::Hex).unwrap();
+    let bytes = export_key(&address, KeyFormat::SolanaJson).unwrap();
+    assert!(import_key(&text, KeyFormat::SolanaJson).is_err());
+    assert!(import_key(&bytes, KeyFormat::Hex).is_err());
+
+    assert!(import_key(&ExportedKey::Text("not hex".to_string()), KeyFormat::Hex).is_err());
+    assert!(import_key(&ExportedKey::Bytes(vec![1; 32]), KeyFormat::SolanaJson).is_err());
+}
+
+#[test]
+fn key_format_names_parse() {
+    for format in KeyFormat::ALL {
+        assert_eq!(format.as_str().parse::<KeyFormat>().unwrap(), format);
+    }
+    assert_eq!(KeyFormat::default(), KeyFormat::Base58);
+    assert!("json".parse::<KeyFormat>().is_err());
+}
+
+#[test]
+fn claim_responses_document_the_exported_key() {
+    use utoipa::OpenApi;
+
+    let doc = serde_json::to_value(PetAddr::ApiDoc::openapi()).unwrap();
+    let schemas = &doc["components"]["schemas"];
+    assert!(schemas.get("ExportedKey").is_some());
+    assert!(schemas.get("PrivateKey").is_none());
+    assert_eq!(
+        schemas["GetPetAddressResponse"]["properties"]["private_key"]["$ref"],
+        "#/components/schemas/ExportedKey"
+    );
+}
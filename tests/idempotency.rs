@@ -0,0 +1,94 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use PetAddr::pet::Scope;
+
+use common::{TestApp, TestResponse};
+
+async fn claim(app: &TestApp, uri: &str, key: Option<&str>, idempotency_key: &str, body: Option<Value>) -> TestResponse {
+    let method = if uri.ends_with("/claims") { Method::POST } else { Method::GET };
+    app.request(method, uri, key, &[("idempotency-key", idempotency_key)], body).await
+}
+
+#[tokio::test]
+async fn retries_return_the_same_address() {
+    let app = TestApp::new(|_| {}).await;
+    app.fill_pool("pet", 2);
+    let key = app.api_key(&[Scope::Claim]);
+
+    let first = claim(&app, "/api/v1/pet/address", Some(&key), "order-1", None).await;
+    assert_eq!(first.status, StatusCode::OK, "{}", first.body);
+    let retry = claim(&app, "/api/v1/pet/address", Some(&key), "order-1", None).await;
+    assert_eq!(retry.status, StatusCode::OK);
+    assert_eq!(retry.data()["public_key"], first.data()["public_key"]);
+    assert_eq!(retry.data()["private_key"], first.data()["private_key"]);
+
+    // Naming the default format is the same request, another format is not
+    let base58 = claim(&app, "/api/v1/pet/address?format=base58", Some(&key), "order-1", None).await;
+    assert_eq!(base58.status, StatusCode::OK);
+    assert_eq!(base58.data()["public_key"], first.data()["public_key"]);
+    let hex = claim(&app, "/api/v1/pet/address?format=hex", Some(&key), "order-1", None).await;
+    assert_eq!(hex.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Only one address was taken
+    let next = app.get("/api/v1/pet/address", Some(&key)).await;
+    assert_eq!(next.status, StatusCode::OK);
+    assert_ne!(next.data()["public_key"], first.data()["public_key"]);
+}
+
+#[tokio::test]
+async fn keys_of_different_clients_do_not_collide() {
+    let app = TestApp::new(|_| {}).await;
+    app.fill_pool("pet", 2);
+    let alice = app.api_key(&[Scope::Claim]);
+    let bob = app.api_key(&[Scope::Claim]);
+
+    let first = claim(&app, "/api/v1/pet/address", Some(&alice), "order-1", None).await;
+    let second = claim(&app, "/api/v1/pet/address", Some(&bob), "order-1", None).await;
+    assert_eq!(first.status, StatusCode::OK);
+    assert_eq!(second.status, StatusCode::OK, "{}", second.body);
+    assert_ne!(first.data()["public_key"], second.data()["public_key"], "replayed another client's claim");
+}
+
+#[tokio::test]
+async fn anonymous_requests_cannot_use_idempotency_keys() {
+    let app = TestApp::new(|config| config.auth.anonymous.push(Scope::Claim)).await;
+    app.fill_pool("pet", 2);
+
+    let anonymous = claim(&app, "/api/v1/pet/address", None, "order-1", None).await;
+    assert_eq!(anonymous.status, StatusCode::BAD_REQUEST);
+    let leased = claim(&app, "/api/v1/claims", None, "order-1", None).await;
+    assert_eq!(leased.status, StatusCode::BAD_REQUEST);
+
+    // Nothing was taken by the refused requests
+    assert_eq!(app.get("/api/v1/pet/address", None).await.status, StatusCode::OK);
+    assert_eq!(app.get("/api/v1/pet/address", None).await.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn lease_retries_stop_once_the_lease_is_gone() {
+    let app = TestApp::new(|_| {}).await;
+    app.fill_pool("pet", 2);
+    let key = app.api_key(&[Scope::Claim]);
+    let body = || Some(json!({ "ttl_seconds": 60 }));
+
+    let lease = claim(&app, "/api/v1/claims", Some(&key), "lease-1", body()).await;
+    assert_eq!(lease.status, StatusCode::OK, "{}", lease.body);
+    let id = lease.data()["lease_id"].as_str().unwrap().to_string();
+
+    let retry = claim(&app, "/api/v1/claims", Some(&key), "lease-1", body()).await;
+    assert_eq!(retry.data()["lease_id"], id.as_str());
+    let other_ttl = claim(&app, "/api/v1/claims", Some(&key), "lease-1", Some(json!({ "ttl_seconds": 30 }))).await;
+    assert_eq!(other_ttl.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let released = app.post(&format!("/api/v1/claims/{}/release", id), Some(&key), None).await;
+    assert_eq!(released.status, StatusCode::OK);
+    assert_eq!(claim(&app, "/api/v1/claims", Some(&key), "lease-1", body()).await.status, StatusCode::GONE);
+
+    // A confirmed lease is gone as well
+    let lease = claim(&app, "/api/v1/claims", Some(&key), "lease-2", body()).await.data();
+    let confirm = format!("/api/v1/claims/{}/confirm", lease["lease_id"].as_str().unwrap());
+    assert_eq!(app.post(&confirm, Some(&key), None).await.status, StatusCode::OK);
+    assert_eq!(claim(&app, "/api/v1/claims", Some(&key), "lease-2", body()).await.status, StatusCode::GONE);
+}
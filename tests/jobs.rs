@@ -0,0 +1,151 @@
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::{Method, StatusCode};
+use serde_json::json;
+use PetAddr::config::JobsConfig;
+use PetAddr::pet::{Estimate, JobManager, JobStatus, PetStorage, Scope, SearchEngine, VanityJob, VanityPattern};
+
+use common::TestApp;
+
+fn pattern(prefix: Option<&str>, suffix: Option<&str>) -> VanityPattern {
+    VanityPattern::new(prefix.map(str::to_string), suffix.map(str::to_string), false).unwrap()
+}
+
+/// A pattern no test will ever find
+fn impossible() -> VanityPattern {
+    pattern(Some("zzzzzzzz"), None)
+}
+
+fn manager(storage: &Arc<PetStorage>) -> JobManager {
+    manager_with(storage, JobsConfig::default())
+}
+
+fn manager_with(storage: &Arc<PetStorage>, limits: JobsConfig) -> JobManager {
+    JobManager::new(Arc::clone(storage), Arc::new(SearchEngine::new(1, 0)), limits)
+}
+
+async fn wait_for_completion(jobs: &JobManager, id: &str) -> VanityJob {
+    for _ in 0..600 {
+        let progress = jobs.get(id).unwrap().unwrap();
+        if progress.job.status != JobStatus::Running {
+            return progress.job;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("job {} did not finish", id);
+}
+
+#[tokio::test]
+async fn submitted_job_completes_with_a_matching_keypair() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(PetStorage::new(dir.path().join("db")).unwrap());
+    let jobs = manager(&storage);
+
+    let job = jobs.submit(pattern(None, Some("p")), Some("owner")).unwrap();
+    assert_eq!(job.status, JobStatus::Running);
+    assert_eq!(job.id.len(), 32, "job ids are 16 random bytes");
+
+    let job = wait_for_completion(&jobs, &job.id).await;
+    assert_eq!(job.status, JobStatus::Completed);
+    assert!(job.finished_at.is_some());
+    let result = job.result.unwrap();
+    assert!(result.address.ends_with('p'));
+    assert!(!result.private_key.is_empty());
+
+    // Finished jobs can no longer be cancelled
+    assert_eq!(jobs.cancel(&job.id).unwrap().unwrap().status, JobStatus::Completed);
+}
+
+#[tokio::test]
+async fn cancelled_job_stops_running() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(PetStorage::new(dir.path().join("db")).unwrap());
+    let jobs = manager(&storage);
+
+    let job = jobs.submit(impossible(), None).unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let cancelled = jobs.cancel(&job.id).unwrap().unwrap();
+    assert_eq!(cancelled.status, JobStatus::Cancelled);
+    assert!(cancelled.finished_at.is_some());
+
+    let progress = jobs.get(&job.id).unwrap().unwrap();
+    assert_eq!(progress.job.status, JobStatus::Cancelled);
+    assert_eq!(progress.rate, None);
+    assert!(jobs.cancel("unknown").unwrap().is_none());
+}
+
+#[tokio::test]
+async fn running_jobs_are_resumed_after_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(PetStorage::new(dir.path().join("db")).unwrap());
+
+    // Left running by a server that stopped, next to one that had finished
+    let running = VanityJob::new(pattern(None, Some("p")), 58.0, None);
+    let mut cancelled = VanityJob::new(impossible(), 1e14, None);
+    cancelled.status = JobStatus::Cancelled;
+    storage.store_job(&running).unwrap();
+    storage.store_job(&cancelled).unwrap();
+
+    let jobs = manager(&storage);
+    assert_eq!(jobs.resume().unwrap(), 1);
+    assert_eq!(jobs.resume().unwrap(), 0, "a running job was started twice");
+
+    assert_eq!(wait_for_completion(&jobs, &running.id).await.status, JobStatus::Completed);
+    assert_eq!(jobs.get(&cancelled.id).unwrap().unwrap().job.status, JobStatus::Cancelled);
+}
+
+#[tokio::test]
+async fn jobs_are_only_visible_to_the_key_that_submitted_them() {
+    let app = TestApp::new(common::unlimited_jobs).await;
+    let owner = app.api_key(&[Scope::Jobs]);
+    let other = app.api_key(&[Scope::Jobs]);
+
+    let created = app.post("/api/v1/jobs", Some(&owner), Some(json!({ "prefix": "zzzzzzzz" }))).await;
+    assert_eq!(created.status, StatusCode::OK, "{}", created.body);
+    let id = created.data()["id"].as_str().unwrap().to_string();
+    let uri = format!("/api/v1/jobs/{}", id);
+
+    assert_eq!(app.get(&uri, Some(&owner)).await.status, StatusCode::OK);
+    assert_eq!(app.get(&uri, Some(&other)).await.status, StatusCode::NOT_FOUND);
+
+    let cancel = |key| app.request(Method::DELETE, &uri, Some(key), &[], None);
+    assert_eq!(cancel(&other).await.status, StatusCode::NOT_FOUND);
+    let cancelled = cancel(&owner).await;
+    assert_eq!(cancelled.status, StatusCode::OK);
+    assert_eq!(cancelled.data()["status"], "cancelled");
+}
+
+#[test]
+fn admission_fails_closed_until_a_rate_is_known() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(PetStorage::new(dir.path().join("db")).unwrap());
+    let cheap = pattern(None, Some("p"));
+
+    let jobs = manager(&storage);
+    assert!(jobs.admits(&Estimate::new(&cheap, None)));
+    assert!(!jobs.admits(&Estimate::new(&impossible(), None)), "the default difficulty cap let it through");
+    assert!(!jobs.admits(&Estimate::new(&pattern(None, Some("Pet")), Some(1.0))), "too slow at the measured rate");
+
+    let no_cap = manager_with(&storage, JobsConfig { max_eta_seconds: 3600, max_difficulty: 0.0 });
+    assert!(!no_cap.admits(&Estimate::new(&cheap, None)), "admitted without any limit to check");
+    assert!(no_cap.admits(&Estimate::new(&cheap, Some(1000.0))));
+
+    let open = manager_with(&storage, JobsConfig { max_eta_seconds: 0, max_difficulty: 0.0 });
+    assert!(open.admits(&Estimate::new(&impossible(), None)));
+}
+
+#[tokio::test]
+async fn impossible_jobs_are_refused_before_a_rate_is_measured() {
+    let app = TestApp::new(|_| {}).await;
+    let key = app.api_key(&[Scope::Jobs]);
+
+    let estimate = app.get("/api/v1/estimate?prefix=zzzzzzzz", Some(&key)).await.data();
+    assert!(estimate["hash_rate"].is_null());
+    assert_eq!(estimate["job_allowed"], false);
+    let refused = app.post("/api/v1/jobs", Some(&key), Some(json!({ "prefix": "zzzzzzzz" }))).await;
+    assert_eq!(refused.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
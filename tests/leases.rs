@@ -0,0 +1,106 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+use PetAddr::pet::{ApiKey, Claimant, Scope};
+
+use common::TestApp;
+
+const POOL: &str = "pet";
+
+#[tokio::test]
+async fn lease_hands_out_the_keypair_only_on_confirmation() {
+    let app = TestApp::new(|_| {}).await;
+    let stored = app.fill_pool(POOL, 1);
+    let key = app.api_key(&[Scope::Claim]);
+
+    let leased = app.post("/api/v1/claims", Some(&key), None).await;
+    assert_eq!(leased.status, StatusCode::OK, "{}", leased.body);
+    let lease = leased.data();
+    assert_eq!(lease["public_key"], stored[0]);
+    assert!(!leased.body.contains("private_key"), "lease exposed the key: {}", leased.body);
+    let id = lease["lease_id"].as_str().unwrap();
+
+    let confirmed = app
+        .post(&format!("/api/v1/claims/{}/confirm", id), Some(&key), Some(json!({ "format": "hex" })))
+        .await;
+    assert_eq!(confirmed.status, StatusCode::OK, "{}", confirmed.body);
+    let confirmed = confirmed.data();
+    assert_eq!(confirmed["status"], "confirmed");
+    assert_eq!(confirmed["address"]["public_key"], stored[0]);
+    assert_eq!(confirmed["address"]["key_format"], "hex");
+    assert_eq!(confirmed["address"]["private_key"].as_str().unwrap().len(), 128);
+
+    // Confirmed for good: nothing to confirm or release any more, and the pool is empty
+    let again = app.post(&format!("/api/v1/claims/{}/confirm", id), Some(&key), None).await;
+    assert_eq!(again.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.post(&format!("/api/v1/claims/{}/release", id), Some(&key), None).await.status, StatusCode::NOT_FOUND);
+    assert_eq!(app.post("/api/v1/claims", Some(&key), None).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn released_lease_returns_its_address_and_quota() {
+    let app = TestApp::new(|_| {}).await;
+    let stored = app.fill_pool(POOL, 1);
+    let key = app.api_key_with(&[Scope::Claim], |record| record.daily_quota = Some(1));
+    let other = app.api_key(&[Scope::Claim]);
+
+    let lease = app.post("/api/v1/claims", Some(&key), None).await.data();
+    let release = format!("/api/v1/claims/{}/release", lease["lease_id"].as_str().unwrap());
+
+    assert_eq!(app.post(&release, Some(&other), None).await.status, StatusCode::NOT_FOUND);
+    let released = app.post(&release, Some(&key), None).await;
+    assert_eq!(released.status, StatusCode::OK);
+    assert_eq!(released.data()["status"], "released");
+
+    // Same address, and the quota of one claim a day was given back
+    let again = app.post("/api/v1/claims", Some(&key), None).await;
+    assert_eq!(again.status, StatusCode::OK, "{}", again.body);
+    assert_eq!(again.data()["public_key"], stored[0]);
+}
+
+#[tokio::test]
+async fn expired_lease_goes_back_to_the_pool() {
+    let app = TestApp::new(|_| {}).await;
+    let stored = app.fill_pool(POOL, 1);
+    let key = app.api_key(&[Scope::Claim]);
+
+    let lease = app.post("/api/v1/claims", Some(&key), Some(json!({ "ttl_seconds": 1 }))).await.data();
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+
+    let confirm = format!("/api/v1/claims/{}/confirm", lease["lease_id"].as_str().unwrap());
+    assert_eq!(app.post(&confirm, Some(&key), None).await.status, StatusCode::GONE);
+
+    let again = app.post("/api/v1/claims", Some(&key), None).await;
+    assert_eq!(again.status, StatusCode::OK, "{}", again.body);
+    assert_eq!(again.data()["public_key"], stored[0]);
+}
+
+#[tokio::test]
+async fn reaper_refunds_the_period_the_lease_was_charged_in() {
+    let app = TestApp::new(|_| {}).await;
+    app.fill_pool(POOL, 1);
+    let storage = app.storage();
+    let (_, api_key) = ApiKey::generate("test".to_string(), vec![Scope::Claim], Some(1), Some(1), None).unwrap();
+    storage.store_api_key(&api_key).unwrap();
+
+    // Charged last month, expiring this month
+    let now = chrono::Utc::now();
+    let charged_at = now - chrono::Duration::days(40);
+    assert!(storage.consume_claim_quota(&api_key, 1, charged_at).unwrap());
+    let claimant = Claimant { api_key: Some(api_key.id.clone()), ..Claimant::default() };
+    let (lease, _) = storage
+        .lease_next_address(POOL, &claimant, chrono::Duration::seconds(60), charged_at, None)
+        .unwrap()
+        .unwrap();
+
+    let released = storage.release_expired_leases(now + chrono::Duration::seconds(120)).unwrap();
+    assert_eq!(released.len(), 1);
+    assert_eq!(released[0].id, lease.id);
+    assert!(storage.get_lease(&lease.id).unwrap().is_none());
+
+    // The old period has its claim back, and the current one was never touched
+    assert!(storage.consume_claim_quota(&api_key, 1, charged_at).unwrap());
+    assert_eq!(storage.api_key_usage(&api_key.id).unwrap(), (0, 0));
+    assert!(storage.release_expired_leases(now + chrono::Duration::seconds(120)).unwrap().is_empty());
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use axum::http::StatusCode;
+use PetAddr::pet::Scope;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn metrics_need_a_status_key_even_when_status_is_open() {
+    let app = TestApp::new(|_| {}).await;
+    assert!(app.config.auth.anonymous.contains(&Scope::Status));
+    let claim = app.api_key(&[Scope::Claim]);
+
+    assert_eq!(app.get("/api/v1/pet/status", None).await.status, StatusCode::OK);
+    assert_eq!(app.get("/metrics", None).await.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(app.get("/metrics", Some(&claim)).await.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn metrics_report_pool_depth_and_claims() {
+    let app = TestApp::new(|_| {}).await;
+    app.fill_pool("pet", 3);
+    let status = app.api_key(&[Scope::Status]);
+    let claim = app.api_key(&[Scope::Claim]);
+
+    assert_eq!(app.get("/api/v1/pet/address", Some(&claim)).await.status, StatusCode::OK);
+
+    let response = app.get("/metrics", Some(&status)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.headers["content-type"].to_str().unwrap().starts_with("text/plain"));
+
+    let lines: Vec<&str> = response.body.lines().collect();
+    assert!(lines.contains(&r#"petaddr_pool_depth{pool="pet"} 2"#), "{}", response.body);
+    assert!(lines.contains(&r#"petaddr_pool_target{pool="pet"} 10"#), "{}", response.body);
+    assert!(lines.contains(&r#"petaddr_addresses_claimed_total{pool="pet"} 1"#), "{}", response.body);
+    assert!(lines.contains(&r#"petaddr_claim_requests_total{outcome="claimed",pool="pet"} 1"#), "{}", response.body);
+    assert!(lines.iter().any(|line| line.starts_with("petaddr_http_request_duration_seconds_count")));
+}
@@ -0,0 +1,64 @@
+use solana_sdk::signature::Signer;
+use PetAddr::pet::delivery::{generate_recipient_key, open_sealed_mnemonic, parse_recipient_key, seal_mnemonic};
+use PetAddr::pet::{derive_keypair, KeySearch, MnemonicSearch, PetAddress, VanityPattern, SOLANA_DERIVATION_PATH};
+
+/// The BIP39 test vector with an all-zero entropy
+const TEST_MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+#[test]
+fn derives_the_address_wallets_restore() {
+    let keypair = derive_keypair(TEST_MNEMONIC, SOLANA_DERIVATION_PATH).unwrap();
+    assert_eq!(keypair.pubkey().to_string(), "HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk");
+
+    let other_account = derive_keypair(TEST_MNEMONIC, "m/44'/501'/1'/0'").unwrap();
+    assert_ne!(other_account.pubkey(), keypair.pubkey());
+
+    assert!(derive_keypair("abandon abandon", SOLANA_DERIVATION_PATH).is_err());
+    assert!(derive_keypair(TEST_MNEMONIC, "not a path").is_err());
+}
+
+#[test]
+fn sealed_mnemonic_opens_with_recipient_secret() {
+    let keypair = derive_keypair(TEST_MNEMONIC, SOLANA_DERIVATION_PATH).unwrap();
+    let address = PetAddress::from_mnemonic(&keypair, TEST_MNEMONIC, SOLANA_DERIVATION_PATH);
+
+    let (secret, public) = generate_recipient_key();
+    let recipient = parse_recipient_key(&public).unwrap();
+    let sealed = seal_mnemonic(&recipient, &address).unwrap().unwrap();
+    assert!(!sealed.contains("abandon"));
+    assert_eq!(open_sealed_mnemonic(&secret, &sealed).unwrap(), TEST_MNEMONIC);
+
+    let (other_secret, _) = generate_recipient_key();
+    assert!(open_sealed_mnemonic(&other_secret, &sealed).is_err());
+
+    // Plain keypairs have no phrase to seal
+    assert!(seal_mnemonic(&recipient, &PetAddress::from_keypair(&keypair)).unwrap().is_none());
+}
+
+#[test]
+fn search_finds_a_restorable_mnemonic() {
+    let pattern = VanityPattern::new(None, Some("p".to_string()), true).unwrap();
+    let search = MnemonicSearch { pattern: pattern.clone() };
+
+    let mut matches = Vec::new();
+    for _ in 0..100 {
+        search.search_chunk(u64::MAX, &mut |address| {
+            matches.push(address);
+            false
+        });
+        if !matches.is_empty() {
+            break;
+        }
+    }
+    let address = matches.pop().expect("no match within 6400 mnemonics");
+
+    assert!(pattern.matches(&address.public_key));
+    assert_eq!(address.derivation_path.as_deref(), Some(SOLANA_DERIVATION_PATH));
+    let phrase = address.mnemonic.as_deref().unwrap();
+    assert_eq!(phrase.split_whitespace().count(), 12);
+
+    let restored = derive_keypair(phrase, SOLANA_DERIVATION_PATH).unwrap();
+    assert_eq!(restored.pubkey().to_string(), address.public_key);
+    assert_eq!(bs58::encode(restored.to_bytes()).into_string(), address.private_key);
+}
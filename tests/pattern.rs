@@ -0,0 +1,47 @@
+use PetAddr::pet::VanityPattern;
+
+const ADDRESS: &str = "7kXzDogs9nR2vQhVbFq8tYwM3pLcE5uGjA6sH4eKxPet";
+
+fn pattern(prefix: Option<&str>, suffix: Option<&str>, case_insensitive: bool) -> VanityPattern {
+    VanityPattern::new(prefix.map(str::to_string), suffix.map(str::to_string), case_insensitive).unwrap()
+}
+
+#[test]
+fn prefix_and_suffix_match_the_ends_of_the_address() {
+    assert!(pattern(Some("7kX"), None, false).matches(ADDRESS));
+    assert!(!pattern(Some("kXz"), None, false).matches(ADDRESS));
+
+    assert!(pattern(None, Some("Pet"), false).matches(ADDRESS));
+    assert!(!pattern(None, Some("xPe"), false).matches(ADDRESS));
+
+    assert!(pattern(Some("7k"), Some("Pet"), false).matches(ADDRESS));
+    assert!(!pattern(Some("7k"), Some("Cat"), false).matches(ADDRESS));
+}
+
+#[test]
+fn case_is_only_ignored_when_asked() {
+    assert!(!pattern(None, Some("pet"), false).matches(ADDRESS));
+    assert!(pattern(None, Some("pet"), true).matches(ADDRESS));
+    assert!(pattern(None, Some("PET"), true).matches(ADDRESS));
+    assert!(pattern(Some("7KX"), None, true).matches(ADDRESS));
+}
+
+#[test]
+fn empty_parts_are_ignored_and_one_is_required() {
+    let suffix_only = VanityPattern::new(Some(String::new()), Some("Pet".to_string()), false).unwrap();
+    assert_eq!(suffix_only.prefix, None);
+    assert!(VanityPattern::new(None, None, false).is_err());
+    assert!(VanityPattern::new(Some(String::new()), Some(String::new()), false).is_err());
+}
+
+#[test]
+fn characters_outside_base58_are_rejected() {
+    for invalid in ["0", "O", "I", "l", "Pe-t", "P t"] {
+        assert!(VanityPattern::new(None, Some(invalid.to_string()), false).is_err(), "{}", invalid);
+    }
+
+    // Only the upper case `L` is in the alphabet, so `l` is accepted when case is ignored
+    assert!(VanityPattern::new(Some("oil".to_string()), None, false).is_err());
+    assert!(VanityPattern::new(Some("oil".to_string()), None, true).is_ok());
+    assert!(VanityPattern::new(None, Some("0".to_string()), true).is_err());
+}
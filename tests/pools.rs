@@ -0,0 +1,244 @@
+mod common;
+
+use axum::http::{Method, StatusCode};
+use serde_json::{json, Value};
+use solana_sdk::signature::Keypair;
+use PetAddr::config::{AppConfig, PatternConfig, PoolConfig};
+use PetAddr::pet::{PetAddress, PoolKind, Scope};
+
+use common::{TestApp, TestResponse};
+
+const POOL_URI: &str = "/api/v1/admin/pools/pet";
+
+async fn update(app: &TestApp, key: &str, body: Value) -> TestResponse {
+    app.request(Method::PATCH, POOL_URI, Some(key), &[], Some(body)).await
+}
+
+#[tokio::test]
+async fn low_watermark_is_reset_with_an_explicit_null() {
+    let app = TestApp::new(|_| {}).await;
+    let admin = app.api_key(&[Scope::Admin]);
+
+    let set = update(&app, &admin, json!({ "low_watermark": 4 })).await;
+    assert_eq!(set.status, StatusCode::OK, "{}", set.body);
+    assert_eq!(set.data()["low_watermark"], 4);
+
+    // Omitting the field keeps it
+    let kept = update(&app, &admin, json!({ "batch_size": 3 })).await.data();
+    assert_eq!((kept["low_watermark"].as_u64(), kept["batch_size"].as_u64()), (Some(4), Some(3)));
+
+    let reset = update(&app, &admin, json!({ "low_watermark": null })).await.data();
+    assert_eq!(reset["low_watermark"], reset["pool_size"]);
+    let stored = app.storage().get_pool_settings("pet").unwrap().unwrap();
+    assert_eq!(stored.low_watermark, None);
+    assert_eq!(stored.batch_size, 3);
+}
+
+#[tokio::test]
+async fn invalid_settings_are_refused_and_change_nothing() {
+    let app = TestApp::new(|_| {}).await;
+    let admin = app.api_key(&[Scope::Admin]);
+    let claim = app.api_key(&[Scope::Claim]);
+
+    for body in [json!({ "batch_size": 0 }), json!({ "low_watermark": 11 }), json!({ "pool_size": 2, "low_watermark": 3 })] {
+        assert_eq!(update(&app, &admin, body.clone()).await.status, StatusCode::BAD_REQUEST, "{}", body);
+    }
+    assert!(app.storage().get_pool_settings("pet").unwrap().is_none(), "a refused change was stored");
+
+    let unknown = app.request(Method::PATCH, "/api/v1/admin/pools/other", Some(&admin), &[], Some(json!({}))).await;
+    assert_eq!(unknown.status, StatusCode::NOT_FOUND);
+    assert_eq!(update(&app, &claim, json!({ "pool_size": 5 })).await.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn pools_pause_and_resume_alone_or_together() {
+    let app = TestApp::new(|_| {}).await;
+    let admin = app.api_key(&[Scope::Admin]);
+    app.generator.start().await.unwrap();
+
+    let paused = app.post(&format!("{}/pause", POOL_URI), Some(&admin), None).await;
+    assert_eq!(paused.status, StatusCode::OK);
+    assert_eq!(paused.data()["state"], "paused");
+    assert!(app.storage().get_pool_settings("pet").unwrap().unwrap().paused);
+
+    // Pausing keeps settings changed before
+    let resized = update(&app, &admin, json!({ "pool_size": 20 })).await.data();
+    assert_eq!((resized["pool_size"].as_u64(), resized["state"].as_str()), (Some(20), Some("paused")));
+
+    let resumed = app.post("/api/v1/admin/generator/resume", Some(&admin), None).await.data();
+    assert!(resumed.as_array().unwrap().iter().all(|pool| pool["state"] != "paused"));
+    let settings = app.storage().get_pool_settings("pet").unwrap().unwrap();
+    assert_eq!((settings.paused, settings.pool_size), (false, 20));
+
+    let all = app.post("/api/v1/admin/generator/pause", Some(&admin), None).await.data();
+    assert!(all.as_array().unwrap().iter().all(|pool| pool["state"] == "paused"));
+    app.generator.stop().await.unwrap();
+}
+
+#[tokio::test]
+async fn refill_needs_a_running_unpaused_generator() {
+    let app = TestApp::new(|_| {}).await;
+    let admin = app.api_key(&[Scope::Admin]);
+    let uri = format!("{}/refill", POOL_URI);
+    let refill = || app.post(&uri, Some(&admin), None);
+
+    assert_eq!(refill().await.status, StatusCode::CONFLICT, "refilled with the generator stopped");
+
+    app.generator.start().await.unwrap();
+    app.post(&format!("{}/pause", POOL_URI), Some(&admin), None).await;
+    assert_eq!(refill().await.status, StatusCode::CONFLICT, "refilled a paused pool");
+
+    app.post(&format!("{}/resume", POOL_URI), Some(&admin), None).await;
+    assert_eq!(refill().await.status, StatusCode::OK);
+    app.generator.stop().await.unwrap();
+}
+
+#[tokio::test]
+async fn clear_needs_the_pool_name_repeated() {
+    let app = TestApp::new(|_| {}).await;
+    app.fill_pool("pet", 3);
+    let admin = app.api_key(&[Scope::Admin]);
+    let uri = format!("{}/clear", POOL_URI);
+    let clear = |confirm: &str| app.post(&uri, Some(&admin), Some(json!({ "confirm": confirm })));
+
+    assert_eq!(clear("other").await.status, StatusCode::BAD_REQUEST);
+    assert_eq!(app.storage().count_addresses("pet").unwrap(), 3);
+
+    let cleared = clear("pet").await;
+    assert_eq!(cleared.status, StatusCode::OK);
+    assert_eq!(cleared.data()["removed"], 3);
+    assert_eq!(app.storage().count_addresses("pet").unwrap(), 0);
+}
+
+#[tokio::test]
+async fn concurrent_changes_do_not_undo_each_other() {
+    let app = TestApp::new(|_| {}).await;
+    let pool = app.generator.pool("pet").unwrap().clone();
+
+    std::thread::scope(|scope| {
+        for batch_size in 1..=8 {
+            let (generator, pool) = (&app.generator, &pool);
+            scope.spawn(move || {
+                generator.update_settings(pool, |settings| {
+                    settings.paused = true;
+                    true
+                }).unwrap();
+                generator.update_settings(pool, |settings| {
+                    settings.pool_size += 1;
+                    settings.batch_size = batch_size;
+                    true
+                }).unwrap();
+            });
+        }
+    });
+
+    let settings = app.generator.settings(&pool).unwrap();
+    assert!(settings.paused);
+    assert_eq!(settings.pool_size, 18, "an increment was lost");
+    assert_eq!(app.storage().get_pool_settings("pet").unwrap().unwrap().pool_size, 18);
+}
+
+fn two_pools(config: &mut AppConfig) {
+    let pool = |name: &str, suffix: &str, pool_size: usize| PoolConfig {
+        name: name.to_string(),
+        kind: PoolKind::Keypair,
+        pool_size,
+        low_watermark: None,
+        batch_size: 5,
+        pattern: PatternConfig { prefix: None, suffix: Some(suffix.to_string()), case_insensitive: false },
+    };
+    config.pet_generator.pools = vec![pool("pet", "Pet", 10), pool("dog", "Dog", 4)];
+}
+
+#[tokio::test]
+async fn named_pools_are_claimed_from_separately() {
+    let app = TestApp::new(two_pools).await;
+    let pets = app.fill_pool("pet", 1);
+    let dogs = app.fill_pool("dog", 2);
+    let key = app.api_key(&[Scope::Claim]);
+
+    let dog = app.get("/api/v1/pools/dog/address", Some(&key)).await;
+    assert_eq!(dog.status, StatusCode::OK, "{}", dog.body);
+    assert_eq!(dog.data()["public_key"], dogs[0].as_str());
+    // The first configured pool is the default one
+    assert_eq!(app.get("/api/v1/pet/address", Some(&key)).await.data()["public_key"], pets[0].as_str());
+    assert_eq!(app.get("/api/v1/pet/address", Some(&key)).await.status, StatusCode::NOT_FOUND);
+
+    let batch = app.post("/api/v1/pet/addresses", Some(&key), Some(json!({ "count": 1, "pool": "dog" }))).await;
+    assert_eq!(batch.data()["addresses"][0]["public_key"], dogs[1].as_str());
+
+    assert_eq!(app.get("/api/v1/pools/cat/address", Some(&key)).await.status, StatusCode::NOT_FOUND);
+    let unknown = app.post("/api/v1/pet/addresses", Some(&key), Some(json!({ "count": 1, "pool": "cat" }))).await;
+    assert_eq!(unknown.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn every_pool_reports_its_own_status() {
+    let app = TestApp::new(two_pools).await;
+    app.fill_pool("dog", 3);
+    let status = app.api_key(&[Scope::Status]);
+
+    let pools = app.get("/api/v1/pools", Some(&status)).await.data();
+    let summary: Vec<_> = pools
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|pool| (pool["pool"].clone(), pool["pattern"].clone(), pool["total_addresses"].clone(), pool["pool_size"].clone()))
+        .collect();
+    assert_eq!(summary, vec![
+        (json!("pet"), json!("*Pet"), json!(0), json!(10)),
+        (json!("dog"), json!("*Dog"), json!(3), json!(4)),
+    ]);
+
+    let dog = app.get("/api/v1/pools/dog/status", Some(&status)).await.data();
+    assert_eq!(dog["total_addresses"], 3);
+    assert_eq!(app.get("/api/v1/pools/cat/status", Some(&status)).await.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn pool_names_must_be_unique_and_valid() {
+    for configure in [
+        |config: &mut AppConfig| {
+            two_pools(config);
+            config.pet_generator.pools[1].name = "pet".to_string();
+        },
+        |config: &mut AppConfig| {
+            two_pools(config);
+            config.pet_generator.pools[1].name = "dog/cat".to_string();
+        },
+    ] {
+        let dir = tempfile::tempdir().unwrap();
+        let mut config = common::config();
+        config.pet_generator.db_path = dir.path().join("db").to_string_lossy().into_owned();
+        configure(&mut config);
+        assert!(PetAddr::create_app(config).await.is_err());
+    }
+}
+
+#[tokio::test]
+async fn status_reports_the_generator_as_it_is() {
+    let app = TestApp::new(|_| {}).await;
+    let storage = app.storage();
+    for _ in 0..2 {
+        storage.store_generated_address("pet", PetAddress::from_keypair(&Keypair::new())).unwrap();
+    }
+    app.fill_pool("pet", 1);
+    let status = app.api_key(&[Scope::Status]);
+    let claim = app.api_key(&[Scope::Claim]);
+    assert_eq!(app.get("/api/v1/pet/address", Some(&claim)).await.status, StatusCode::OK);
+
+    let stopped = app.get("/api/v1/pet/status", Some(&status)).await.data();
+    assert_eq!(stopped["state"], "stopped");
+    assert_eq!(stopped["generation_active"], false);
+    assert!(stopped["batch_in_flight"].is_null());
+    assert_eq!(stopped["total_addresses"], 2);
+    // Claimed addresses still count, addresses stored by hand never did
+    assert_eq!(stopped["generated_total"], 2);
+    assert_eq!((stopped["pool_size"].as_u64(), stopped["low_watermark"].as_u64()), (Some(10), Some(10)));
+
+    app.generator.start().await.unwrap();
+    let running = app.get("/api/v1/pet/status", Some(&status)).await.data();
+    assert_ne!(running["state"], "stopped");
+    app.generator.stop().await.unwrap();
+    assert_eq!(app.get("/api/v1/pet/status", Some(&status)).await.data()["state"], "stopped");
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use axum::http::StatusCode;
+use serde_json::json;
+use PetAddr::config::RateLimitRule;
+use PetAddr::middleware::RateLimiter;
+use PetAddr::pet::Scope;
+
+use common::TestApp;
+
+#[tokio::test]
+async fn limiter_counts_each_client_within_the_window() {
+    let limiter = RateLimiter::new(2, 60);
+
+    let first = limiter.check("a");
+    assert!(first.allowed);
+    assert_eq!((first.limit, first.remaining), (2, 1));
+    assert!(limiter.check("a").allowed);
+
+    let rejected = limiter.check("a");
+    assert!(!rejected.allowed);
+    assert_eq!(rejected.remaining, 0);
+    assert!((1..=60).contains(&rejected.reset_seconds()));
+
+    assert!(limiter.check("b").allowed, "clients share a counter");
+}
+
+#[tokio::test]
+async fn limiter_message_names_its_rule() {
+    let rule = RateLimitRule { max_requests: 3, window_seconds: 10 };
+    let limiter = RateLimiter::from_rule(&rule, "At most {limit} per {window}s");
+    assert_eq!(limiter.message(), "At most 3 per 10s");
+}
+
+#[tokio::test]
+async fn job_submissions_are_limited_but_polling_is_not() {
+    let app = TestApp::new(|config| {
+        common::unlimited_jobs(config);
+        config.rate_limit.jobs = Some(RateLimitRule { max_requests: 2, window_seconds: 60 });
+    })
+    .await;
+    let key = app.api_key(&[Scope::Jobs]);
+    let submit = || app.post("/api/v1/jobs", Some(&key), Some(json!({ "suffix": "zzzzzzzz" })));
+
+    let created = submit().await;
+    assert_eq!(created.status, StatusCode::OK, "{}", created.body);
+    assert_eq!(created.headers["ratelimit-limit"], "2");
+    assert_eq!(created.headers["ratelimit-remaining"], "1");
+    let uri = format!("/api/v1/jobs/{}", created.data()["id"].as_str().unwrap());
+
+    assert_eq!(submit().await.status, StatusCode::OK);
+    let rejected = submit().await;
+    assert_eq!(rejected.status, StatusCode::TOO_MANY_REQUESTS);
+    assert!(rejected.headers.contains_key("retry-after"));
+    assert_eq!(rejected.json()["code"], 429);
+
+    for _ in 0..5 {
+        let polled = app.get(&uri, Some(&key)).await;
+        assert_eq!(polled.status, StatusCode::OK);
+        assert!(!polled.headers.contains_key("ratelimit-limit"));
+        assert_eq!(app.get("/api/v1/estimate?suffix=Pet", Some(&key)).await.status, StatusCode::OK);
+    }
+}
+
+#[tokio::test]
+async fn claim_and_job_limits_are_counted_separately() {
+    let app = TestApp::new(|config| {
+        common::unlimited_jobs(config);
+        config.rate_limit.max_requests_per_minute = 1;
+        config.rate_limit.jobs = Some(RateLimitRule { max_requests: 1, window_seconds: 60 });
+    })
+    .await;
+    app.fill_pool("pet", 2);
+    let key = app.api_key(&[Scope::Claim, Scope::Jobs]);
+
+    assert_eq!(app.get("/api/v1/pet/address", Some(&key)).await.status, StatusCode::OK);
+    assert_eq!(app.get("/api/v1/pet/address", Some(&key)).await.status, StatusCode::TOO_MANY_REQUESTS);
+
+    let job = app.post("/api/v1/jobs", Some(&key), Some(json!({ "suffix": "zzzzzzzz" }))).await;
+    assert_eq!(job.status, StatusCode::OK, "{}", job.body);
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use std::time::Duration;
+
+use PetAddr::pet::{ApiKey, Scope};
+
+use common::TestApp;
+
+/// A write made while handling a request, one sled leaves to its background flusher
+fn charge_claim(app: &TestApp, key: &str) -> anyhow::Result<()> {
+    let id = ApiKey::parse_id(key).unwrap();
+    let api_key = app.storage().get_api_key(id)?.unwrap();
+    app.storage().consume_claim_quota(&api_key, 1, chrono::Utc::now())?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn database_is_flushed_after_requests_drain() {
+    let app = TestApp::new(|_| {}).await;
+    app.generator.start().await.unwrap();
+    let storage = app.storage();
+    let key = app.api_key(&[Scope::Claim]);
+
+    // A request still writing after the generator has already stopped
+    let drain = async {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        charge_claim(&app, &key)
+    };
+    PetAddr::shutdown(drain, &app.generator).await.unwrap();
+
+    assert!(!app.generator.is_running().await);
+    assert_eq!(storage.api_key_usage(ApiKey::parse_id(&key).unwrap()).unwrap(), (1, 1));
+    assert_eq!(storage.flush().unwrap(), 0, "the drained request's write was not flushed");
+}
+
+#[tokio::test]
+async fn drain_errors_are_reported_after_flushing() {
+    let app = TestApp::new(|_| {}).await;
+    let storage = app.storage();
+    let key = app.api_key(&[Scope::Claim]);
+
+    let drain = async {
+        charge_claim(&app, &key)?;
+        anyhow::bail!("connection reset")
+    };
+    let result = PetAddr::shutdown(drain, &app.generator).await;
+
+    assert_eq!(result.unwrap_err().to_string(), "connection reset");
+    assert_eq!(storage.flush().unwrap(), 0);
+}
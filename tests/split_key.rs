@@ -0,0 +1,84 @@
+mod common;
+
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use PetAddr::pet::{PartialKey, Scope, SearchEngine, SplitKeySearch, SplitKeySecret, VanityPattern};
+
+use common::TestApp;
+
+#[tokio::test]
+async fn combined_split_key_signs_for_the_vanity_address() {
+    let secret = SplitKeySecret::generate();
+    let pattern = VanityPattern::new(None, Some("p".to_string()), false).unwrap();
+    let search = SplitKeySearch::new(&secret.public_point(), pattern.clone()).unwrap();
+
+    // The server only ever sees the public point
+    let engine = SearchEngine::new(1, 0);
+    let mut handle = engine.submit_search(search, 1);
+    let partial = handle.recv().await.unwrap();
+    assert!(pattern.matches(&partial.address));
+
+    let key = secret.combine(&partial).unwrap();
+    assert_eq!(key.pubkey().to_string(), partial.address);
+
+    let message = b"split-key signing test";
+    let signature = key.sign(message);
+    assert!(signature.verify(key.pubkey().as_ref(), message));
+    assert!(!signature.verify(key.pubkey().as_ref(), b"another message"));
+}
+
+#[test]
+fn combine_rejects_a_partial_key_for_another_address() {
+    let secret = SplitKeySecret::generate();
+    let other = SplitKeySecret::generate();
+
+    let partial = PartialKey {
+        partial_scalar: other.to_base58(),
+        address: other.public_point(),
+    };
+    assert!(secret.combine(&partial).is_err());
+}
+
+#[test]
+fn split_key_search_rejects_invalid_points() {
+    let pattern = VanityPattern::new(None, Some("p".to_string()), false).unwrap();
+    assert!(SplitKeySearch::new("not-base58!", pattern.clone()).is_err());
+    assert!(SplitKeySearch::new(&bs58::encode([0u8; 32]).into_string(), pattern).is_err());
+}
+
+#[tokio::test]
+async fn split_key_requests_run_as_jobs_of_their_key() {
+    let app = TestApp::new(|_| {}).await;
+    let owner = app.api_key(&[Scope::Jobs]);
+    let other = app.api_key(&[Scope::Jobs]);
+    let secret = SplitKeySecret::generate();
+
+    let invalid = app.post("/api/v1/split-key", Some(&owner), Some(json!({ "public_point": "not-base58!", "suffix": "p" }))).await;
+    assert_eq!(invalid.status, StatusCode::BAD_REQUEST);
+
+    let created = app
+        .post("/api/v1/split-key", Some(&owner), Some(json!({ "public_point": secret.public_point(), "suffix": "p" })))
+        .await;
+    assert_eq!(created.status, StatusCode::OK, "{}", created.body);
+    let id = created.data()["id"].as_str().unwrap().to_string();
+    let uri = format!("/api/v1/jobs/{}", id);
+    assert_eq!(app.get(&uri, Some(&other)).await.status, StatusCode::NOT_FOUND);
+
+    let mut job = Value::Null;
+    for _ in 0..600 {
+        job = app.get(&uri, Some(&owner)).await.data();
+        if job["status"] != "running" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert_eq!(job["status"], "completed");
+    assert_eq!(job["public_point"], secret.public_point());
+    assert!(job["result"].is_null(), "a split-key job has no keypair");
+
+    let partial: PartialKey = serde_json::from_value(job["partial_key"].clone()).unwrap();
+    assert!(partial.address.ends_with('p'));
+    assert_eq!(secret.combine(&partial).unwrap().pubkey().to_string(), partial.address);
+}
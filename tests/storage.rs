@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use solana_sdk::signature::Keypair;
+use PetAddr::pet::{Claimant, PetAddress, PetStorage};
+
+const POOL: &str = "pet";
+
+fn fill_pool(storage: &PetStorage, count: usize) {
+    for _ in 0..count {
+        storage
+            .store_address(POOL, PetAddress::from_keypair(&Keypair::new()))
+            .unwrap();
+    }
+}
+
+#[test]
+fn concurrent_claims_never_hand_out_the_same_address() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(PetStorage::new(dir.path().join("db")).unwrap());
+
+    const ADDRESSES: usize = 200;
+    const CLAIMERS: usize = 400;
+    fill_pool(&storage, ADDRESSES);
+
+    let handles: Vec<_> = (0..CLAIMERS)
+        .map(|_| {
+            let storage = Arc::clone(&storage);
+            std::thread::spawn(move || storage.get_next_address(POOL).unwrap())
+        })
+        .collect();
+
+    let claimed: Vec<_> = handles
+        .into_iter()
+        .filter_map(|handle| handle.join().unwrap())
+        .collect();
+
+    let ids: HashSet<_> = claimed.iter().map(|info| info.id).collect();
+    let keys: HashSet<_> = claimed.iter().map(|info| info.address.private_key.clone()).collect();
+
+    assert_eq!(claimed.len(), ADDRESSES);
+    assert_eq!(ids.len(), ADDRESSES, "an id was handed out twice");
+    assert_eq!(keys.len(), ADDRESSES, "a private key was handed out twice");
+    assert_eq!(storage.count_addresses(POOL).unwrap(), 0);
+}
+
+#[test]
+fn address_counter_matches_a_scan_under_concurrent_inserts_and_claims() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(PetStorage::new(dir.path().join("db")).unwrap());
+
+    const INSERTERS: usize = 4;
+    const INSERTS: usize = 50;
+    const CLAIMERS: usize = 8;
+    const ROUNDS: usize = 30;
+    fill_pool(&storage, 20);
+
+    let inserters = (0..INSERTERS).map(|_| {
+        let storage = Arc::clone(&storage);
+        std::thread::spawn(move || fill_pool(&storage, INSERTS))
+    });
+    let claimers = (0..CLAIMERS).map(|i| {
+        let storage = Arc::clone(&storage);
+        std::thread::spawn(move || {
+            let claimant = Claimant::default();
+            for _ in 0..ROUNDS {
+                match i % 3 {
+                    0 => {
+                        storage.get_next_address(POOL).unwrap();
+                    }
+                    1 => {
+                        storage.claim_addresses(POOL, 3, &claimant, true).unwrap();
+                    }
+                    _ => {
+                        // Released leases put their address back into the pool
+                        let ttl = chrono::Duration::seconds(60);
+                        if let Some((lease, _)) = storage.lease_next_address(POOL, &claimant, ttl, chrono::Utc::now(), None).unwrap() {
+                            storage.release_lease(&lease.id).unwrap();
+                        }
+                    }
+                }
+            }
+        })
+    });
+
+    let handles: Vec<_> = inserters.chain(claimers).collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let counted = storage.count_addresses(POOL).unwrap();
+    assert_eq!(counted, storage.recount_addresses(POOL).unwrap());
+    assert_eq!(storage.repair_address_count(POOL).unwrap(), None, "the counter drifted");
+
+    storage.clear_all_addresses(POOL).unwrap();
+    assert_eq!(storage.count_addresses(POOL).unwrap(), 0);
+    assert_eq!(storage.recount_addresses(POOL).unwrap(), 0);
+}
+
+#[test]
+fn generated_total_counts_every_generated_address_once() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = Arc::new(PetStorage::new(dir.path().join("db")).unwrap());
+
+    const GENERATORS: usize = 4;
+    const ADDRESSES: usize = 25;
+    let generators: Vec<_> = (0..GENERATORS)
+        .map(|_| {
+            let storage = Arc::clone(&storage);
+            std::thread::spawn(move || {
+                for _ in 0..ADDRESSES {
+                    storage
+                        .store_generated_address(POOL, PetAddress::from_keypair(&Keypair::new()))
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for generator in generators {
+        generator.join().unwrap();
+    }
+
+    // Imported or test addresses are stored but not generated
+    fill_pool(&storage, 3);
+    storage.get_next_address(POOL).unwrap().unwrap();
+
+    assert_eq!(storage.generated_count(POOL).unwrap(), (GENERATORS * ADDRESSES) as u64);
+    assert_eq!(storage.count_addresses(POOL).unwrap(), GENERATORS * ADDRESSES + 2);
+}